@@ -0,0 +1,46 @@
+-- Tables the later migrations build on. IF NOT EXISTS keeps databases created before
+-- migrations were tracked working.
+CREATE TABLE IF NOT EXISTS profiles (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    phonenumber TEXT NOT NULL,
+    address TEXT,
+    email TEXT NOT NULL,
+    bank_name TEXT NOT NULL,
+    bank_account_number TEXT NOT NULL,
+    bank_routing_number TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS clients (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    phone TEXT NOT NULL,
+    address TEXT,
+    email TEXT NOT NULL,
+    profile_id INTEGER NOT NULL REFERENCES profiles(id)
+);
+
+CREATE TABLE IF NOT EXISTS projects (
+    id SERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES clients(id),
+    name TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE
+);
+
+CREATE TABLE IF NOT EXISTS invoices (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    number INTEGER NOT NULL,
+    submit_date DATE NOT NULL,
+    due_date DATE NOT NULL,
+    rate NUMERIC(10, 2),
+    status TEXT NOT NULL DEFAULT 'Draft'
+);
+
+CREATE TABLE IF NOT EXISTS invoice_line_item (
+    id SERIAL PRIMARY KEY,
+    invoice_id INTEGER NOT NULL REFERENCES invoices(id),
+    description TEXT NOT NULL,
+    hours NUMERIC(10, 2) NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Optional per-invoice billing address shown instead of the client's address
+ALTER TABLE invoices ADD COLUMN bill_to_override TEXT;
@@ -0,0 +1,2 @@
+-- Whether invoices for the client include the bank account details
+ALTER TABLE clients ADD COLUMN show_bank_details BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,2 @@
+-- Private reminders about the invoice, never included in generated output
+ALTER TABLE invoices ADD COLUMN internal_notes TEXT;
@@ -0,0 +1,3 @@
+-- Flat-fee invoices bill each line item's amount instead of hours at the hourly rate
+ALTER TABLE invoices ADD COLUMN billing_type TEXT NOT NULL DEFAULT 'Hourly';
+ALTER TABLE invoice_line_item ADD COLUMN amount NUMERIC(10, 2) NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- Immutable copies of invoices as they were when emailed to the client
+CREATE TABLE invoice_snapshots (
+    id SERIAL PRIMARY KEY,
+    invoice_id INTEGER NOT NULL REFERENCES invoices(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    total NUMERIC(10, 2) NOT NULL,
+    snapshot JSONB NOT NULL
+);
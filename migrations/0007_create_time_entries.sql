@@ -0,0 +1,9 @@
+-- Tracked time for a project, linked to the invoice it was billed on
+CREATE TABLE time_entries (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES projects(id),
+    entry_date DATE NOT NULL,
+    description TEXT NOT NULL,
+    hours NUMERIC(10, 2) NOT NULL,
+    invoice_id INTEGER REFERENCES invoices(id)
+);
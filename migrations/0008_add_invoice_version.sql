@@ -0,0 +1,2 @@
+-- Incremented on every update so concurrent edits of the same invoice can be detected
+ALTER TABLE invoices ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
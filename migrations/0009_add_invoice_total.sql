@@ -0,0 +1,7 @@
+-- Cached invoice total, kept up to date whenever the invoice or its line items are saved
+ALTER TABLE invoices ADD COLUMN total NUMERIC(10, 2) NOT NULL DEFAULT 0;
+UPDATE invoices i SET total = COALESCE((
+    SELECT SUM(CASE WHEN i.billing_type = 'Flat' THEN li.amount ELSE ROUND(li.hours * i.rate, 2) END)
+    FROM invoice_line_item li
+    WHERE li.invoice_id = i.id
+), 0);
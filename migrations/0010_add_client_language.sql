@@ -0,0 +1,2 @@
+-- ISO 639-1 code of the language invoices for the client are printed in
+ALTER TABLE clients ADD COLUMN language TEXT NOT NULL DEFAULT 'en';
@@ -0,0 +1,3 @@
+-- When the invoice was last emailed and to whom, shown in the invoices list
+ALTER TABLE invoices ADD COLUMN last_emailed_at TIMESTAMP;
+ALTER TABLE invoices ADD COLUMN last_emailed_to TEXT;
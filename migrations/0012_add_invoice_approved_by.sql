@@ -0,0 +1,2 @@
+-- Who approved the invoice for sending, when an approver name is configured
+ALTER TABLE invoices ADD COLUMN approved_by TEXT;
@@ -0,0 +1,10 @@
+-- Keep line items in the order they were entered, independent of their ids
+ALTER TABLE invoice_line_item ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE invoice_line_item li
+SET position = ordered.position
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY invoice_id ORDER BY id) - 1 AS position
+    FROM invoice_line_item
+) ordered
+WHERE ordered.id = li.id;
@@ -0,0 +1,9 @@
+-- Payments received against an invoice, which may be paid in several parts
+CREATE TABLE payments (
+    id SERIAL PRIMARY KEY,
+    invoice_id INTEGER NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    amount NUMERIC(10, 2) NOT NULL,
+    paid_on DATE NOT NULL
+);
+
+CREATE INDEX payments_invoice_id_idx ON payments (invoice_id);
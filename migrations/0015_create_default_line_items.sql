@@ -0,0 +1,9 @@
+-- Line item descriptions new invoices of a profile start with
+CREATE TABLE default_line_items (
+    id SERIAL PRIMARY KEY,
+    profile_id INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+    description TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX default_line_items_profile_id_idx ON default_line_items (profile_id);
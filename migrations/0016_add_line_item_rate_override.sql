@@ -0,0 +1,2 @@
+-- Line items billed at their own hourly rate instead of the invoice's, NULL uses the invoice rate
+ALTER TABLE invoice_line_item ADD COLUMN rate_override NUMERIC(10, 2);
@@ -0,0 +1,3 @@
+-- Colors of generated invoices, defaulting to the colors they always used
+ALTER TABLE profiles ADD COLUMN header_color TEXT NOT NULL DEFAULT '#343876';
+ALTER TABLE profiles ADD COLUMN accent_color TEXT NOT NULL DEFAULT '#e83e8c';
@@ -0,0 +1,10 @@
+-- Every attempt to email an invoice, including the ones that failed
+CREATE TABLE invoice_email_log (
+    id SERIAL PRIMARY KEY,
+    invoice_id INTEGER NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    sent_at TIMESTAMP NOT NULL,
+    recipient TEXT NOT NULL,
+    success BOOLEAN NOT NULL
+);
+
+CREATE INDEX invoice_email_log_invoice_id_idx ON invoice_email_log (invoice_id);
@@ -0,0 +1,7 @@
+-- Date an invoice was marked paid, shown in the invoices list
+ALTER TABLE invoices ADD COLUMN paid_date DATE;
+
+-- Invoices already paid off count as paid on their last payment
+UPDATE invoices
+SET paid_date = (SELECT MAX(paid_on) FROM payments WHERE payments.invoice_id = invoices.id)
+WHERE status = 'Paid';
@@ -0,0 +1,2 @@
+-- ISO 4217 code of the currency a profile's invoices are in
+ALTER TABLE profiles ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD';
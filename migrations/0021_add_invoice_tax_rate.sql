@@ -0,0 +1,2 @@
+-- Tax or VAT percentage added on top of the line items, NULL for invoices without tax
+ALTER TABLE invoices ADD COLUMN tax_rate NUMERIC(5, 2);
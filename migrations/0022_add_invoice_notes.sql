@@ -0,0 +1,2 @@
+-- Notes for the client such as payment terms, printed beneath the totals of generated invoices
+ALTER TABLE invoices ADD COLUMN notes TEXT;
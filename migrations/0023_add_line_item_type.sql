@@ -0,0 +1,2 @@
+-- Fixed line items bill their amount as entered instead of hours at the rate, even on hourly invoices
+ALTER TABLE invoice_line_item ADD COLUMN item_type TEXT NOT NULL DEFAULT 'Hourly';
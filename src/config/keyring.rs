@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+
+// The keyring is the platform's credential store: the Secret Service on Linux, the login
+// keychain on macOS and the Credential Manager on Windows
+
+/// Service name the password is stored under, with the SMTP username as the account
+const SERVICE: &str = "invoice-manager";
+
+fn entry(account: &str) -> Result<::keyring::Entry> {
+    ::keyring::Entry::new(SERVICE, account).context("Could not open the keyring")
+}
+
+/// Look up the password stored for the account
+pub fn get_password(account: &str) -> Result<String> {
+    match entry(account)?.get_password() {
+        Ok(password) if !password.is_empty() => Ok(password),
+        Ok(_) | Err(::keyring::Error::NoEntry) => {
+            anyhow::bail!("No SMTP password for {} is stored in the keyring", account)
+        }
+        Err(e) => Err(e).context("Could not read the keyring"),
+    }
+}
+
+/// Store the password for the account, replacing any stored before
+pub fn set_password(account: &str, password: &str) -> Result<()> {
+    entry(account)?
+        .set_password(password)
+        .context("Storing the SMTP password in the keyring failed")
+}
@@ -1,13 +1,16 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use chrono::{NaiveDate, NaiveDateTime};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
-use crate::config::Config;
-use crate::models::{Profile, Client, Project, Invoice, InvoiceLineItem};
+use crate::config::{Config, NumberingScope};
+use crate::error::{AppError, Result};
+use crate::models::{Profile, Client, Project, Invoice, InvoiceLineItem, InvoiceExportRow, InvoiceSnapshot, InvoiceEmailLogEntry, OrphanReport, TimeEntry, DuplicateInvoiceNumber, ProfileTotals, ProjectSummary};
 
 /// Database connection pool
 pub struct Database {
     pool: PgPool,
+    numbering_scope: NumberingScope,
 }
 
 impl Database {
@@ -18,13 +21,24 @@ impl Database {
             .connect(config.database_url())
             .await?;
             
-        Ok(Self { pool })
+        Ok(Self { pool, numbering_scope: config.numbering_scope })
+    }
+    
+    /// Database over an existing pool, such as the scratch database of an sqlx test
+    #[cfg(test)]
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool, numbering_scope: NumberingScope::Profile }
     }
     
     /// Get a reference to the connection pool
     pub fn get_pool(&self) -> &PgPool {
         &self.pool
     }
+    
+    /// Wait for queries in flight to finish and close all connections
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
 
     // Profile operations
     pub async fn get_profiles(&self) -> Result<Vec<Profile>> {
@@ -53,8 +67,8 @@ impl Database {
     pub async fn create_profile(&self, profile: &Profile) -> Result<i32> {
         let id = sqlx::query_scalar!(
             r#"
-            INSERT INTO profiles (name, phonenumber, address, email, bank_name, bank_account_number, bank_routing_number)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO profiles (name, phonenumber, address, email, bank_name, bank_account_number, bank_routing_number, header_color, accent_color, currency)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             RETURNING id
             "#,
             profile.name,
@@ -63,7 +77,10 @@ impl Database {
             profile.email,
             profile.bank_name,
             profile.bank_account_number,
-            profile.bank_routing_number
+            profile.bank_routing_number,
+            profile.header_color,
+            profile.accent_color,
+            profile.currency
         )
         .fetch_one(self.get_pool())
         .await?;
@@ -71,13 +88,113 @@ impl Database {
         Ok(id)
     }
 
+    /// Line item descriptions new invoices of the profile start with, in order
+    pub async fn get_default_line_items(&self, profile_id: i32) -> Result<Vec<String>> {
+        let descriptions = sqlx::query_scalar!(
+            "SELECT description FROM default_line_items WHERE profile_id = $1 ORDER BY position ASC, id ASC",
+            profile_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(descriptions)
+    }
+
+    /// Default line items of the profile owning the project, for starting a new invoice
+    pub async fn get_default_line_items_by_project(&self, project_id: i32) -> Result<Vec<String>> {
+        let descriptions = sqlx::query_scalar!(
+            r#"
+            SELECT d.description
+            FROM default_line_items d
+            JOIN clients c ON c.profile_id = d.profile_id
+            JOIN projects p ON p.client_id = c.id
+            WHERE p.id = $1
+            ORDER BY d.position ASC, d.id ASC
+            "#,
+            project_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(descriptions)
+    }
+
+    /// Currency of a profile
+    pub async fn get_currency_by_profile(&self, profile_id: i32) -> Result<String> {
+        let currency = sqlx::query_scalar!("SELECT currency FROM profiles WHERE id = $1", profile_id)
+            .fetch_one(self.get_pool())
+            .await?;
+        
+        Ok(currency)
+    }
+
+    /// Currency of the profile a client belongs to
+    pub async fn get_currency_by_client(&self, client_id: i32) -> Result<String> {
+        let currency = sqlx::query_scalar!(
+            r#"
+            SELECT pr.currency
+            FROM clients c
+            JOIN profiles pr ON pr.id = c.profile_id
+            WHERE c.id = $1
+            "#,
+            client_id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(currency)
+    }
+
+    /// Currency of the profile a project belongs to
+    pub async fn get_currency_by_project(&self, project_id: i32) -> Result<String> {
+        let currency = sqlx::query_scalar!(
+            r#"
+            SELECT pr.currency
+            FROM projects p
+            JOIN clients c ON c.id = p.client_id
+            JOIN profiles pr ON pr.id = c.profile_id
+            WHERE p.id = $1
+            "#,
+            project_id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(currency)
+    }
+
+    /// Replace the profile's default line items
+    pub async fn set_default_line_items(&self, profile_id: i32, descriptions: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query!("DELETE FROM default_line_items WHERE profile_id = $1", profile_id)
+            .execute(&mut *tx)
+            .await?;
+        
+        for (position, description) in descriptions.iter().enumerate() {
+            sqlx::query!(
+                "INSERT INTO default_line_items (profile_id, description, position) VALUES ($1, $2, $3)",
+                profile_id,
+                description,
+                position as i32
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        
+        Ok(())
+    }
+
     pub async fn update_profile(&self, profile: &Profile) -> Result<()> {
         sqlx::query!(
             r#"
             UPDATE profiles
             SET name = $1, phonenumber = $2, address = $3, email = $4,
-                bank_name = $5, bank_account_number = $6, bank_routing_number = $7
-            WHERE id = $8
+                bank_name = $5, bank_account_number = $6, bank_routing_number = $7,
+                header_color = $8, accent_color = $9, currency = $10
+            WHERE id = $11
             "#,
             profile.name,
             profile.phonenumber,
@@ -86,6 +203,9 @@ impl Database {
             profile.bank_name,
             profile.bank_account_number,
             profile.bank_routing_number,
+            profile.header_color,
+            profile.accent_color,
+            profile.currency,
             profile.id
         )
         .execute(self.get_pool())
@@ -128,6 +248,22 @@ impl Database {
                 .execute(&mut *tx)
                 .await?;
                 
+                // Delete sent invoice snapshots
+                sqlx::query!(
+                    "DELETE FROM invoice_snapshots WHERE invoice_id IN (SELECT id FROM invoices WHERE project_id = $1)",
+                    project.id
+                )
+                .execute(&mut *tx)
+                .await?;
+                
+                // Delete time entries
+                sqlx::query!(
+                    "DELETE FROM time_entries WHERE project_id = $1",
+                    project.id
+                )
+                .execute(&mut *tx)
+                .await?;
+                
                 // Delete invoices
                 sqlx::query!(
                     "DELETE FROM invoices WHERE project_id = $1",
@@ -193,15 +329,17 @@ impl Database {
     pub async fn create_client(&self, client: &Client) -> Result<i32> {
         let id = sqlx::query_scalar!(
             r#"
-            INSERT INTO clients (name, phone, address, email, profile_id)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO clients (name, phone, address, email, profile_id, show_bank_details, language)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING id
             "#,
             client.name,
             client.phone,
             client.address,
             client.email,
-            client.profile_id
+            client.profile_id,
+            client.show_bank_details,
+            client.language
         )
         .fetch_one(self.get_pool())
         .await?;
@@ -209,17 +347,46 @@ impl Database {
         Ok(id)
     }
 
+    /// Create several clients in one transaction, so a failure leaves none of them behind
+    pub async fn create_clients(&self, clients: &[Client]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        for client in clients {
+            sqlx::query!(
+                r#"
+                INSERT INTO clients (name, phone, address, email, profile_id, show_bank_details, language)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                client.name,
+                client.phone,
+                client.address,
+                client.email,
+                client.profile_id,
+                client.show_bank_details,
+                client.language
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        
+        Ok(())
+    }
+
     pub async fn update_client(&self, client: &Client) -> Result<()> {
         sqlx::query!(
             r#"
             UPDATE clients
-            SET name = $1, phone = $2, address = $3, email = $4
-            WHERE id = $5
+            SET name = $1, phone = $2, address = $3, email = $4, show_bank_details = $5, language = $6
+            WHERE id = $7
             "#,
             client.name,
             client.phone,
             client.address,
             client.email,
+            client.show_bank_details,
+            client.language,
             client.id
         )
         .execute(self.get_pool())
@@ -251,6 +418,22 @@ impl Database {
             .execute(&mut *tx)
             .await?;
             
+            // Delete sent invoice snapshots
+            sqlx::query!(
+                "DELETE FROM invoice_snapshots WHERE invoice_id IN (SELECT id FROM invoices WHERE project_id = $1)",
+                project.id
+            )
+            .execute(&mut *tx)
+            .await?;
+            
+            // Delete time entries
+            sqlx::query!(
+                "DELETE FROM time_entries WHERE project_id = $1",
+                project.id
+            )
+            .execute(&mut *tx)
+            .await?;
+            
             // Delete invoices
             sqlx::query!(
                 "DELETE FROM invoices WHERE project_id = $1",
@@ -279,6 +462,270 @@ impl Database {
         Ok(())
     }
 
+    /// Move all projects from the source client to the target client and delete the source.
+    /// Returns the number of projects that were moved.
+    pub async fn merge_clients(&self, source_id: i32, target_id: i32) -> Result<u64> {
+        if source_id == target_id {
+            return Err(AppError::Validation("Cannot merge a client into itself".to_string()));
+        }
+        
+        // Start a transaction
+        let mut tx = self.pool.begin().await?;
+        
+        let source = sqlx::query_as!(
+            Client,
+            "SELECT * FROM clients WHERE id = $1",
+            source_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        let target = sqlx::query_as!(
+            Client,
+            "SELECT * FROM clients WHERE id = $1",
+            target_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        if source.profile_id != target.profile_id {
+            return Err(AppError::Validation("Clients must belong to the same profile to be merged".to_string()));
+        }
+        
+        // Repoint the source's projects to the target
+        let moved = sqlx::query!(
+            "UPDATE projects SET client_id = $1 WHERE client_id = $2",
+            target_id,
+            source_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        // Delete the now empty source client
+        sqlx::query!("DELETE FROM clients WHERE id = $1", source_id)
+            .execute(&mut *tx)
+            .await?;
+        
+        // Commit the transaction
+        tx.commit().await?;
+        
+        Ok(moved)
+    }
+
+    // Maintenance operations
+    
+    /// Find rows whose parent record is missing, e.g. line items left behind by an interrupted delete
+    pub async fn find_orphans(&self) -> Result<OrphanReport> {
+        let client_ids = sqlx::query!(
+            "SELECT c.id FROM clients c LEFT JOIN profiles p ON p.id = c.profile_id WHERE p.id IS NULL ORDER BY c.id"
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+        
+        let project_ids = sqlx::query!(
+            "SELECT pr.id FROM projects pr LEFT JOIN clients c ON c.id = pr.client_id WHERE c.id IS NULL ORDER BY pr.id"
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+        
+        let invoice_ids = sqlx::query!(
+            "SELECT i.id FROM invoices i LEFT JOIN projects pr ON pr.id = i.project_id WHERE pr.id IS NULL ORDER BY i.id"
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+        
+        let line_item_ids = sqlx::query!(
+            "SELECT li.id FROM invoice_line_item li LEFT JOIN invoices i ON i.id = li.invoice_id WHERE i.id IS NULL ORDER BY li.id"
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+        
+        Ok(OrphanReport {
+            client_ids,
+            project_ids,
+            invoice_ids,
+            line_item_ids,
+        })
+    }
+    
+    /// Delete all orphaned rows in a single transaction. Returns the number of rows deleted.
+    pub async fn delete_orphans(&self) -> Result<u64> {
+        // Start a transaction
+        let mut tx = self.pool.begin().await?;
+        
+        // Delete from the top down so children of removed orphans are caught by the next step
+        let mut deleted = sqlx::query!(
+            "DELETE FROM clients WHERE profile_id NOT IN (SELECT id FROM profiles)"
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        deleted += sqlx::query!(
+            "DELETE FROM projects WHERE client_id NOT IN (SELECT id FROM clients)"
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        deleted += sqlx::query!(
+            "DELETE FROM invoices WHERE project_id NOT IN (SELECT id FROM projects)"
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        deleted += sqlx::query!(
+            "DELETE FROM invoice_line_item WHERE invoice_id NOT IN (SELECT id FROM invoices)"
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        // Commit the transaction
+        tx.commit().await?;
+        
+        Ok(deleted)
+    }
+
+    /// Invoice numbers used by more than one of the profile's invoices, or by more than one
+    /// invoice of the same project when numbering is per project
+    pub async fn find_duplicate_invoice_numbers(&self, profile_id: i32) -> Result<Vec<DuplicateInvoiceNumber>> {
+        let duplicates = sqlx::query_as!(
+            DuplicateInvoiceNumber,
+            r#"
+            SELECT i.number, COUNT(*) as "count!"
+            FROM invoices i
+            JOIN projects p ON p.id = i.project_id
+            JOIN clients c ON c.id = p.client_id
+            WHERE c.profile_id = $1
+            GROUP BY i.number, CASE WHEN $2 THEN i.project_id END
+            HAVING COUNT(*) > 1
+            ORDER BY i.number ASC
+            "#,
+            profile_id,
+            self.numbering_scope == NumberingScope::Project
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(duplicates)
+    }
+    
+    /// Invoice count and billed total of each of the client's projects, including projects
+    /// without invoices
+    pub async fn get_project_summaries(&self, client_id: i32) -> Result<Vec<ProjectSummary>> {
+        let summaries = sqlx::query_as!(
+            ProjectSummary,
+            r#"
+            SELECT
+                p.id as project_id,
+                COUNT(i.id) as "invoice_count!: i64",
+                COALESCE(SUM(i.total) FILTER (WHERE i.status <> 'Cancelled'), 0)::float8 as "billed!: f64"
+            FROM projects p
+            LEFT JOIN invoices i ON i.project_id = p.id
+            WHERE p.client_id = $1
+            GROUP BY p.id
+            "#,
+            client_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(summaries)
+    }
+    
+    /// Billed, paid and outstanding totals of every profile, for invoices submitted within
+    /// the optional date range. Partial payments count as paid. Cancelled invoices are left out.
+    pub async fn get_all_profiles_totals(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Result<Vec<ProfileTotals>> {
+        let totals = sqlx::query_as!(
+            ProfileTotals,
+            r#"
+            WITH invoice_totals AS (
+                SELECT
+                    c.profile_id,
+                    i.total,
+                    CASE WHEN i.status = 'Paid' THEN i.total ELSE COALESCE(pm.paid, 0) END as paid
+                FROM invoices i
+                JOIN projects p ON p.id = i.project_id
+                JOIN clients c ON c.id = p.client_id
+                LEFT JOIN (
+                    SELECT invoice_id, SUM(amount) as paid
+                    FROM payments
+                    GROUP BY invoice_id
+                ) pm ON pm.invoice_id = i.id
+                WHERE i.status <> 'Cancelled'
+                  AND ($1::date IS NULL OR i.submit_date >= $1::date)
+                  AND ($2::date IS NULL OR i.submit_date <= $2::date)
+            )
+            SELECT
+                pr.name as profile_name,
+                pr.currency,
+                COALESCE(SUM(t.total), 0)::float8 as "billed!: f64",
+                COALESCE(SUM(t.paid), 0)::float8 as "paid!: f64",
+                COALESCE(SUM(t.total - t.paid), 0)::float8 as "outstanding!: f64"
+            FROM profiles pr
+            LEFT JOIN invoice_totals t ON t.profile_id = pr.id
+            GROUP BY pr.id, pr.name, pr.currency
+            ORDER BY pr.name ASC
+            "#,
+            from,
+            to
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(totals)
+    }
+    
+    /// Renumber all of the profile's invoices 1, 2, 3... in submit date order, restarting
+    /// for each project when numbering is per project. Returns the number of invoices renumbered.
+    pub async fn renumber_invoices(&self, profile_id: i32) -> Result<u64> {
+        // Start a transaction
+        let mut tx = self.pool.begin().await?;
+        
+        let renumbered = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET number = ordered.new_number, version = version + 1
+            FROM (
+                SELECT i.id, ROW_NUMBER() OVER (
+                    PARTITION BY CASE WHEN $2 THEN i.project_id END
+                    ORDER BY i.submit_date ASC, i.id ASC
+                )::int4 as new_number
+                FROM invoices i
+                JOIN projects p ON p.id = i.project_id
+                JOIN clients c ON c.id = p.client_id
+                WHERE c.profile_id = $1
+            ) ordered
+            WHERE invoices.id = ordered.id
+            "#,
+            profile_id,
+            self.numbering_scope == NumberingScope::Project
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        
+        // Commit the transaction
+        tx.commit().await?;
+        
+        Ok(renumbered)
+    }
+
     // Project operations
     pub async fn get_projects_by_client(&self, client_id: i32) -> Result<Vec<Project>> {
         let projects = sqlx::query_as!(
@@ -379,7 +826,16 @@ impl Database {
                 submit_date::date as submit_date,
                 due_date::date as due_date,
                 COALESCE(rate::float8, 0.0) as "rate!: f64",
-                status
+                status,
+                bill_to_override,
+                internal_notes,
+                billing_type,
+                version,
+                last_emailed_at,
+                last_emailed_to,
+                paid_date,
+                tax_rate::float8 as "tax_rate: f64",
+                notes
             FROM invoices 
             WHERE project_id = $1 
             ORDER BY submit_date DESC
@@ -403,7 +859,16 @@ impl Database {
                 submit_date::date as submit_date,
                 due_date::date as due_date,
                 COALESCE(rate::float8, 0.0) as "rate!: f64",
-                status
+                status,
+                bill_to_override,
+                internal_notes,
+                billing_type,
+                version,
+                last_emailed_at,
+                last_emailed_to,
+                paid_date,
+                tax_rate::float8 as "tax_rate: f64",
+                notes
             FROM invoices 
             WHERE id = $1
             "#,
@@ -415,19 +880,31 @@ impl Database {
         Ok(invoice)
     }
 
+    /// Number the next invoice of the project gets, following the configured numbering scope
+    pub async fn next_invoice_number(&self, project_id: i32) -> Result<i32> {
+        next_invoice_number(self.get_pool(), project_id, self.numbering_scope).await
+    }
+
+    /// Insert a new invoice, numbered after the last one in its numbering scope
     pub async fn create_invoice(&self, invoice: &Invoice) -> Result<i32> {
+        let number = self.next_invoice_number(invoice.project_id).await?;
         let id = sqlx::query_scalar!(
             r#"
-            INSERT INTO invoices (project_id, number, submit_date, due_date, rate, status)
-            VALUES ($1, $2, $3::date, $4::date, $5::float8, $6)
+            INSERT INTO invoices (project_id, number, submit_date, due_date, rate, status, bill_to_override, internal_notes, billing_type, tax_rate, notes)
+            VALUES ($1, $2, $3::date, $4::date, $5::float8, $6, $7, $8, $9, $10::float8, $11)
             RETURNING id
             "#,
             invoice.project_id,
-            invoice.number,
+            number,
             invoice.submit_date as _,
             invoice.due_date as _,
             invoice.rate as f64,
-            invoice.status
+            invoice.status,
+            invoice.bill_to_override,
+            invoice.internal_notes,
+            invoice.billing_type,
+            invoice.tax_rate,
+            invoice.notes
         )
         .fetch_one(self.get_pool())
         .await?;
@@ -436,80 +913,497 @@ impl Database {
     }
 
     pub async fn update_invoice(&self, invoice: &Invoice) -> Result<()> {
-        sqlx::query!(
+        let updated = sqlx::query!(
             r#"
             UPDATE invoices
-            SET submit_date = $1::date, due_date = $2::date, rate = $3::float8, status = $4
-            WHERE id = $5
+            SET submit_date = $1::date, due_date = $2::date, rate = $3::float8, status = $4, bill_to_override = $5, internal_notes = $6, billing_type = $7, tax_rate = $10::float8, notes = $11,
+                version = version + 1
+            WHERE id = $8 AND version = $9
             "#,
             invoice.submit_date as _,
             invoice.due_date as _,
             invoice.rate as f64,
             invoice.status,
-            invoice.id
+            invoice.bill_to_override,
+            invoice.internal_notes,
+            invoice.billing_type,
+            invoice.id,
+            invoice.version,
+            invoice.tax_rate,
+            invoice.notes
         )
         .execute(self.get_pool())
         .await?;
         
-        Ok(())
-    }
-
-    pub async fn delete_invoice(&self, id: i32) -> Result<()> {
-        sqlx::query!("DELETE FROM invoices WHERE id = $1", id)
-            .execute(self.get_pool())
-            .await?;
+        // Nothing matched the version the caller loaded, someone else saved in between
+        if updated.rows_affected() == 0 {
+            return Err(AppError::InvoiceModified);
+        }
+        
+        // The rate or tax may have changed
+        refresh_invoice_total(self.get_pool(), invoice.id).await?;
         
         Ok(())
     }
 
-    // Line item operations
-    pub async fn get_line_items_by_invoice(&self, invoice_id: i32) -> Result<Vec<InvoiceLineItem>> {
-        let line_items = sqlx::query_as!(
-            InvoiceLineItem,
+    /// Recompute the cached total of every invoice from its line items and tax, repairing any
+    /// that are out of date. Returns the number of invoices corrected.
+    pub async fn recompute_all_totals(&self) -> Result<u64> {
+        let result = sqlx::query!(
             r#"
-            SELECT 
-                id,
-                invoice_id,
-                description,
-                hours::float8 as "hours!: f64"
-            FROM invoice_line_item 
-            WHERE invoice_id = $1 
-            ORDER BY id ASC
-            "#,
-            invoice_id
+            UPDATE invoices i
+            SET total = t.total, version = version + 1
+            FROM (
+                SELECT s.id, s.subtotal + ROUND(s.subtotal * COALESCE(s.tax_rate, 0) / 100, 2) as total
+                FROM (
+                    SELECT i.id, i.tax_rate, COALESCE(SUM(CASE WHEN i.billing_type = 'Flat' OR li.item_type = 'Fixed' THEN li.amount ELSE ROUND(li.hours * COALESCE(li.rate_override, i.rate), 2) END), 0) as subtotal
+                    FROM invoices i
+                    LEFT JOIN invoice_line_item li ON li.invoice_id = i.id
+                    GROUP BY i.id
+                ) s
+            ) t
+            WHERE t.id = i.id AND i.total IS DISTINCT FROM t.total
+            "#
         )
-        .fetch_all(self.get_pool())
+        .execute(self.get_pool())
         .await?;
         
-        Ok(line_items)
+        Ok(result.rows_affected())
     }
-
-    pub async fn create_line_item(&self, line_item: &InvoiceLineItem) -> Result<i32> {
-        let id = sqlx::query_scalar!(
-            r#"
-            INSERT INTO invoice_line_item (invoice_id, description, hours)
-            VALUES ($1, $2, $3::float8)
-            RETURNING id
-            "#,
-            line_item.invoice_id,
-            line_item.description,
-            line_item.hours as f64
+    
+    /// Approve a draft invoice for sending, recording who approved it when known
+    pub async fn approve_invoice(&self, id: i32, approver: Option<&str>) -> Result<()> {
+        sqlx::query!(
+            "UPDATE invoices SET status = 'Approved', approved_by = $1, version = version + 1 WHERE id = $2 AND status = 'Draft'",
+            approver,
+            id
         )
-        .fetch_one(self.get_pool())
+        .execute(self.get_pool())
         .await?;
         
-        Ok(id)
+        Ok(())
     }
-
-    pub async fn update_line_item(&self, line_item: &InvoiceLineItem) -> Result<()> {
+    
+    /// Remember when and to whom the invoice was last emailed
+    pub async fn record_invoice_emailed(&self, id: i32, recipient: &str, emailed_at: NaiveDateTime) -> Result<()> {
         sqlx::query!(
-            r#"
-            UPDATE invoice_line_item
-            SET description = $1, hours = $2::float8
-            WHERE id = $3
-            "#,
+            "UPDATE invoices SET last_emailed_at = $1, last_emailed_to = $2, version = version + 1 WHERE id = $3",
+            emailed_at,
+            recipient,
+            id
+        )
+        .execute(self.get_pool())
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Mark an invoice paid as of the given date
+    pub async fn mark_invoice_paid(&self, id: i32, date: NaiveDate) -> Result<()> {
+        sqlx::query!(
+            "UPDATE invoices SET status = 'Paid', paid_date = $1, version = version + 1 WHERE id = $2",
+            date,
+            id
+        )
+        .execute(self.get_pool())
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Undo marking an invoice paid, putting it back to sent or approved depending on whether
+    /// it was emailed. Returns the new status.
+    pub async fn mark_invoice_unpaid(&self, id: i32) -> Result<String> {
+        let status = sqlx::query_scalar!(
+            r#"
+            UPDATE invoices
+            SET status = CASE WHEN last_emailed_at IS NULL THEN 'Approved' ELSE 'Sent' END,
+                paid_date = NULL,
+                version = version + 1
+            WHERE id = $1
+            RETURNING status
+            "#,
+            id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(status)
+    }
+    
+    pub async fn set_invoice_status(&self, id: i32, status: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE invoices SET status = $1, version = version + 1 WHERE id = $2",
+            status,
+            id
+        )
+        .execute(self.get_pool())
+        .await?;
+        
+        Ok(())
+    }
+
+    /// Delete an invoice along with its line items and sent snapshots
+    pub async fn delete_invoice(&self, id: i32) -> Result<()> {
+        // Start a transaction
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query!("DELETE FROM invoice_snapshots WHERE invoice_id = $1", id)
+            .execute(&mut *tx)
+            .await?;
+        
+        sqlx::query!("DELETE FROM invoice_line_item WHERE invoice_id = $1", id)
+            .execute(&mut *tx)
+            .await?;
+        
+        // Time billed on the invoice becomes unbilled again
+        sqlx::query!("UPDATE time_entries SET invoice_id = NULL WHERE invoice_id = $1", id)
+            .execute(&mut *tx)
+            .await?;
+        
+        sqlx::query!("DELETE FROM invoices WHERE id = $1", id)
+            .execute(&mut *tx)
+            .await?;
+        
+        // Commit the transaction
+        tx.commit().await?;
+        
+        Ok(())
+    }
+
+    /// Total amount of each invoice of a project, keyed by invoice id
+    pub async fn get_invoice_totals(&self, project_id: i32) -> Result<HashMap<i32, f64>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT 
+                i.id,
+                i.total::float8 as "total!: f64"
+            FROM invoices i
+            WHERE i.project_id = $1
+            "#,
+            project_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(rows.into_iter().map(|row| (row.id, row.total)).collect())
+    }
+
+    /// Amount paid so far on each invoice of a project that has payments, keyed by invoice id
+    pub async fn get_invoice_paid_amounts(&self, project_id: i32) -> Result<HashMap<i32, f64>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT 
+                pm.invoice_id,
+                SUM(pm.amount)::float8 as "paid!: f64"
+            FROM payments pm
+            JOIN invoices i ON i.id = pm.invoice_id
+            WHERE i.project_id = $1
+            GROUP BY pm.invoice_id
+            "#,
+            project_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(rows.into_iter().map(|row| (row.invoice_id, row.paid)).collect())
+    }
+
+    /// Amount paid so far on one invoice
+    pub async fn get_invoice_paid_amount(&self, invoice_id: i32) -> Result<f64> {
+        let paid = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(amount), 0)::float8 as "paid!: f64" FROM payments WHERE invoice_id = $1"#,
+            invoice_id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(paid)
+    }
+
+    /// Record a full or partial payment received for an invoice
+    pub async fn record_payment(&self, invoice_id: i32, amount: f64, paid_on: NaiveDate) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO payments (invoice_id, amount, paid_on) VALUES ($1, $2::float8, $3)",
+            invoice_id,
+            amount,
+            paid_on
+        )
+        .execute(self.get_pool())
+        .await?;
+        
+        Ok(())
+    }
+
+    /// The client's sent or approved invoices that are still unpaid, oldest first. Drafts
+    /// haven't been approved, so they never go on a statement.
+    pub async fn get_unpaid_invoices_by_client(&self, client_id: i32) -> Result<Vec<Invoice>> {
+        let invoices = sqlx::query_as!(
+            Invoice,
+            r#"
+            SELECT 
+                i.id,
+                i.project_id,
+                i.number,
+                i.submit_date::date as submit_date,
+                i.due_date::date as due_date,
+                COALESCE(i.rate::float8, 0.0) as "rate!: f64",
+                i.status,
+                i.bill_to_override,
+                i.internal_notes,
+                i.billing_type,
+                i.version,
+                i.last_emailed_at,
+                i.last_emailed_to,
+                i.paid_date,
+                i.tax_rate::float8 as "tax_rate: f64",
+                i.notes
+            FROM invoices i
+            JOIN projects p ON p.id = i.project_id
+            WHERE p.client_id = $1
+              AND i.status NOT IN ('Paid', 'Cancelled', 'Draft')
+            ORDER BY i.submit_date ASC, i.number ASC
+            "#,
+            client_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(invoices)
+    }
+
+    /// Unpaid balance of each of the profile's clients, less any partial payments, keyed by
+    /// client id. Drafts don't count until they're approved. Clients with nothing outstanding
+    /// are left out.
+    pub async fn get_client_outstanding_totals(&self, profile_id: i32) -> Result<HashMap<i32, f64>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                p.client_id,
+                SUM(i.total - COALESCE((SELECT SUM(amount) FROM payments WHERE invoice_id = i.id), 0))::float8 as "outstanding!: f64"
+            FROM invoices i
+            JOIN projects p ON p.id = i.project_id
+            JOIN clients c ON c.id = p.client_id
+            WHERE c.profile_id = $1
+              AND i.status NOT IN ('Paid', 'Cancelled', 'Draft')
+            GROUP BY p.client_id
+            "#,
+            profile_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(rows.into_iter().map(|row| (row.client_id, row.outstanding)).collect())
+    }
+
+    /// Total of the client's unpaid invoices, excluding the given invoice
+    pub async fn get_client_outstanding_balance(&self, client_id: i32, exclude_invoice_id: i32) -> Result<f64> {
+        let balance = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(i.total - COALESCE((SELECT SUM(amount) FROM payments WHERE invoice_id = i.id), 0)), 0)::float8 as "balance!: f64"
+            FROM invoices i
+            JOIN projects p ON p.id = i.project_id
+            WHERE p.client_id = $1
+              AND i.id <> $2
+              AND i.status NOT IN ('Paid', 'Cancelled', 'Draft')
+            "#,
+            client_id,
+            exclude_invoice_id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(balance)
+    }
+
+    /// Invoices across all of a profile's clients with their client, project and total
+    pub async fn get_invoice_export_rows(&self, profile_id: i32) -> Result<Vec<InvoiceExportRow>> {
+        let rows = sqlx::query_as!(
+            InvoiceExportRow,
+            r#"
+            SELECT 
+                i.number,
+                c.name as client_name,
+                p.name as project_name,
+                i.submit_date,
+                i.due_date,
+                i.status,
+                i.total::float8 as "total!: f64"
+            FROM invoices i
+            JOIN projects p ON p.id = i.project_id
+            JOIN clients c ON c.id = p.client_id
+            WHERE c.profile_id = $1
+            ORDER BY i.submit_date ASC, i.number ASC
+            "#,
+            profile_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(rows)
+    }
+
+    /// Store an immutable copy of the invoice exactly as it was sent. Returns the snapshot id.
+    pub async fn save_invoice_snapshot(&self, invoice: &Invoice, line_items: &[InvoiceLineItem]) -> Result<i32> {
+        // Exact decimal total, converted at the column boundary
+        let total = crate::money::to_f64(crate::money::invoice_total(invoice, line_items));
+        let snapshot = serde_json::json!({
+            "invoice": invoice,
+            "line_items": line_items,
+            "total": total,
+        })
+        .to_string();
+        
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO invoice_snapshots (invoice_id, total, snapshot)
+            VALUES ($1, $2::float8, $3::jsonb)
+            RETURNING id
+            "#,
+            invoice.id,
+            total,
+            snapshot as String
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(id)
+    }
+
+    /// Snapshots of an invoice, most recent first
+    pub async fn get_invoice_snapshots(&self, invoice_id: i32) -> Result<Vec<InvoiceSnapshot>> {
+        let snapshots = sqlx::query_as!(
+            InvoiceSnapshot,
+            r#"
+            SELECT 
+                created_at,
+                total::float8 as "total!: f64",
+                snapshot::text as "snapshot!"
+            FROM invoice_snapshots 
+            WHERE invoice_id = $1 
+            ORDER BY created_at DESC, id DESC
+            "#,
+            invoice_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(snapshots)
+    }
+
+    /// Record an attempt to email an invoice, whether or not it went through
+    pub async fn log_invoice_email(&self, invoice_id: i32, recipient: &str, sent_at: NaiveDateTime, success: bool) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO invoice_email_log (invoice_id, sent_at, recipient, success) VALUES ($1, $2, $3, $4)",
+            invoice_id,
+            sent_at,
+            recipient,
+            success
+        )
+        .execute(self.get_pool())
+        .await?;
+        
+        Ok(())
+    }
+
+    /// Email attempts of an invoice, most recent first
+    pub async fn get_invoice_email_log(&self, invoice_id: i32) -> Result<Vec<InvoiceEmailLogEntry>> {
+        let entries = sqlx::query_as!(
+            InvoiceEmailLogEntry,
+            r#"
+            SELECT sent_at, recipient, success
+            FROM invoice_email_log
+            WHERE invoice_id = $1
+            ORDER BY sent_at DESC, id DESC
+            "#,
+            invoice_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(entries)
+    }
+
+    // Time entry operations
+    pub async fn get_unbilled_time_entries(&self, project_id: i32) -> Result<Vec<TimeEntry>> {
+        let entries = sqlx::query_as!(
+            TimeEntry,
+            r#"
+            SELECT 
+                id,
+                entry_date,
+                description,
+                hours::float8 as "hours!: f64"
+            FROM time_entries 
+            WHERE project_id = $1 AND invoice_id IS NULL
+            ORDER BY entry_date ASC, id ASC
+            "#,
+            project_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(entries)
+    }
+
+    // Line item operations
+    pub async fn get_line_items_by_invoice(&self, invoice_id: i32) -> Result<Vec<InvoiceLineItem>> {
+        let line_items = sqlx::query_as!(
+            InvoiceLineItem,
+            r#"
+            SELECT 
+                id,
+                invoice_id,
+                description,
+                hours::float8 as "hours!: f64",
+                amount::float8 as "amount!: f64",
+                rate_override::float8 as "rate_override: f64",
+                item_type
+            FROM invoice_line_item 
+            WHERE invoice_id = $1 
+            ORDER BY position ASC, id ASC
+            "#,
+            invoice_id
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        Ok(line_items)
+    }
+
+    pub async fn create_line_item(&self, line_item: &InvoiceLineItem) -> Result<i32> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO invoice_line_item (invoice_id, description, hours, amount, rate_override, item_type, position)
+            VALUES ($1, $2, $3::float8, $4::float8, $5::float8, $6,
+                (SELECT COALESCE(MAX(position) + 1, 0) FROM invoice_line_item WHERE invoice_id = $1))
+            RETURNING id
+            "#,
+            line_item.invoice_id,
+            line_item.description,
+            line_item.hours as f64,
+            line_item.amount as f64,
+            line_item.rate_override,
+            line_item.item_type
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok(id)
+    }
+
+    pub async fn update_line_item(&self, line_item: &InvoiceLineItem) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE invoice_line_item
+            SET description = $1, hours = $2::float8, amount = $3::float8, rate_override = $4::float8, item_type = $5
+            WHERE id = $6
+            "#,
             line_item.description,
             line_item.hours as f64,
+            line_item.amount as f64,
+            line_item.rate_override,
+            line_item.item_type,
             line_item.id
         )
         .execute(self.get_pool())
@@ -535,29 +1429,64 @@ impl Database {
     }
 
     // Transaction methods
+    // Save an invoice that is still being built, line items may be empty
+    pub async fn save_draft_invoice(&self, invoice: &Invoice, line_items: &[InvoiceLineItem], time_entry_ids: &[i32]) -> Result<i32> {
+        let draft = Invoice {
+            status: "Draft".to_string(),
+            ..invoice.clone()
+        };
+        self.save_invoice_billing_time_entries(&draft, line_items, time_entry_ids).await
+    }
+    
     pub async fn save_invoice_with_line_items(
         &self,
         invoice: &Invoice, 
         line_items: &[InvoiceLineItem]
+    ) -> Result<i32> {
+        self.save_invoice_billing_time_entries(invoice, line_items, &[]).await
+    }
+    
+    /// Save the invoice and link the time entries it bills to it, all or nothing, so the
+    /// entries only count as billed once their invoice exists
+    pub async fn save_invoice_billing_time_entries(
+        &self,
+        invoice: &Invoice,
+        line_items: &[InvoiceLineItem],
+        time_entry_ids: &[i32],
     ) -> Result<i32> {
         // Begin a transaction
         let mut tx = self.pool.begin().await?;
         
+        // A number entered in the wizard has to be free within its numbering scope
+        if invoice.number > 0 && invoice_number_taken(&mut *tx, invoice, self.numbering_scope).await? {
+            return Err(AppError::Validation(format!("Invoice number {} is already used by another invoice.", invoice.number)));
+        }
+        
         // Create or update the invoice
         let invoice_id = if invoice.id == 0 {
-            // New invoice
+            // New invoice, numbered inside the transaction unless a number was entered
+            let number = if invoice.number > 0 {
+                invoice.number
+            } else {
+                next_invoice_number(&mut *tx, invoice.project_id, self.numbering_scope).await?
+            };
             let id = sqlx::query_scalar!(
                 r#"
-                INSERT INTO invoices (project_id, number, submit_date, due_date, rate, status)
-                VALUES ($1, $2, $3::date, $4::date, $5::float8, $6)
+                INSERT INTO invoices (project_id, number, submit_date, due_date, rate, status, bill_to_override, internal_notes, billing_type, tax_rate, notes)
+                VALUES ($1, $2, $3::date, $4::date, $5::float8, $6, $7, $8, $9, $10::float8, $11)
                 RETURNING id
                 "#,
                 invoice.project_id,
-                invoice.number,
+                number,
                 invoice.submit_date,
                 invoice.due_date,
                 invoice.rate as f64,
-                invoice.status
+                invoice.status,
+                invoice.bill_to_override,
+                invoice.internal_notes,
+                invoice.billing_type,
+                invoice.tax_rate,
+                invoice.notes
             )
             .fetch_one(&mut *tx)
             .await?;
@@ -565,44 +1494,45 @@ impl Database {
             id
         } else {
             // Update existing invoice
-            sqlx::query!(
+            let updated = sqlx::query!(
                 r#"
                 UPDATE invoices
-                SET submit_date = $1::date, due_date = $2::date, rate = $3::float8, status = $4
-                WHERE id = $5
+                SET submit_date = $1::date, due_date = $2::date, rate = $3::float8, status = $4, bill_to_override = $5, internal_notes = $6, billing_type = $7, tax_rate = $10::float8, notes = $12,
+                    number = COALESCE(NULLIF($11::int4, 0), number),
+                    version = version + 1
+                WHERE id = $8 AND version = $9
                 "#,
                 invoice.submit_date,
                 invoice.due_date,
                 invoice.rate as f64,
                 invoice.status,
-                invoice.id
+                invoice.bill_to_override,
+                invoice.internal_notes,
+                invoice.billing_type,
+                invoice.id,
+                invoice.version,
+                invoice.tax_rate,
+                invoice.number,
+                invoice.notes
             )
             .execute(&mut *tx)
             .await?;
             
+            // Dropping the transaction rolls back, leaving the other session's changes in place
+            if updated.rows_affected() == 0 {
+                return Err(AppError::InvoiceModified);
+            }
+            
             invoice.id
         };
         
-        // Delete existing line items if updating
-        if invoice.id > 0 {
-            sqlx::query!("DELETE FROM invoice_line_item WHERE invoice_id = $1", invoice_id)
-                .execute(&mut *tx)
-                .await?;
-        }
+        // Only write the line items that were added, changed or removed
+        sync_line_items(&mut tx, invoice_id, line_items).await?;
         
-        // Insert all line items
-        for line_item in line_items {
-            sqlx::query!(
-                r#"
-                INSERT INTO invoice_line_item (invoice_id, description, hours)
-                VALUES ($1, $2, $3::float8)
-                "#,
-                invoice_id,
-                line_item.description,
-                line_item.hours as f64
-            )
-            .execute(&mut *tx)
-            .await?;
+        refresh_invoice_total(&mut *tx, invoice_id).await?;
+        
+        if !time_entry_ids.is_empty() {
+            bill_time_entries(&mut *tx, time_entry_ids, invoice_id).await?;
         }
         
         // Commit the transaction
@@ -617,6 +1547,87 @@ impl Database {
         Ok((invoice, line_items))
     }
 
+    /// One page of a project's invoices, newest first, along with how many invoices the
+    /// project has in total
+    pub async fn load_invoices_by_project_paged(&self, project_id: i32, limit: i64, offset: i64) -> Result<(Vec<Invoice>, i64)> {
+        let invoices = sqlx::query_as!(
+            Invoice,
+            r#"
+            SELECT 
+                id,
+                project_id,
+                number,
+                submit_date::date as submit_date,
+                due_date::date as due_date,
+                COALESCE(rate::float8, 0.0) as "rate!: f64",
+                status,
+                bill_to_override,
+                internal_notes,
+                billing_type,
+                version,
+                last_emailed_at,
+                last_emailed_to,
+                paid_date,
+                tax_rate::float8 as "tax_rate: f64",
+                notes
+            FROM invoices 
+            WHERE project_id = $1 
+            ORDER BY submit_date DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            project_id,
+            limit,
+            offset
+        )
+        .fetch_all(self.get_pool())
+        .await?;
+        
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM invoices WHERE project_id = $1"#,
+            project_id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok((invoices, count))
+    }
+    
+    /// Id of the project's most recently submitted invoice
+    pub async fn get_latest_invoice_id(&self, project_id: i32) -> Result<Option<i32>> {
+        let id = sqlx::query_scalar!(
+            "SELECT id FROM invoices WHERE project_id = $1 ORDER BY submit_date DESC, id DESC LIMIT 1",
+            project_id
+        )
+        .fetch_optional(self.get_pool())
+        .await?;
+        
+        Ok(id)
+    }
+    
+    /// Billed and outstanding totals of all of a project's invoices. Cancelled invoices
+    /// aren't billed and paid ones aren't outstanding.
+    pub async fn get_project_invoice_totals(&self, project_id: i32) -> Result<(f64, f64)> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                COALESCE(SUM(i.total) FILTER (WHERE i.status <> 'Cancelled'), 0)::float8 as "billed!: f64",
+                COALESCE(SUM(i.total - COALESCE(pm.paid, 0)) FILTER (WHERE i.status NOT IN ('Cancelled', 'Paid')), 0)::float8 as "outstanding!: f64"
+            FROM invoices i
+            LEFT JOIN (
+                SELECT invoice_id, SUM(amount) as paid
+                FROM payments
+                GROUP BY invoice_id
+            ) pm ON pm.invoice_id = i.id
+            WHERE i.project_id = $1
+            "#,
+            project_id
+        )
+        .fetch_one(self.get_pool())
+        .await?;
+        
+        Ok((row.billed, row.outstanding))
+    }
+
     // Additional invoice operations used by UI layer
     pub async fn load_invoices_by_project(&self, project_id: i32) -> Result<Vec<Invoice>> {
         // This is similar to get_invoices_by_project but with explicit type handling
@@ -630,7 +1641,16 @@ impl Database {
                 submit_date::date as submit_date,
                 due_date::date as due_date,
                 COALESCE(rate::float8, 0.0) as "rate!: f64",
-                status
+                status,
+                bill_to_override,
+                internal_notes,
+                billing_type,
+                version,
+                last_emailed_at,
+                last_emailed_to,
+                paid_date,
+                tax_rate::float8 as "tax_rate: f64",
+                notes
             FROM invoices 
             WHERE project_id = $1 
             ORDER BY submit_date DESC
@@ -682,12 +1702,758 @@ impl Database {
     }
 }
 
+// A value as it's stored in a NUMERIC(10, 2) column, so edited values with more decimals
+// compare equal to the rounded ones read back
+fn cents(value: f64) -> rust_decimal::Decimal {
+    crate::money::round_cents(crate::money::to_decimal(value))
+}
+
+/// Bring an invoice's stored line items in line with `line_items`, keeping the ids of the
+/// ones that already exist. Items with an id that isn't stored for this invoice are new.
+async fn sync_line_items(conn: &mut sqlx::PgConnection, invoice_id: i32, line_items: &[InvoiceLineItem]) -> Result<()> {
+    let existing = sqlx::query!(
+        r#"
+        SELECT
+            id,
+            description,
+            hours::float8 as "hours!: f64",
+            amount::float8 as "amount!: f64",
+            rate_override::float8 as "rate_override: f64",
+            item_type,
+            position
+        FROM invoice_line_item
+        WHERE invoice_id = $1
+        "#,
+        invoice_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    
+    // Remove the items that are gone
+    let kept: Vec<i32> = existing
+        .iter()
+        .map(|row| row.id)
+        .filter(|id| line_items.iter().any(|item| item.id == *id))
+        .collect();
+    sqlx::query!(
+        "DELETE FROM invoice_line_item WHERE invoice_id = $1 AND id <> ALL($2)",
+        invoice_id,
+        &kept
+    )
+    .execute(&mut *conn)
+    .await?;
+    
+    for (position, line_item) in line_items.iter().enumerate() {
+        let position = position as i32;
+        match existing.iter().find(|row| row.id == line_item.id) {
+            Some(row) if row.description == line_item.description
+                && cents(row.hours) == cents(line_item.hours)
+                && cents(row.amount) == cents(line_item.amount)
+                && row.rate_override.map(cents) == line_item.rate_override.map(cents)
+                && row.item_type == line_item.item_type
+                && row.position == position => {}
+            Some(_) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE invoice_line_item
+                    SET description = $1, hours = $2::float8, amount = $3::float8, rate_override = $4::float8, item_type = $5, position = $6
+                    WHERE id = $7
+                    "#,
+                    line_item.description,
+                    line_item.hours as f64,
+                    line_item.amount as f64,
+                    line_item.rate_override,
+                    line_item.item_type,
+                    position,
+                    line_item.id
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO invoice_line_item (invoice_id, description, hours, amount, rate_override, item_type, position)
+                    VALUES ($1, $2, $3::float8, $4::float8, $5::float8, $6, $7)
+                    "#,
+                    invoice_id,
+                    line_item.description,
+                    line_item.hours as f64,
+                    line_item.amount as f64,
+                    line_item.rate_override,
+                    line_item.item_type,
+                    position
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// One more than the highest invoice number in the project's numbering scope: all of the
+/// profile's invoices, or only the project's own
+async fn next_invoice_number<'e, E: sqlx::PgExecutor<'e>>(executor: E, project_id: i32, scope: NumberingScope) -> Result<i32> {
+    let number = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(MAX(i.number), 0) + 1 as "number!"
+        FROM invoices i
+        JOIN projects p ON p.id = i.project_id
+        JOIN clients c ON c.id = p.client_id
+        WHERE CASE
+            WHEN $2 THEN i.project_id = $1
+            ELSE c.profile_id = (
+                SELECT pc.profile_id
+                FROM projects pp
+                JOIN clients pc ON pc.id = pp.client_id
+                WHERE pp.id = $1
+            )
+        END
+        "#,
+        project_id,
+        scope == NumberingScope::Project
+    )
+    .fetch_one(executor)
+    .await?;
+    
+    Ok(number)
+}
+
+/// Whether another invoice in the same numbering scope already has the invoice's number
+async fn invoice_number_taken<'e, E: sqlx::PgExecutor<'e>>(executor: E, invoice: &Invoice, scope: NumberingScope) -> Result<bool> {
+    let taken = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM invoices i
+            JOIN projects p ON p.id = i.project_id
+            JOIN clients c ON c.id = p.client_id
+            WHERE i.number = $2
+              AND i.id <> $3
+              AND CASE
+                  WHEN $4 THEN i.project_id = $1
+                  ELSE c.profile_id = (
+                      SELECT pc.profile_id
+                      FROM projects pp
+                      JOIN clients pc ON pc.id = pp.client_id
+                      WHERE pp.id = $1
+                  )
+              END
+        ) as "taken!"
+        "#,
+        invoice.project_id,
+        invoice.number,
+        invoice.id,
+        scope == NumberingScope::Project
+    )
+    .fetch_one(executor)
+    .await?;
+    
+    Ok(taken)
+}
+
+/// Link time entries to the invoice they're billed on. Fails when any of them was billed
+/// on another invoice in the meantime, so the same hours are never billed twice.
+async fn bill_time_entries<'e, E: sqlx::PgExecutor<'e>>(executor: E, entry_ids: &[i32], invoice_id: i32) -> Result<()> {
+    let billed = sqlx::query!(
+        "UPDATE time_entries SET invoice_id = $1 WHERE id = ANY($2) AND invoice_id IS NULL",
+        invoice_id,
+        entry_ids
+    )
+    .execute(executor)
+    .await?;
+    
+    if billed.rows_affected() < entry_ids.len() as u64 {
+        return Err(AppError::Validation(
+            "Some of these time entries were billed on another invoice in the meantime. Start the invoice from the time entries again.".to_string(),
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Recompute the cached total of one invoice from its line items and tax
+async fn refresh_invoice_total<'e, E: sqlx::PgExecutor<'e>>(executor: E, invoice_id: i32) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE invoices i
+        SET total = (
+            SELECT s.subtotal + ROUND(s.subtotal * COALESCE(i.tax_rate, 0) / 100, 2)
+            FROM (
+                SELECT COALESCE(SUM(CASE WHEN i.billing_type = 'Flat' OR li.item_type = 'Fixed' THEN li.amount ELSE ROUND(li.hours * COALESCE(li.rate_override, i.rate), 2) END), 0) as subtotal
+                FROM invoice_line_item li
+                WHERE li.invoice_id = i.id
+            ) s
+        )
+        WHERE i.id = $1
+        "#,
+        invoice_id
+    )
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
 /// Initialize the database connection pool
 pub async fn init(config: &Config) -> Result<Database> {
     let db = Database::new(config).await?;
     
-    // Run migrations if you want to automatically migrate the database
-    // sqlx::migrate!().run(db.get_pool()).await?;
+    // Bring the schema up to date, so a fresh database gets every table
+    if config.auto_migrate {
+        sqlx::migrate!().run(db.get_pool()).await?;
+    }
     
     Ok(db)
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, invoice, line_item};
+
+    fn database(pool: PgPool) -> Database {
+        Database::from_pool(pool)
+    }
+
+    // Profile, client and project to hang invoices off, returning the client and project ids
+    async fn seed_project(db: &Database) -> (i32, i32) {
+        seed_profile_project(db, "Studio").await
+    }
+
+    async fn seed_profile_project(db: &Database, profile_name: &str) -> (i32, i32) {
+        let profile_id = db.create_profile(&test_support::profile(profile_name)).await.unwrap();
+        let client_id = db.create_client(&test_support::client(profile_id, "Acme")).await.unwrap();
+        let project_id = db.create_project(&test_support::project(client_id, "Website")).await.unwrap();
+        (client_id, project_id)
+    }
+
+    // New invoice of the project billing the given hours at 100 an hour
+    async fn seed_invoice(db: &Database, project_id: i32, hours: f64) -> i32 {
+        let invoice = Invoice { id: 0, number: 0, project_id, ..invoice(0) };
+        db.save_invoice_with_line_items(&invoice, &[line_item(0, "Work", hours)]).await.unwrap()
+    }
+
+    #[sqlx::test]
+    async fn merging_clients_moves_the_projects_and_deletes_the_source(pool: PgPool) {
+        let db = database(pool);
+        let (target_id, _) = seed_project(&db).await;
+        let profile_id = db.get_client(target_id).await.unwrap().profile_id;
+        let source_id = db.create_client(&test_support::client(profile_id, "Acme Ltd")).await.unwrap();
+        db.create_project(&test_support::project(source_id, "App")).await.unwrap();
+        db.create_project(&test_support::project(source_id, "Hosting")).await.unwrap();
+
+        assert_eq!(db.merge_clients(source_id, target_id).await.unwrap(), 2);
+
+        let projects = db.get_projects_by_client(target_id).await.unwrap();
+        let mut names: Vec<_> = projects.iter().map(|project| project.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["App", "Hosting", "Website"]);
+        assert!(matches!(db.get_client(source_id).await, Err(AppError::NotFound)));
+    }
+
+    #[sqlx::test]
+    async fn a_client_cant_be_merged_into_itself(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, _) = seed_project(&db).await;
+
+        assert!(matches!(db.merge_clients(client_id, client_id).await, Err(AppError::Validation(_))));
+        assert_eq!(db.get_projects_by_client(client_id).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn sending_stores_a_snapshot_matching_the_invoice(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let id = seed_invoice(&db, project_id, 3.0).await;
+        let (invoice, line_items) = db.get_invoice_with_line_items(id).await.unwrap();
+
+        db.save_invoice_snapshot(&invoice, &line_items).await.unwrap();
+
+        let snapshots = db.get_invoice_snapshots(id).await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].total, 300.0);
+        let stored: serde_json::Value = serde_json::from_str(&snapshots[0].snapshot).unwrap();
+        assert_eq!(stored["invoice"], serde_json::to_value(&invoice).unwrap());
+        assert_eq!(stored["line_items"], serde_json::to_value(&line_items).unwrap());
+    }
+
+    #[sqlx::test]
+    async fn report_grand_total_is_the_sum_of_the_profiles(pool: PgPool) {
+        let db = database(pool);
+        let (_, first_project) = seed_profile_project(&db, "First").await;
+        let (_, second_project) = seed_profile_project(&db, "Second").await;
+
+        let paid = seed_invoice(&db, first_project, 1.0).await;
+        db.mark_invoice_paid(paid, test_support::date(2024, 2, 1)).await.unwrap();
+        seed_invoice(&db, first_project, 2.0).await;
+        let part_paid = seed_invoice(&db, second_project, 4.0).await;
+        db.record_payment(part_paid, 150.0, test_support::date(2024, 2, 1)).await.unwrap();
+        let cancelled = seed_invoice(&db, second_project, 8.0).await;
+        db.set_invoice_status(cancelled, "Cancelled").await.unwrap();
+
+        let totals = db.get_all_profiles_totals(None, None).await.unwrap();
+        let rows: Vec<_> = totals.iter().map(|t| (t.profile_name.as_str(), t.billed, t.paid, t.outstanding)).collect();
+        assert_eq!(rows, [("First", 300.0, 100.0, 200.0), ("Second", 400.0, 150.0, 250.0)]);
+
+        let report = crate::ui::report::ReportState::new(totals, None, None);
+        let cents = |amount: i64| rust_decimal::Decimal::new(amount * 100, 2);
+        assert_eq!(report.grand_totals(), [("USD".to_string(), cents(700), cents(250), cents(450))]);
+
+        // Profiles billing in another currency get a grand total of their own
+        let euro_profile = Profile { currency: "EUR".to_string(), ..test_support::profile("Euro") };
+        let euro_profile_id = db.create_profile(&euro_profile).await.unwrap();
+        let euro_client_id = db.create_client(&test_support::client(euro_profile_id, "GmbH")).await.unwrap();
+        let euro_project = db.create_project(&test_support::project(euro_client_id, "Shop")).await.unwrap();
+        seed_invoice(&db, euro_project, 16.0).await;
+
+        let report = crate::ui::report::ReportState::new(db.get_all_profiles_totals(None, None).await.unwrap(), None, None);
+        assert_eq!(report.grand_totals(), [
+            ("EUR".to_string(), cents(1600), cents(0), cents(1600)),
+            ("USD".to_string(), cents(700), cents(250), cents(450)),
+        ]);
+    }
+
+    #[sqlx::test]
+    async fn previous_balance_counts_only_other_unpaid_sent_invoices(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, project_id) = seed_project(&db).await;
+
+        let paid = seed_invoice(&db, project_id, 1.0).await;
+        db.set_invoice_status(paid, "Sent").await.unwrap();
+        db.mark_invoice_paid(paid, test_support::date(2024, 2, 1)).await.unwrap();
+        let unpaid = seed_invoice(&db, project_id, 2.0).await;
+        db.set_invoice_status(unpaid, "Sent").await.unwrap();
+        seed_invoice(&db, project_id, 4.0).await;
+        let cancelled = seed_invoice(&db, project_id, 8.0).await;
+        db.set_invoice_status(cancelled, "Cancelled").await.unwrap();
+        let current = seed_invoice(&db, project_id, 16.0).await;
+        db.set_invoice_status(current, "Sent").await.unwrap();
+
+        let balance = db.get_client_outstanding_balance(client_id, current).await.unwrap();
+        assert_eq!(balance, 200.0);
+    }
+
+    #[sqlx::test]
+    async fn a_line_item_without_an_invoice_is_reported_and_cleaned_up(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        seed_invoice(&db, project_id, 1.0).await;
+
+        // The foreign key stops the orphan being inserted, as it would be left after an interrupted delete
+        let mut tx = db.pool.begin().await.unwrap();
+        sqlx::query("SET LOCAL session_replication_role = replica").execute(&mut *tx).await.unwrap();
+        let orphan_id: i32 = sqlx::query_scalar(
+            "INSERT INTO invoice_line_item (invoice_id, description, hours) VALUES (9999, 'Lost', 1) RETURNING id"
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        let report = db.find_orphans().await.unwrap();
+        assert_eq!(report.line_item_ids, [orphan_id]);
+        assert_eq!(report.total(), 1);
+
+        assert_eq!(db.delete_orphans().await.unwrap(), 1);
+        assert!(db.find_orphans().await.unwrap().is_empty());
+    }
+
+    async fn cached_total(db: &Database, id: i32) -> f64 {
+        sqlx::query_scalar("SELECT total::float8 FROM invoices WHERE id = $1")
+            .bind(id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn saving_updates_the_cached_total(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let id = seed_invoice(&db, project_id, 2.5).await;
+        assert_eq!(cached_total(&db, id).await, 250.0);
+
+        let (saved, mut line_items) = db.get_invoice_with_line_items(id).await.unwrap();
+        line_items.push(line_item(0, "Review", 1.0));
+        let taxed = Invoice { tax_rate: Some(10.0), ..saved };
+        db.save_invoice_with_line_items(&taxed, &line_items).await.unwrap();
+        assert_eq!(cached_total(&db, id).await, 385.0);
+    }
+
+    #[sqlx::test]
+    async fn recomputing_repairs_a_wrong_cached_total(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let wrong = seed_invoice(&db, project_id, 3.0).await;
+        let right = seed_invoice(&db, project_id, 1.0).await;
+        sqlx::query("UPDATE invoices SET total = 1 WHERE id = $1").bind(wrong).execute(&db.pool).await.unwrap();
+
+        // Only the wrong total is rewritten
+        assert_eq!(db.recompute_all_totals().await.unwrap(), 1);
+        assert_eq!(cached_total(&db, wrong).await, 300.0);
+        assert_eq!(cached_total(&db, right).await, 100.0);
+    }
+
+    // Transaction that last wrote each of the invoice's line items, by id
+    async fn line_item_writes(db: &Database, invoice_id: i32) -> Vec<(i32, String)> {
+        sqlx::query_as("SELECT id, xmin::text FROM invoice_line_item WHERE invoice_id = $1 ORDER BY id")
+            .bind(invoice_id)
+            .fetch_all(&db.pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn updating_writes_only_the_changed_line_items(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let new_invoice = Invoice { id: 0, number: 0, project_id, ..invoice(0) };
+        let items = [line_item(0, "Design", 1.0), line_item(0, "Build", 2.0), line_item(0, "Launch", 3.0)];
+        let id = db.save_invoice_with_line_items(&new_invoice, &items).await.unwrap();
+
+        let (saved, mut line_items) = db.get_invoice_with_line_items(id).await.unwrap();
+        let before = line_item_writes(&db, id).await;
+        // Extra decimals round to what's already stored
+        line_items[0].hours = 1.004;
+        line_items[1].hours = 2.5;
+        line_items.pop();
+        line_items.push(line_item(0, "Support", 1.0));
+        db.save_invoice_with_line_items(&saved, &line_items).await.unwrap();
+
+        let after = line_item_writes(&db, id).await;
+        assert_eq!(after.len(), 3);
+        // Unchanged and changed items keep their ids, only the changed one is rewritten
+        assert_eq!(after[0], before[0]);
+        assert_eq!(after[1].0, before[1].0);
+        assert_ne!(after[1].1, before[1].1);
+        // The removed item is gone and the added one gets a new id
+        assert!(after[2].0 > before[2].0);
+
+        let (_, stored) = db.get_invoice_with_line_items(id).await.unwrap();
+        let rows: Vec<_> = stored.iter().map(|item| (item.description.as_str(), item.hours)).collect();
+        assert_eq!(rows, [("Design", 1.0), ("Build", 2.5), ("Support", 1.0)]);
+    }
+
+    #[sqlx::test]
+    async fn project_summaries_count_and_total_each_project(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, website) = seed_project(&db).await;
+        let app = db.create_project(&test_support::project(client_id, "App")).await.unwrap();
+        let idle = db.create_project(&test_support::project(client_id, "Idle")).await.unwrap();
+
+        seed_invoice(&db, website, 1.0).await;
+        seed_invoice(&db, website, 2.5).await;
+        let cancelled = seed_invoice(&db, website, 8.0).await;
+        db.set_invoice_status(cancelled, "Cancelled").await.unwrap();
+        seed_invoice(&db, app, 4.0).await;
+
+        let mut summaries: Vec<_> = db.get_project_summaries(client_id).await.unwrap()
+            .into_iter()
+            .map(|summary| (summary.project_id, summary.invoice_count, summary.billed))
+            .collect();
+        summaries.sort_by_key(|(project_id, _, _)| *project_id);
+        // Cancelled invoices are counted but not billed, projects without invoices show 0
+        assert_eq!(summaries, [(website, 3, 350.0), (app, 1, 400.0), (idle, 0, 0.0)]);
+    }
+
+    #[sqlx::test]
+    async fn default_line_items_are_stored_in_order_per_profile(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, project_id) = seed_project(&db).await;
+        let profile_id = db.get_client(client_id).await.unwrap().profile_id;
+        let defaults = ["Hosting".to_string(), "Design".to_string()];
+
+        db.set_default_line_items(profile_id, &defaults).await.unwrap();
+        assert_eq!(db.get_default_line_items(profile_id).await.unwrap(), defaults);
+        assert_eq!(db.get_default_line_items_by_project(project_id).await.unwrap(), defaults);
+
+        // Setting them again replaces the list
+        db.set_default_line_items(profile_id, &defaults[1..]).await.unwrap();
+        assert_eq!(db.get_default_line_items(profile_id).await.unwrap(), ["Design"]);
+    }
+
+    #[sqlx::test]
+    async fn numbering_follows_the_configured_scope(pool: PgPool) {
+        let db = database(pool.clone());
+        let by_project = Database { pool, numbering_scope: NumberingScope::Project };
+        let (client_id, website) = seed_project(&db).await;
+        let app = db.create_project(&test_support::project(client_id, "App")).await.unwrap();
+        let (_, other_profile) = seed_profile_project(&db, "Other").await;
+
+        seed_invoice(&db, website, 1.0).await;
+        seed_invoice(&db, website, 1.0).await;
+        seed_invoice(&db, other_profile, 1.0).await;
+
+        // Sequential across all of the profile's projects, but not other profiles
+        assert_eq!(db.next_invoice_number(app).await.unwrap(), 3);
+        assert_eq!(db.next_invoice_number(other_profile).await.unwrap(), 2);
+        // Restarting for each project
+        assert_eq!(by_project.next_invoice_number(website).await.unwrap(), 3);
+        assert_eq!(by_project.next_invoice_number(app).await.unwrap(), 1);
+    }
+
+    #[sqlx::test]
+    async fn deleting_an_invoice_removes_its_line_items(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let deleted = seed_invoice(&db, project_id, 1.0).await;
+        let kept = seed_invoice(&db, project_id, 2.0).await;
+        // Sent invoices have snapshots referencing them
+        let (invoice, line_items) = db.get_invoice_with_line_items(deleted).await.unwrap();
+        db.save_invoice_snapshot(&invoice, &line_items).await.unwrap();
+
+        db.delete_invoice(deleted).await.unwrap();
+
+        assert!(matches!(db.get_invoice_with_line_items(deleted).await, Err(AppError::NotFound)));
+        assert!(db.get_line_items_by_invoice(deleted).await.unwrap().is_empty());
+        assert_eq!(db.get_line_items_by_invoice(kept).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn importing_a_csv_creates_only_the_valid_clients(pool: PgPool) {
+        let db = database(pool);
+        let profile_id = db.create_profile(&test_support::profile("Studio")).await.unwrap();
+        let path = std::env::temp_dir().join(format!("clients-{}.csv", std::process::id()));
+        std::fs::write(&path, "name,email,phone,address\nAcme,accounts@acme.example,555-0100,\n,missing@name.example,555-0101,\n").unwrap();
+
+        let report = crate::import::import_clients_csv(&db, profile_id, path.to_str().unwrap()).await;
+        std::fs::remove_file(&path).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.skipped.len(), 1);
+        let clients = db.get_clients_by_profile(profile_id).await.unwrap();
+        let names: Vec<_> = clients.iter().map(|client| client.name.as_str()).collect();
+        assert_eq!(names, ["Acme"]);
+    }
+
+    #[sqlx::test]
+    async fn a_send_records_when_and_to_whom(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let id = seed_invoice(&db, project_id, 1.0).await;
+        let emailed_at = test_support::date(2024, 3, 2).and_hms_opt(14, 30, 0).unwrap();
+
+        db.record_invoice_emailed(id, "accounts@client.example", emailed_at).await.unwrap();
+
+        let (saved, _) = db.get_invoice_with_line_items(id).await.unwrap();
+        assert_eq!(saved.last_emailed_at, Some(emailed_at));
+        assert_eq!(saved.last_emailed_to.as_deref(), Some("accounts@client.example"));
+    }
+
+    #[sqlx::test]
+    async fn a_draft_without_line_items_can_be_saved(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let skeleton = Invoice { id: 0, number: 0, project_id, status: "Approved".to_string(), ..invoice(0) };
+
+        let id = db.save_draft_invoice(&skeleton, &[], &[]).await.unwrap();
+
+        let (saved, line_items) = db.get_invoice_with_line_items(id).await.unwrap();
+        assert_eq!(saved.status, "Draft");
+        assert!(line_items.is_empty());
+        assert!(!saved.can_be_emailed());
+    }
+
+    #[sqlx::test]
+    async fn saving_a_stale_version_is_rejected(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let id = seed_invoice(&db, project_id, 1.0).await;
+
+        // Two sessions open the same invoice, the first one saves
+        let (first, line_items) = db.get_invoice_with_line_items(id).await.unwrap();
+        let (stale, _) = db.get_invoice_with_line_items(id).await.unwrap();
+        let edited = Invoice { rate: 120.0, ..first };
+        db.save_invoice_with_line_items(&edited, &line_items).await.unwrap();
+
+        let overwrite = Invoice { rate: 80.0, ..stale };
+        assert!(matches!(db.save_invoice_with_line_items(&overwrite, &line_items).await, Err(AppError::InvoiceModified)));
+
+        let (saved, _) = db.get_invoice_with_line_items(id).await.unwrap();
+        assert_eq!(saved.rate, 120.0);
+        assert_eq!(saved.version, edited.version + 1);
+    }
+
+    #[sqlx::test]
+    async fn a_wizard_opened_before_a_status_change_cant_overwrite_it(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let id = seed_invoice(&db, project_id, 1.0).await;
+        db.set_invoice_status(id, "Sent").await.unwrap();
+
+        // The wizard still holds the Sent status when another session marks the invoice paid
+        let (opened, line_items) = db.get_invoice_with_line_items(id).await.unwrap();
+        db.mark_invoice_paid(id, test_support::date(2024, 2, 1)).await.unwrap();
+
+        let edited = Invoice { rate: 120.0, ..opened };
+        assert!(matches!(db.save_invoice_with_line_items(&edited, &line_items).await, Err(AppError::InvoiceModified)));
+        assert_eq!(db.get_invoice_with_line_items(id).await.unwrap().0.status, "Paid");
+    }
+
+    #[sqlx::test]
+    async fn duplicate_numbers_are_renumbered_in_submit_date_order(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, project_id) = seed_project(&db).await;
+        let profile_id = db.get_client(client_id).await.unwrap().profile_id;
+
+        // Numbers left behind by the old timestamp based numbering
+        let mut ids = Vec::new();
+        for (number, day) in [(4242, 20), (4242, 5), (17, 10)] {
+            let id = seed_invoice(&db, project_id, 1.0).await;
+            sqlx::query("UPDATE invoices SET number = $1, submit_date = $2 WHERE id = $3")
+                .bind(number)
+                .bind(test_support::date(2024, 1, day))
+                .bind(id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        let duplicates = db.find_duplicate_invoice_numbers(profile_id).await.unwrap();
+        let found: Vec<_> = duplicates.iter().map(|duplicate| (duplicate.number, duplicate.count)).collect();
+        assert_eq!(found, [(4242, 2)]);
+
+        assert_eq!(db.renumber_invoices(profile_id).await.unwrap(), 3);
+        let mut numbers = Vec::new();
+        for id in ids {
+            numbers.push(db.get_invoice_with_line_items(id).await.unwrap().0.number);
+        }
+        assert_eq!(numbers, [3, 1, 2]);
+        assert!(db.find_duplicate_invoice_numbers(profile_id).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn outstanding_totals_count_unpaid_invoices_only(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, project_id) = seed_project(&db).await;
+        let profile_id = db.get_client(client_id).await.unwrap().profile_id;
+        let idle_client_id = db.create_client(&test_support::client(profile_id, "Idle Co")).await.unwrap();
+
+        let sent = seed_invoice(&db, project_id, 2.0).await;
+        db.set_invoice_status(sent, "Sent").await.unwrap();
+        let part_paid = seed_invoice(&db, project_id, 3.0).await;
+        db.set_invoice_status(part_paid, "Sent").await.unwrap();
+        db.record_payment(part_paid, 50.0, test_support::date(2024, 2, 1)).await.unwrap();
+        let paid = seed_invoice(&db, project_id, 10.0).await;
+        db.mark_invoice_paid(paid, test_support::date(2024, 2, 1)).await.unwrap();
+        let cancelled = seed_invoice(&db, project_id, 20.0).await;
+        db.set_invoice_status(cancelled, "Cancelled").await.unwrap();
+        // Drafts aren't owed until they're approved
+        seed_invoice(&db, project_id, 40.0).await;
+
+        let totals = db.get_client_outstanding_totals(profile_id).await.unwrap();
+        assert_eq!(totals.get(&client_id), Some(&450.0));
+        assert_eq!(totals.get(&idle_client_id), None);
+
+        // Other profiles' clients stay out of the totals
+        let (_, other_project_id) = seed_profile_project(&db, "Other").await;
+        let other = seed_invoice(&db, other_project_id, 1.0).await;
+        db.set_invoice_status(other, "Sent").await.unwrap();
+        assert_eq!(db.get_client_outstanding_totals(profile_id).await.unwrap(), totals);
+    }
+
+    async fn seed_time_entry(db: &Database, project_id: i32, hours: f64) -> i32 {
+        sqlx::query_scalar("INSERT INTO time_entries (project_id, entry_date, description, hours) VALUES ($1, '2024-01-10', 'Work', $2) RETURNING id")
+            .bind(project_id)
+            .bind(hours)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn time_entries_are_billed_with_the_invoice_and_only_once(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let first = seed_time_entry(&db, project_id, 1.0).await;
+        let second = seed_time_entry(&db, project_id, 2.0).await;
+        let new_invoice = Invoice { id: 0, number: 0, project_id, ..invoice(0) };
+        let items = [line_item(0, "Work", 3.0)];
+
+        let id = db.save_invoice_billing_time_entries(&new_invoice, &items, &[first, second]).await.unwrap();
+        assert!(db.get_unbilled_time_entries(project_id).await.unwrap().is_empty());
+        let billed_on: Vec<Option<i32>> = sqlx::query_scalar("SELECT invoice_id FROM time_entries ORDER BY id")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(billed_on, [Some(id), Some(id)]);
+
+        // Another session billing the same entries saves nothing
+        let third = seed_time_entry(&db, project_id, 4.0).await;
+        let invoices_before = db.get_invoices_by_project(project_id).await.unwrap().len();
+        assert!(matches!(
+            db.save_invoice_billing_time_entries(&new_invoice, &items, &[second, third]).await,
+            Err(AppError::Validation(_))
+        ));
+        assert_eq!(db.get_invoices_by_project(project_id).await.unwrap().len(), invoices_before);
+        assert_eq!(db.get_unbilled_time_entries(project_id).await.unwrap().len(), 1);
+    }
+
+    #[sqlx::test]
+    async fn statements_leave_out_drafts(pool: PgPool) {
+        let db = database(pool);
+        let (client_id, project_id) = seed_project(&db).await;
+
+        seed_invoice(&db, project_id, 1.0).await;
+        let sent = seed_invoice(&db, project_id, 2.0).await;
+        db.set_invoice_status(sent, "Sent").await.unwrap();
+        let paid = seed_invoice(&db, project_id, 4.0).await;
+        db.mark_invoice_paid(paid, test_support::date(2024, 2, 1)).await.unwrap();
+
+        let unpaid: Vec<_> = db.get_unpaid_invoices_by_client(client_id).await.unwrap().iter().map(|i| i.id).collect();
+        assert_eq!(unpaid, [sent]);
+    }
+
+    #[sqlx::test]
+    async fn failures_map_to_their_error_variants(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        assert!(matches!(db.get_invoice_with_line_items(9999).await, Err(AppError::NotFound)));
+
+        let id = seed_invoice(&db, project_id, 1.0).await;
+        let number = db.get_invoice_with_line_items(id).await.unwrap().0.number;
+        let duplicate = Invoice { id: 0, number, project_id, ..invoice(0) };
+        assert!(matches!(
+            db.save_invoice_with_line_items(&duplicate, &[line_item(0, "Work", 1.0)]).await,
+            Err(AppError::Validation(_))
+        ));
+
+        // Nothing listens on port 1, so the query can't get a connection
+        let unreachable = PgPoolOptions::new()
+            .acquire_timeout(std::time::Duration::from_millis(500))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/invoices")
+            .unwrap();
+        assert!(matches!(database(unreachable).get_invoice_with_line_items(id).await, Err(AppError::Database(_))));
+    }
+
+    #[sqlx::test]
+    async fn each_send_is_logged_most_recent_first(pool: PgPool) {
+        let db = database(pool);
+        let (_, project_id) = seed_project(&db).await;
+        let id = seed_invoice(&db, project_id, 1.0).await;
+        let other_id = seed_invoice(&db, project_id, 1.0).await;
+        let first_sent = test_support::date(2024, 2, 1).and_hms_opt(9, 0, 0).unwrap();
+        let reminder_sent = test_support::date(2024, 2, 20).and_hms_opt(9, 0, 0).unwrap();
+
+        db.log_invoice_email(id, "accounts@client.example", first_sent, true).await.unwrap();
+        db.log_invoice_email(id, "ap@client.example; cc: boss@client.example; bcc: me@studio.example", reminder_sent, false).await.unwrap();
+        db.log_invoice_email(other_id, "accounts@client.example", first_sent, true).await.unwrap();
+
+        let log: Vec<_> = db
+            .get_invoice_email_log(id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.sent_at, entry.recipient, entry.success))
+            .collect();
+        assert_eq!(log, [
+            (reminder_sent, "ap@client.example; cc: boss@client.example; bcc: me@studio.example".to_string(), false),
+            (first_sent, "accounts@client.example".to_string(), true),
+        ]);
+    }
+}
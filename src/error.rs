@@ -0,0 +1,55 @@
+// Errors of the database and email layers, so callers can tell a missing record from a
+// failed connection or bad input. The UI and main keep using anyhow on top of these.
+
+/// Result type of the database and email layers
+pub type Result<T> = std::result::Result<T, AppError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// A query expected a row that doesn't exist
+    #[error("Record not found")]
+    NotFound,
+
+    /// Input that was rejected before anything was changed
+    #[error("{0}")]
+    Validation(String),
+
+    /// Returned when saving an invoice that another session updated since it was loaded
+    #[error("This invoice was modified in another session since it was opened. Reopen it to see the latest version.")]
+    InvoiceModified,
+
+    /// A setting needed for the operation is missing or invalid
+    #[error("{0}")]
+    Config(String),
+
+    #[error("Database error: {0}")]
+    Database(sqlx::Error),
+
+    /// Applying the migrations on startup failed, leaving the schema as it was
+    #[error("Database migration failed: {0}. Fix the database or set AUTO_MIGRATE=false to start without migrating.")]
+    Migration(#[from] sqlx::migrate::MigrateError),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("Invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+
+    #[error("Failed to build email: {0}")]
+    Email(#[from] lettre::error::Error),
+
+    #[error("Invalid content type: {0}")]
+    ContentType(#[from] lettre::message::header::ContentTypeErr),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => AppError::NotFound,
+            error => AppError::Database(error),
+        }
+    }
+}
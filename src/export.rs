@@ -0,0 +1,158 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::Write;
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::invoice_gen::InvoiceGenerator;
+use crate::models::InvoiceExportRow;
+
+/// Column layouts supported for accounting software imports
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    /// QuickBooks Online invoice import layout
+    QuickBooks,
+    /// Plain layout with one column per invoice field, usable by Xero and spreadsheets
+    Generic,
+}
+
+impl ExportFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::QuickBooks => "QuickBooks",
+            ExportFormat::Generic => "Generic",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            ExportFormat::QuickBooks => ExportFormat::Generic,
+            ExportFormat::Generic => ExportFormat::QuickBooks,
+        }
+    }
+
+    /// Header row for the format.
+    ///
+    /// QuickBooks:
+    /// - `InvoiceNo` <- invoice number
+    /// - `Customer` <- client name
+    /// - `InvoiceDate` <- submit date (MM/DD/YYYY)
+    /// - `DueDate` <- due date (MM/DD/YYYY)
+    /// - `ItemDescription` <- project name
+    /// - `ItemAmount` <- invoice total
+    ///
+    /// Generic: one column per field, ISO dates and the invoice status included.
+    pub fn headers(&self) -> &'static [&'static str] {
+        match self {
+            ExportFormat::QuickBooks => &["InvoiceNo", "Customer", "InvoiceDate", "DueDate", "ItemDescription", "ItemAmount"],
+            ExportFormat::Generic => &["invoice_number", "client", "project", "submit_date", "due_date", "status", "total"],
+        }
+    }
+
+    fn record(&self, row: &InvoiceExportRow) -> Vec<String> {
+        match self {
+            ExportFormat::QuickBooks => vec![
+                row.number.to_string(),
+                row.client_name.clone(),
+                row.submit_date.format("%m/%d/%Y").to_string(),
+                row.due_date.format("%m/%d/%Y").to_string(),
+                row.project_name.clone(),
+                format!("{:.2}", row.total),
+            ],
+            ExportFormat::Generic => vec![
+                row.number.to_string(),
+                row.client_name.clone(),
+                row.project_name.clone(),
+                row.submit_date.format("%Y-%m-%d").to_string(),
+                row.due_date.format("%Y-%m-%d").to_string(),
+                row.status.clone(),
+                format!("{:.2}", row.total),
+            ],
+        }
+    }
+}
+
+/// Write the rows as CSV in the given format
+pub fn write_accounting_csv<W: Write>(writer: W, rows: &[InvoiceExportRow], format: ExportFormat) -> Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+
+    csv_writer.write_record(format.headers())?;
+    for row in rows {
+        csv_writer.write_record(format.record(row))?;
+    }
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Export all invoices of a profile to a CSV file. Returns the number of invoices exported.
+pub async fn export_accounting_csv(db: &Database, profile_id: i32, format: ExportFormat, path: &str) -> Result<usize> {
+    let rows = db.get_invoice_export_rows(profile_id).await?;
+
+    let file = File::create(path)?;
+    write_accounting_csv(file, &rows, format)?;
+
+    Ok(rows.len())
+}
+
+/// Bundle all of a client's unpaid invoices into one statement PDF in the invoices directory.
+/// Returns the path of the PDF, or None when the client has nothing outstanding.
+pub async fn export_client_statement(db: &Database, config: &Config, client_id: i32) -> Result<Option<String>> {
+    let unpaid = db.get_unpaid_invoices_by_client(client_id).await?;
+    if unpaid.is_empty() {
+        return Ok(None);
+    }
+
+    let client = db.get_client(client_id).await?;
+    let profile = db.get_profile(client.profile_id).await?;
+
+    let mut invoices = Vec::with_capacity(unpaid.len());
+    for invoice in unpaid {
+        let line_items = db.get_line_items_by_invoice(invoice.id).await?;
+        let project = db.get_project(invoice.project_id).await?;
+        let paid = db.get_invoice_paid_amount(invoice.id).await?;
+        invoices.push((invoice, line_items, project, paid));
+    }
+
+    let generator = InvoiceGenerator::new(config)?;
+    let path = generator.generate_statement(&invoices, &profile, &client)?;
+
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::date;
+
+    fn export(format: ExportFormat) -> Vec<String> {
+        let row = InvoiceExportRow {
+            number: 42,
+            client_name: "Acme, Inc.".to_string(),
+            project_name: "Website".to_string(),
+            submit_date: date(2024, 3, 5),
+            due_date: date(2024, 4, 4),
+            status: "Sent".to_string(),
+            total: 1250.5,
+        };
+        let mut output = Vec::new();
+        write_accounting_csv(&mut output, &[row], format).unwrap();
+        String::from_utf8(output).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn quickbooks_layout() {
+        assert_eq!(export(ExportFormat::QuickBooks), [
+            "InvoiceNo,Customer,InvoiceDate,DueDate,ItemDescription,ItemAmount",
+            "42,\"Acme, Inc.\",03/05/2024,04/04/2024,Website,1250.50",
+        ]);
+    }
+
+    #[test]
+    fn generic_layout() {
+        assert_eq!(export(ExportFormat::Generic), [
+            "invoice_number,client,project,submit_date,due_date,status,total",
+            "42,\"Acme, Inc.\",Website,2024-03-05,2024-04-04,Sent,1250.50",
+        ]);
+    }
+}
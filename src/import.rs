@@ -0,0 +1,151 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::Read;
+
+use crate::db::Database;
+use crate::labels::DEFAULT_LANGUAGE;
+use crate::models::Client;
+
+// One row of a clients CSV, with columns `name,email,phone,address`
+#[derive(Deserialize)]
+struct ClientRow {
+    name: String,
+    email: String,
+    phone: String,
+    address: Option<String>,
+}
+
+/// A CSV row that wasn't imported
+#[derive(Debug)]
+pub struct SkippedRow {
+    pub line: u64,
+    pub reason: String,
+}
+
+/// Outcome of a clients import
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl ImportReport {
+    /// One line summary, followed by the first few skipped rows
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Imported {} client(s), skipped {}", self.imported, self.skipped.len());
+        for row in self.skipped.iter().take(3) {
+            summary.push_str(&format!(" | line {}: {}", row.line, row.reason));
+        }
+        if self.skipped.len() > 3 {
+            summary.push_str(" | ...");
+        }
+        summary
+    }
+}
+
+// Same required fields as the client wizard
+fn validate_row(row: &ClientRow) -> Result<(), String> {
+    if row.name.trim().is_empty() {
+        return Err("name is missing".into());
+    }
+    if !row.email.contains('@') {
+        return Err(format!("invalid email \"{}\"", row.email));
+    }
+    if row.phone.trim().is_empty() {
+        return Err("phone is missing".into());
+    }
+    Ok(())
+}
+
+/// Parse clients from CSV, collecting the rows that can't be imported and why
+pub fn parse_clients_csv<R: Read>(reader: R, profile_id: i32) -> Result<(Vec<Client>, Vec<SkippedRow>)> {
+    let mut csv_reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(reader);
+    let headers = csv_reader.headers()?.clone();
+    let mut clients = Vec::new();
+    let mut skipped = Vec::new();
+
+    for record in csv_reader.records() {
+        // A malformed row, like one with too few fields, is skipped like an invalid one.
+        // Only failing to read the file stops the import.
+        let record = match record {
+            Ok(record) => record,
+            Err(e) if !e.is_io_error() => {
+                let line = e.position().map_or(0, |p| p.line());
+                skipped.push(SkippedRow { line, reason: e.to_string() });
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let line = record.position().map_or(0, |p| p.line());
+        let row = record
+            .deserialize::<ClientRow>(Some(&headers))
+            .map_err(|e| e.to_string())
+            .and_then(|row| validate_row(&row).map(|_| row));
+
+        match row {
+            Ok(row) => clients.push(Client {
+                id: 0,
+                name: row.name,
+                phone: row.phone,
+                address: row.address.filter(|address| !address.is_empty()),
+                email: row.email,
+                profile_id,
+                show_bank_details: true,
+                language: DEFAULT_LANGUAGE.to_string(),
+            }),
+            Err(reason) => skipped.push(SkippedRow { line, reason }),
+        }
+    }
+
+    Ok((clients, skipped))
+}
+
+/// Create the valid clients in a CSV file under the profile, all or nothing.
+/// Invalid rows are skipped and listed in the report.
+pub async fn import_clients_csv(db: &Database, profile_id: i32, path: &str) -> Result<ImportReport> {
+    let file = File::open(path)?;
+    let (clients, skipped) = parse_clients_csv(file, profile_id)?;
+
+    db.create_clients(&clients).await?;
+
+    Ok(ImportReport { imported: clients.len(), skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "name,email,phone,address\n\
+        Acme,accounts@acme.example,555-0100,1 Main St\n\
+        Globex,not-an-email,555-0101,\n\
+        Initech,info@initech.example\n\
+        Umbrella,hq@umbrella.example,555-0102,\n";
+
+    #[test]
+    fn valid_rows_are_imported_and_invalid_rows_are_reported() {
+        let (clients, skipped) = parse_clients_csv(CSV.as_bytes(), 4).unwrap();
+
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].name, "Acme");
+        assert_eq!(clients[0].profile_id, 4);
+        assert_eq!(clients[0].address.as_deref(), Some("1 Main St"));
+        // Rows after a short one are still imported
+        assert_eq!(clients[1].name, "Umbrella");
+
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].line, 3);
+        assert_eq!(skipped[0].reason, "invalid email \"not-an-email\"");
+        assert_eq!(skipped[1].line, 4);
+        assert!(skipped[1].reason.contains("found record with 2 fields"), "{}", skipped[1].reason);
+    }
+
+    #[test]
+    fn summary_lists_the_skipped_rows() {
+        let report = ImportReport {
+            imported: 1,
+            skipped: vec![SkippedRow { line: 3, reason: "phone is missing".to_string() }],
+        };
+        assert_eq!(report.summary(), "Imported 1 client(s), skipped 1 | line 3: phone is missing");
+    }
+}
@@ -1,26 +1,243 @@
 use anyhow::Result;
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
 use std::process::Command;
 
+use crate::config::{Config, LineItemSort};
+use crate::labels::{self, Label};
+use crate::money;
 use crate::models::{Invoice, InvoiceLineItem, Profile, Client, Project};
 
 /// Service for generating invoice files in Markdown and PDF format
 pub struct InvoiceGenerator {
     output_dir: String,
+    line_item_sort: LineItemSort,
+    zebra_stripes: bool,
+}
+
+/// Background of every other line item row, a light tint of the header blue
+const ZEBRA_STRIPE_COLOR: &str = "#f1f2f8";
+
+/// Layout of the PDFs written without pandoc, in points on an A4 page
+const PDF_PAGE_WIDTH: f32 = 595.0;
+const PDF_PAGE_HEIGHT: f32 = 842.0;
+const PDF_MARGIN: f32 = 50.0;
+const PDF_FONT_SIZE: f32 = 9.0;
+const PDF_LEADING: f32 = 11.0;
+
+/// Amounts printed below an invoice's total
+#[derive(Clone, Copy, Default)]
+pub struct Balances {
+    /// The client's unpaid balance from other invoices, when it's shown
+    pub previous_balance: Option<f64>,
+    /// Payments already received for this invoice
+    pub amount_paid: f64,
+}
+
+/// Order line items for display. The stored order is left untouched.
+pub fn sort_line_items(line_items: &[InvoiceLineItem], sort: LineItemSort) -> Vec<&InvoiceLineItem> {
+    let mut sorted: Vec<&InvoiceLineItem> = line_items.iter().collect();
+    
+    match sort {
+        LineItemSort::AsEntered => {}
+        LineItemSort::Description => {
+            sorted.sort_by_key(|item| item.description.to_lowercase());
+        }
+        LineItemSort::HoursDesc => {
+            sorted.sort_by(|a, b| b.hours.partial_cmp(&a.hours).unwrap_or(Ordering::Equal));
+        }
+    }
+    
+    sorted
+}
+
+/// Plain text rendering of an invoice for previewing in the terminal, with the line items
+/// in the configured display order
+pub fn generate_plaintext(invoice: &Invoice, line_items: &[InvoiceLineItem], sort: LineItemSort, currency: &str) -> String {
+    let mut content = String::new();
+    
+    content.push_str(&format!("INVOICE #{}\n", invoice.number));
+    content.push_str(&format!(
+        "Submitted {}, due {}\n",
+        invoice.submit_date.format("%Y-%m-%d"),
+        invoice.due_date.format("%Y-%m-%d")
+    ));
+    content.push_str(&format!("Status: {}\n", invoice.status));
+    
+    if let Some(bill_to) = invoice.bill_to_override.as_ref().filter(|address| !address.trim().is_empty()) {
+        content.push_str("\nBill to:\n");
+        for line in bill_to.lines() {
+            content.push_str(&format!("  {}\n", line));
+        }
+    }
+    
+    content.push('\n');
+    if invoice.is_flat_fee() {
+        content.push_str(&format!("{:>3}  {:<40} {:>12}\n", "#", "Description", "Amount"));
+    } else {
+        content.push_str(&format!(
+            "{:>3}  {:<40} {:>8} {:>10} {:>12}\n",
+            "#", "Description", "Hours", "Rate", "Amount"
+        ));
+    }
+    
+    for (i, item) in sort_line_items(line_items, sort).into_iter().enumerate() {
+        let amount = money::format_money_in(invoice.line_item_amount(item), currency);
+        if invoice.is_flat_fee() {
+            content.push_str(&format!("{:>3}  {:<40} {:>12}\n", i + 1, item.description, amount));
+        } else if item.is_fixed() {
+            // Fixed amounts have no hours or rate to show
+            content.push_str(&format!("{:>3}  {:<40} {:>8} {:>10} {:>12}\n", i + 1, item.description, "", "", amount));
+        } else {
+            content.push_str(&format!(
+                "{:>3}  {:<40} {:>8} {:>10} {:>12}\n",
+                i + 1,
+                item.description,
+                item.hours,
+                money::format_money_in(item.effective_rate(invoice.rate), currency),
+                amount
+            ));
+        }
+    }
+    
+    content.push('\n');
+    let breakdown = money::compute_breakdown(invoice, line_items);
+    if let Some((tax_rate, tax)) = breakdown.tax {
+        content.push_str(&format!("Subtotal: {}\n", money::format_money_in(breakdown.subtotal, currency)));
+        content.push_str(&format!("Tax ({}%): {}\n", money::format_tax_rate(tax_rate), money::format_money_in(tax, currency)));
+    }
+    content.push_str(&format!("Total: {}\n", money::format_money_in(breakdown.total, currency)));
+    
+    content
+}
+
+/// Escape user supplied text for interpolation into the generated HTML markup
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    
+    escaped
+}
+
+/// Name reduced to lowercase letters, digits and underscores for use in a file name
+fn file_name_part(name: &str) -> String {
+    let part: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    
+    if part.is_empty() { "client".to_string() } else { part }
+}
+
+/// Whether the pandoc binary used for PDF conversion can be run
+pub fn pandoc_available() -> bool {
+    command_available("pandoc")
+}
+
+/// Whether a program is on the path and runs successfully with --version
+fn command_available(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Write plain text as a PDF in Courier, one of the fonts every PDF reader has built in, so
+/// nothing needs embedding and the columns stay aligned. Each entry of `documents` starts on
+/// a new page and continues onto more pages when it's too long for one.
+fn write_text_pdf(path: &str, documents: &[String]) -> Result<()> {
+    let lines_per_page = ((PDF_PAGE_HEIGHT - 2.0 * PDF_MARGIN) / PDF_LEADING) as usize;
+    let mut pages: Vec<Vec<&str>> = documents
+        .iter()
+        .flat_map(|text| {
+            let lines: Vec<&str> = text.lines().collect();
+            lines.chunks(lines_per_page).map(<[&str]>::to_vec).collect::<Vec<_>>()
+        })
+        .collect();
+    if pages.is_empty() {
+        pages.push(Vec::new());
+    }
+    
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let font_id = Ref::new(3);
+    let font_name = Name(b"F1");
+    // Each page takes two ids, the page itself and its content stream right after it
+    let page_ids: Vec<Ref> = (0..pages.len() as i32).map(|i| Ref::new(4 + 2 * i)).collect();
+    
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(pages.len() as i32);
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Courier"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    
+    for (page_id, lines) in page_ids.iter().zip(&pages) {
+        let content_id = Ref::new(page_id.get() + 1);
+        
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, PDF_PAGE_WIDTH, PDF_PAGE_HEIGHT))
+            .parent(page_tree_id)
+            .contents(content_id);
+        page.resources().fonts().pair(font_name, font_id);
+        page.finish();
+        
+        let mut content = Content::new();
+        content.begin_text()
+            .set_font(font_name, PDF_FONT_SIZE)
+            .set_leading(PDF_LEADING)
+            .next_line(PDF_MARGIN, PDF_PAGE_HEIGHT - PDF_MARGIN);
+        for line in lines {
+            content.show(Str(&win_ansi_bytes(line))).next_line_using_leading();
+        }
+        content.end_text();
+        pdf.stream(content_id, &content.finish());
+    }
+    
+    fs::write(path, pdf.finish())?;
+    Ok(())
+}
+
+/// Encode text for the built-in PDF fonts, which agree with Latin-1 apart from a few symbols.
+/// Characters they can't show become '?'.
+fn win_ansi_bytes(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '€' => 0x80,
+            '\u{20}'..='\u{7e}' | '\u{a0}'..='\u{ff}' => c as u8,
+            _ => b'?',
+        })
+        .collect()
 }
 
 impl InvoiceGenerator {
-    pub fn new(output_dir: &str) -> Result<Self> {
-        // Create the output directory if it doesn't exist
-        let path = Path::new(output_dir);
+    pub fn new(config: &Config) -> Result<Self> {
+        // Create the configured output directory if it doesn't exist
+        let path = Path::new(&config.invoice_output_dir);
         if !path.exists() {
             fs::create_dir_all(path)?;
         }
         
         Ok(Self {
-            output_dir: output_dir.to_string(),
+            output_dir: config.invoice_output_dir.clone(),
+            line_item_sort: config.line_item_sort,
+            zebra_stripes: config.invoice_zebra_stripes,
         })
     }
     
@@ -31,10 +248,11 @@ impl InvoiceGenerator {
         line_items: &[InvoiceLineItem],
         profile: &Profile,
         client: &Client,
-        project: &Project
+        project: &Project,
+        balances: Balances,
     ) -> Result<(String, String)> {
         // Generate Markdown content
-        let markdown = self.generate_markdown(invoice, line_items, profile, client, project)?;
+        let markdown = self.generate_markdown(invoice, line_items, profile, client, project, balances)?;
         
         // Create file names
         let md_filename = format!("invoice_{}.md", invoice.number);
@@ -48,30 +266,164 @@ impl InvoiceGenerator {
         let mut file = File::create(&md_path)?;
         file.write_all(markdown.as_bytes())?;
         
+        let text = self.generate_pdf_text(invoice, line_items, profile, client, balances);
+        self.convert_to_pdf(&md_path, &pdf_path, &[text])?;
+        
+        Ok((md_path, pdf_path))
+    }
+    
+    /// Generate a single statement PDF with each of the client's unpaid invoices on its own
+    /// page, along with the amount already paid on each. Returns the path of the PDF.
+    pub fn generate_statement(
+        &self,
+        invoices: &[(Invoice, Vec<InvoiceLineItem>, Project, f64)],
+        profile: &Profile,
+        client: &Client,
+    ) -> Result<String> {
+        let pages = invoices
+            .iter()
+            .map(|(invoice, line_items, project, paid)| {
+                let balances = Balances { previous_balance: None, amount_paid: *paid };
+                self.generate_markdown(invoice, line_items, profile, client, project, balances)
+            })
+            .collect::<Result<Vec<String>>>()?;
+        let text_pages: Vec<String> = invoices
+            .iter()
+            .map(|(invoice, line_items, _, paid)| {
+                let balances = Balances { previous_balance: None, amount_paid: *paid };
+                self.generate_pdf_text(invoice, line_items, profile, client, balances)
+            })
+            .collect();
+        
+        // Raw LaTeX page breaks, which pandoc passes through when rendering the PDF
+        let markdown = pages.join("\n\\newpage\n\n");
+        
+        let name = file_name_part(&client.name);
+        let md_path = format!("{}/statement_{}.md", self.output_dir, name);
+        let pdf_path = format!("{}/statement_{}.pdf", self.output_dir, name);
+        
+        let mut file = File::create(&md_path)?;
+        file.write_all(markdown.as_bytes())?;
+        
+        self.convert_to_pdf(&md_path, &pdf_path, &text_pages)?;
+        
+        Ok(pdf_path)
+    }
+    
+    /// Convert a Markdown file to PDF using pandoc. Without pandoc the plain text pages are
+    /// written as a PDF instead, and the Markdown is only copied when even that fails.
+    fn convert_to_pdf(&self, md_path: &str, pdf_path: &str, text_pages: &[String]) -> Result<()> {
         // Try to generate PDF using pandoc
         let pdf_result = Command::new("pandoc")
-            .arg(&md_path)
+            .arg(md_path)
             .arg("-o")
-            .arg(&pdf_path)
+            .arg(pdf_path)
             .output();
         
         match pdf_result {
             Ok(output) => {
                 if !output.status.success() {
                     let error = String::from_utf8_lossy(&output.stderr);
-                    println!("Warning: Failed to generate PDF: {}", error);
-                    // Create a simple text file as PDF substitute
-                    self.create_markdown_copy(&md_path, &pdf_path)?;
+                    log::warn!("Failed to generate PDF: {}", error);
+                    self.write_fallback_pdf(md_path, pdf_path, text_pages)?;
                 }
             }
             Err(e) => {
-                println!("Warning: Could not run pandoc: {}", e);
+                log::warn!("Could not run pandoc: {}", e);
+                self.write_fallback_pdf(md_path, pdf_path, text_pages)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Write the plain text pages as a PDF, falling back to a copy of the Markdown
+    fn write_fallback_pdf(&self, md_path: &str, pdf_path: &str, text_pages: &[String]) -> Result<()> {
+        match write_text_pdf(pdf_path, text_pages) {
+            Ok(()) => {
+                log::info!("Created plain text PDF without pandoc: {}", pdf_path);
+                Ok(())
+            }
+            Err(e) => {
+                log::warn!("Could not write plain text PDF: {}", e);
                 // Create a simple text file as PDF substitute
-                self.create_markdown_copy(&md_path, &pdf_path)?;
+                self.create_markdown_copy(md_path, pdf_path)
             }
         }
+    }
+    
+    /// Plain text invoice for the PDF written without pandoc, with who it's from and to
+    /// and the amounts below the total
+    fn generate_pdf_text(
+        &self,
+        invoice: &Invoice,
+        line_items: &[InvoiceLineItem],
+        profile: &Profile,
+        client: &Client,
+        balances: Balances,
+    ) -> String {
+        let text = |l: Label| labels::label(&client.language, l);
+        let mut content = String::new();
         
-        Ok((md_path, pdf_path))
+        content.push_str(&format!("{}\n", profile.name));
+        let address = profile.address.as_deref().unwrap_or_default();
+        for line in [address, &profile.email, &profile.phonenumber] {
+            if !line.trim().is_empty() {
+                content.push_str(&format!("{}\n", line));
+            }
+        }
+        
+        content.push_str(&format!("\n{}\n", client.name));
+        if let Some(address) = client.address.as_ref().filter(|address| !address.trim().is_empty()) {
+            content.push_str(&format!("{}\n", address));
+        }
+        content.push('\n');
+        
+        let currency = profile.currency.as_str();
+        content.push_str(&generate_plaintext(invoice, line_items, self.line_item_sort, currency));
+        
+        let total = money::invoice_total(invoice, line_items);
+        let balance_due = total - money::to_decimal(balances.amount_paid);
+        if balances.amount_paid > 0.0 {
+            content.push_str(&format!("{}: {}\n", text(Label::AmountPaid), money::format_money_in(balances.amount_paid, currency)));
+            content.push_str(&format!("{}: {}\n", text(Label::BalanceDue), money::format_money_in(balance_due, currency)));
+        }
+        if let Some(previous_balance) = balances.previous_balance {
+            content.push_str(&format!("{}: {}\n", text(Label::PreviousBalance), money::format_money_in(previous_balance, currency)));
+            content.push_str(&format!(
+                "{}: {}\n",
+                text(Label::TotalDueIncludingPrior),
+                money::format_money_in(money::to_decimal(previous_balance) + balance_due, currency)
+            ));
+        }
+        if let Some(notes) = invoice.notes.as_ref().filter(|notes| !notes.trim().is_empty()) {
+            content.push_str(&format!("\n{}\n", notes.trim_end()));
+        }
+        
+        content
+    }
+    
+    /// Render a generated Markdown invoice as an HTML fragment for inline email bodies.
+    /// Falls back to the escaped Markdown in a preformatted block when pandoc can't run.
+    pub fn generate_html(&self, md_path: &str) -> Result<String> {
+        let html = Command::new("pandoc")
+            .arg(md_path)
+            .arg("-f")
+            .arg("markdown")
+            .arg("-t")
+            .arg("html")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+        
+        match html {
+            Some(html) => Ok(html),
+            None => {
+                let markdown = fs::read_to_string(md_path)?;
+                Ok(format!("<pre>{}</pre>\n", escape_html(&markdown)))
+            }
+        }
     }
     
     /// Create a copy of the markdown file with .pdf extension as fallback
@@ -83,7 +435,7 @@ impl InvoiceGenerator {
         let mut file = File::create(pdf_path)?;
         file.write_all(content.as_bytes())?;
         
-        println!("Created markdown copy as PDF substitute: {}", pdf_path);
+        log::info!("Created markdown copy as PDF substitute: {}", pdf_path);
         Ok(())
     }
     
@@ -94,51 +446,66 @@ impl InvoiceGenerator {
         line_items: &[InvoiceLineItem],
         profile: &Profile,
         client: &Client,
-        project: &Project
+        project: &Project,
+        balances: Balances,
     ) -> Result<String> {
         let mut content = String::new();
+        let text = |l: Label| labels::label(&client.language, l);
+        let header_color = escape_html(&profile.header_color);
+        let accent_color = escape_html(&profile.accent_color);
+        let currency = profile.currency.as_str();
         
         // Add top blue divider
-        content.push_str("<hr style=\"height: 5px; background-color: #343876; border: none;\">\n\n");
+        content.push_str(&format!("<hr style=\"height: 5px; background-color: {}; border: none;\">\n\n", header_color));
         
         // Add profile header (name, address, phone)
-        content.push_str(&format!("# {}\n", profile.name));
+        content.push_str(&format!("# {}\n", escape_html(&profile.name)));
         
         // Address is optional, handle appropriately
         if let Some(address) = &profile.address {
-            content.push_str(&format!("{}\n", address));
+            content.push_str(&format!("{}\n", escape_html(address)));
         }
         
-        content.push_str(&format!("{}\n\n", profile.phonenumber));
+        content.push_str(&format!("{}\n\n", escape_html(&profile.phonenumber)));
         
         // Add Invoice title
-        content.push_str("# Invoice\n");
-        content.push_str(&format!("<span style=\"color: #e83e8c;\">Submitted on {}</span>\n\n", invoice.submit_date.format("%m/%d/%Y")));
+        content.push_str(&format!("# {}\n", text(Label::Invoice)));
+        content.push_str(&format!("<span style=\"color: {};\">{} {}</span>\n\n", accent_color, text(Label::SubmittedOn), invoice.submit_date.format("%m/%d/%Y")));
         
         // Create two column layout for client and payment info
         content.push_str("<div style=\"display: flex; justify-content: space-between;\">\n");
         
         // Left column - Invoice for
         content.push_str("<div style=\"width: 30%;\">\n");
-        content.push_str("**Invoice for**<br>\n");
-        content.push_str(&format!("{}\n", client.name));
-        content.push_str("</div>\n");
+        content.push_str(&format!("**{}**<br>\n", text(Label::InvoiceFor)));
+        content.push_str(&format!("{}\n", escape_html(&client.name)));
         
-        // Middle column - Payable to
-        content.push_str("<div style=\"width: 40%;\">\n");
-        content.push_str("**Payable to**<br>\n");
-        content.push_str(&format!("{}<br>\n", profile.name));
-        content.push_str("<br>\n");
-        content.push_str("**Account Number**<br>\n");
-        content.push_str(&format!("{}<br>\n", profile.bank_account_number));
-        content.push_str("<br>\n");
-        content.push_str("**Routing Number**<br>\n");
-        content.push_str(&format!("{}\n", profile.bank_routing_number));
+        // Per-invoice billing address takes precedence over the client's address
+        let bill_to = invoice.bill_to_override.as_ref().or(client.address.as_ref());
+        if let Some(bill_to) = bill_to.filter(|address| !address.trim().is_empty()) {
+            for line in bill_to.lines() {
+                content.push_str(&format!("<br>{}\n", escape_html(line)));
+            }
+        }
         content.push_str("</div>\n");
         
+        // Middle column - Payable to, omitted for clients that don't pay by bank transfer
+        if client.show_bank_details {
+            content.push_str("<div style=\"width: 40%;\">\n");
+            content.push_str(&format!("**{}**<br>\n", text(Label::PayableTo)));
+            content.push_str(&format!("{}<br>\n", escape_html(&profile.name)));
+            content.push_str("<br>\n");
+            content.push_str(&format!("**{}**<br>\n", text(Label::AccountNumber)));
+            content.push_str(&format!("{}<br>\n", escape_html(&profile.bank_account_number)));
+            content.push_str("<br>\n");
+            content.push_str(&format!("**{}**<br>\n", text(Label::RoutingNumber)));
+            content.push_str(&format!("{}\n", escape_html(&profile.bank_routing_number)));
+            content.push_str("</div>\n");
+        }
+        
         // Right column - Invoice number
         content.push_str("<div style=\"width: 30%;\">\n");
-        content.push_str("**Invoice #**<br>\n");
+        content.push_str(&format!("**{}**<br>\n", text(Label::InvoiceNumber)));
         content.push_str(&format!("{}\n", invoice.number));
         content.push_str("</div>\n");
         
@@ -150,39 +517,344 @@ impl InvoiceGenerator {
         // Add line items table with better formatting
         content.push_str("<table style=\"width: 100%; border-collapse: collapse;\">\n");
         
-        // Table header
+        // Table header, flat-fee invoices only have an amount per line item
         content.push_str("<tr>\n");
-        content.push_str("<th style=\"text-align: left;\">Description</th>\n");
-        content.push_str("<th style=\"text-align: right;\">Hours</th>\n");
-        content.push_str("<th style=\"text-align: right;\">Hourly rate</th>\n");
-        content.push_str("<th style=\"text-align: right;\">Total price</th>\n");
+        content.push_str("<th style=\"text-align: left;\">#</th>\n");
+        content.push_str(&format!("<th style=\"text-align: left;\">{}</th>\n", text(Label::Description)));
+        if invoice.is_flat_fee() {
+            content.push_str(&format!("<th style=\"text-align: right;\">{}</th>\n", text(Label::Amount)));
+        } else {
+            content.push_str(&format!("<th style=\"text-align: right;\">{}</th>\n", text(Label::Hours)));
+            content.push_str(&format!("<th style=\"text-align: right;\">{}</th>\n", text(Label::HourlyRate)));
+            content.push_str(&format!("<th style=\"text-align: right;\">{}</th>\n", text(Label::TotalPrice)));
+        }
         content.push_str("</tr>\n");
         
-        let mut total_hours = 0.0;
-        let mut total_amount = 0.0;
-        
-        // Table rows for each line item
-        for item in line_items {
-            let amount = item.hours * invoice.rate;
-            total_hours += item.hours;
-            total_amount += amount;
+        // Table rows for each line item, numbered in the configured display order
+        for (i, item) in sort_line_items(line_items, self.line_item_sort).into_iter().enumerate() {
+            let amount = invoice.line_item_amount(item);
             
+            if self.zebra_stripes && i % 2 == 1 {
+                content.push_str(&format!("<tr style=\"background-color: {};\">\n", ZEBRA_STRIPE_COLOR));
+            } else {
+                content.push_str("<tr>\n");
+            }
+            content.push_str(&format!("<td style=\"text-align: left;\">{}</td>\n", i + 1));
+            content.push_str(&format!("<td style=\"text-align: left;\">{}</td>\n", escape_html(&item.description)));
+            if !invoice.is_flat_fee() && item.is_fixed() {
+                content.push_str("<td style=\"text-align: right;\"></td>\n");
+                content.push_str("<td style=\"text-align: right;\"></td>\n");
+            } else if !invoice.is_flat_fee() {
+                content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", item.hours));
+                content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", money::format_money_in(item.effective_rate(invoice.rate), currency)));
+            }
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", money::format_money_in(amount, currency)));
+            content.push_str("</tr>\n");
+        }
+        
+        // Taxed invoices show the subtotal and tax above the total
+        let label_columns = if invoice.is_flat_fee() { 2 } else { 4 };
+        let breakdown = money::compute_breakdown(invoice, line_items);
+        let total_amount = breakdown.total;
+        if let Some((tax_rate, tax)) = breakdown.tax {
+            content.push_str("<tr>\n");
+            content.push_str(&format!("<td colspan=\"{}\" style=\"text-align: right;\">{}</td>\n", label_columns, text(Label::Subtotal)));
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", money::format_money_in(breakdown.subtotal, currency)));
+            content.push_str("</tr>\n");
             content.push_str("<tr>\n");
-            content.push_str(&format!("<td style=\"text-align: left;\">{}</td>\n", item.description));
-            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", item.hours));
-            content.push_str(&format!("<td style=\"text-align: right;\">${:.2}</td>\n", invoice.rate));
-            content.push_str(&format!("<td style=\"text-align: right;\">${:.2}</td>\n", amount));
+            content.push_str(&format!("<td colspan=\"{}\" style=\"text-align: right;\">{} ({}%)</td>\n", label_columns, text(Label::Tax), money::format_tax_rate(tax_rate)));
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", money::format_money_in(tax, currency)));
             content.push_str("</tr>\n");
         }
         
         // Add total row
         content.push_str("<tr>\n");
-        content.push_str("<td colspan=\"3\" style=\"text-align: right;\">Total</td>\n");
-        content.push_str(&format!("<td style=\"text-align: right; font-weight: bold; color: #e83e8c;\"><h2>${:.2}</h2></td>\n", total_amount));
+        content.push_str(&format!("<td colspan=\"{}\" style=\"text-align: right;\">{}</td>\n", label_columns, text(Label::Total)));
+        content.push_str(&format!("<td style=\"text-align: right; font-weight: bold; color: {};\"><h2>{}</h2></td>\n", accent_color, money::format_money_in(total_amount, currency)));
         content.push_str("</tr>\n");
         
+        // Partially paid invoices show what's left to pay
+        let amount_paid = money::to_decimal(balances.amount_paid);
+        let balance_due = total_amount - amount_paid;
+        if amount_paid > Decimal::ZERO {
+            content.push_str("<tr>\n");
+            content.push_str(&format!("<td colspan=\"{}\" style=\"text-align: right;\">{}</td>\n", label_columns, text(Label::AmountPaid)));
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", money::format_money_in(amount_paid, currency)));
+            content.push_str("</tr>\n");
+            content.push_str("<tr>\n");
+            content.push_str(&format!("<td colspan=\"{}\" style=\"text-align: right;\">{}</td>\n", label_columns, text(Label::BalanceDue)));
+            content.push_str(&format!("<td style=\"text-align: right; font-weight: bold; color: {};\">{}</td>\n", accent_color, money::format_money_in(balance_due, currency)));
+            content.push_str("</tr>\n");
+        }
+        
         content.push_str("</table>\n");
         
+        // Add running statement with the client's prior unpaid balance
+        if let Some(previous_balance) = balances.previous_balance {
+            content.push_str("\n<table style=\"width: 100%; border-collapse: collapse;\">\n");
+            content.push_str("<tr>\n");
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", text(Label::PreviousBalance)));
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", money::format_money_in(previous_balance, currency)));
+            content.push_str("</tr>\n");
+            content.push_str("<tr>\n");
+            content.push_str(&format!("<td style=\"text-align: right;\">{}</td>\n", text(Label::TotalDueIncludingPrior)));
+            content.push_str(&format!("<td style=\"text-align: right; font-weight: bold; color: {};\">{}</td>\n", accent_color, money::format_money_in(money::to_decimal(previous_balance) + balance_due, currency)));
+            content.push_str("</tr>\n");
+            content.push_str("</table>\n");
+        }
+        
+        // Notes for the client, such as payment terms, close the invoice
+        if let Some(notes) = invoice.notes.as_ref().filter(|notes| !notes.trim().is_empty()) {
+            let lines: Vec<String> = notes.trim_end().lines().map(escape_html).collect();
+            content.push_str(&format!("\n<p>{}</p>\n", lines.join("<br>\n")));
+        }
+        
         Ok(content)
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, invoice, line_item};
+
+    fn generator() -> InvoiceGenerator {
+        InvoiceGenerator {
+            output_dir: "invoices".to_string(),
+            line_item_sort: LineItemSort::AsEntered,
+            zebra_stripes: false,
+        }
+    }
+
+    fn markdown_with(generator: &InvoiceGenerator, invoice: &Invoice, line_items: &[InvoiceLineItem], client: &Client) -> String {
+        let profile = test_support::profile("Studio");
+        let project = test_support::project(client.id, "Website");
+        generator.generate_markdown(invoice, line_items, &profile, client, &project, Balances::default()).unwrap()
+    }
+
+    fn markdown(invoice: &Invoice, line_items: &[InvoiceLineItem]) -> String {
+        markdown_with(&generator(), invoice, line_items, &test_support::client(1, "Acme"))
+    }
+
+    // Descriptions in the order their rows appear in the generated table
+    fn rendered_order(content: &str, descriptions: &[&str]) -> Vec<String> {
+        let mut found: Vec<(usize, String)> = descriptions
+            .iter()
+            .map(|description| (content.find(&format!(">{}</td>", description)).unwrap(), description.to_string()))
+            .collect();
+        found.sort();
+        found.into_iter().map(|(_, description)| description).collect()
+    }
+
+    #[test]
+    fn line_items_render_in_the_configured_order() {
+        let items = [line_item(1, "design", 2.0), line_item(2, "Build", 8.0), line_item(3, "Audit", 0.5)];
+        let descriptions = ["design", "Build", "Audit"];
+        let expected = [
+            (LineItemSort::AsEntered, ["design", "Build", "Audit"]),
+            (LineItemSort::Description, ["Audit", "Build", "design"]),
+            (LineItemSort::HoursDesc, ["Build", "design", "Audit"]),
+        ];
+
+        for (sort, order) in expected {
+            let generator = InvoiceGenerator { line_item_sort: sort, ..generator() };
+            let content = markdown_with(&generator, &invoice(1), &items, &test_support::client(1, "Acme"));
+            assert_eq!(rendered_order(&content, &descriptions), order, "{:?}", sort);
+            // Sorting only moves rows, the total stays the same
+            assert!(content.contains("$1050.00</h2>"), "{:?}", sort);
+        }
+    }
+
+    #[test]
+    fn line_items_are_numbered_from_one_in_display_order() {
+        let items = [line_item(1, "design", 2.0), line_item(2, "Build", 8.0), line_item(3, "Audit", 0.5)];
+        let generator = InvoiceGenerator { line_item_sort: LineItemSort::HoursDesc, ..generator() };
+        let content = markdown_with(&generator, &invoice(1), &items, &test_support::client(1, "Acme"));
+
+        for (number, description) in ["Build", "design", "Audit"].iter().enumerate() {
+            let row = format!("<td style=\"text-align: left;\">{}</td>\n<td style=\"text-align: left;\">{}</td>", number + 1, description);
+            assert!(content.contains(&row), "{}", row);
+        }
+        assert!(!content.contains("<td style=\"text-align: left;\">4</td>"));
+    }
+
+    #[test]
+    fn labels_follow_the_client_language() {
+        let client = Client { language: "de".to_string(), ..test_support::client(1, "Acme") };
+        let content = markdown_with(&generator(), &invoice(1), &[line_item(1, "Work", 1.0)], &client);
+
+        assert!(content.contains(">Beschreibung</th>"));
+        assert!(content.contains(">Stunden</th>"));
+        assert!(content.contains("**Zahlbar an**"));
+        assert!(!content.contains(">Description</th>"));
+        assert!(!content.contains("Payable to"));
+    }
+
+    #[test]
+    fn bill_to_override_replaces_the_client_address() {
+        let overridden = Invoice { bill_to_override: Some("Accounts Payable\n9 Other Rd".to_string()), ..invoice(1) };
+        let content = markdown(&overridden, &[line_item(1, "Work", 1.0)]);
+        assert!(content.contains("<br>Accounts Payable\n<br>9 Other Rd\n"));
+        assert!(!content.contains("2 Side St"));
+    }
+
+    #[test]
+    fn client_address_is_used_without_an_override() {
+        let content = markdown(&invoice(1), &[line_item(1, "Work", 1.0)]);
+        assert!(content.contains("<br>2 Side St\n"));
+    }
+
+    #[test]
+    fn escape_html_neutralises_markup() {
+        assert_eq!(escape_html("<script>alert(1)</script>"), "&lt;script&gt;alert(1)&lt;/script&gt;");
+        assert_eq!(escape_html("Design & Build"), "Design &amp; Build");
+        assert_eq!(escape_html(r#"The "final" client's copy"#), "The &quot;final&quot; client&#39;s copy");
+        assert_eq!(escape_html("Plain text"), "Plain text");
+    }
+
+    #[test]
+    fn descriptions_are_escaped_but_the_generated_markup_is_not() {
+        let content = markdown(&invoice(1), &[line_item(1, "<b>Q&A</b>", 1.0)]);
+        assert!(content.contains("<td style=\"text-align: left;\">&lt;b&gt;Q&amp;A&lt;/b&gt;</td>"));
+        assert!(!content.contains("&amp;lt;"));
+    }
+
+    #[test]
+    fn bank_details_are_left_out_when_the_client_opts_out() {
+        let items = [line_item(1, "Work", 1.0)];
+        let with_bank = markdown(&invoice(1), &items);
+        assert!(with_bank.contains("12345678"));
+
+        let client = Client { show_bank_details: false, ..test_support::client(1, "Acme") };
+        let without_bank = markdown_with(&generator(), &invoice(1), &items, &client);
+        assert!(!without_bank.contains("12345678"));
+        assert!(!without_bank.contains("87654321"));
+        assert!(without_bank.contains("2 Side St"));
+    }
+
+    #[test]
+    fn flat_fee_invoices_render_an_amount_column() {
+        let flat = Invoice { billing_type: "Flat".to_string(), ..invoice(1) };
+        let items = [InvoiceLineItem { amount: 1500.0, ..line_item(1, "Website", 0.0) }];
+        let content = markdown(&flat, &items);
+
+        assert!(content.contains("<th style=\"text-align: right;\">Amount</th>"));
+        assert!(!content.contains("Hours</th>"));
+        assert!(content.contains("<td style=\"text-align: left;\">Website</td>\n<td style=\"text-align: right;\">$1500.00</td>"));
+        assert!(content.contains("$1500.00</h2>"));
+    }
+
+    #[test]
+    fn probe_reports_present_and_missing_binaries() {
+        assert!(command_available(env!("CARGO")));
+        assert!(!command_available("invoice-manager-missing-binary"));
+    }
+
+    #[test]
+    fn a_partially_paid_invoice_shows_the_balance_due() {
+        let profile = test_support::profile("Studio");
+        let client = test_support::client(1, "Acme");
+        let project = test_support::project(1, "Website");
+        let balances = Balances { previous_balance: None, amount_paid: 120.0 };
+        let content = generator()
+            .generate_markdown(&invoice(1), &[line_item(1, "Work", 3.0)], &profile, &client, &project, balances)
+            .unwrap();
+
+        assert!(content.contains("Amount Paid</td>\n<td style=\"text-align: right;\">$120.00</td>"));
+        assert!(content.contains("Balance Due</td>"));
+        assert!(content.contains(">$180.00</td>"));
+        // Unpaid invoices only show the total
+        assert!(!markdown(&invoice(1), &[line_item(1, "Work", 3.0)]).contains("Balance Due"));
+    }
+
+    #[test]
+    fn each_row_shows_its_effective_rate() {
+        let items = [InvoiceLineItem { rate_override: Some(150.0), ..line_item(1, "Senior", 2.0) }, line_item(2, "Junior", 3.0)];
+        let content = markdown(&invoice(1), &items);
+
+        assert!(content.contains(">Senior</td>\n<td style=\"text-align: right;\">2</td>\n<td style=\"text-align: right;\">$150.00</td>\n<td style=\"text-align: right;\">$300.00</td>"));
+        assert!(content.contains(">Junior</td>\n<td style=\"text-align: right;\">3</td>\n<td style=\"text-align: right;\">$100.00</td>\n<td style=\"text-align: right;\">$300.00</td>"));
+        assert!(content.contains("$600.00</h2>"));
+    }
+
+    #[test]
+    fn internal_notes_never_appear_in_the_invoice() {
+        let noted = Invoice { internal_notes: Some("Client disputes item 2\nChase in March".to_string()), ..invoice(1) };
+        let items = [line_item(1, "Work", 1.0)];
+
+        let content = markdown(&noted, &items);
+        assert!(!content.contains("disputes"));
+        assert!(!content.contains("Chase in March"));
+        assert!(!generate_plaintext(&noted, &items, LineItemSort::AsEntered, "USD").contains("disputes"));
+    }
+
+    #[test]
+    fn zebra_stripes_shade_every_other_row() {
+        let items = [line_item(1, "One", 1.0), line_item(2, "Two", 1.0), line_item(3, "Three", 1.0), line_item(4, "Four", 1.0)];
+        let striped_row = format!("<tr style=\"background-color: {};\">\n", ZEBRA_STRIPE_COLOR);
+
+        let generator = InvoiceGenerator { zebra_stripes: true, ..generator() };
+        let content = markdown_with(&generator, &invoice(1), &items, &test_support::client(1, "Acme"));
+        for (number, striped) in [(1, false), (2, true), (3, false), (4, true)] {
+            let row = format!("<td style=\"text-align: left;\">{}</td>", number);
+            let row_start = content[..content.find(&row).unwrap()].rfind("<tr").unwrap();
+            assert_eq!(content[row_start..].starts_with(&striped_row), striped, "row {}", number);
+        }
+        assert_eq!(content.matches(&striped_row).count(), 2);
+
+        assert!(!markdown(&invoice(1), &items).contains(ZEBRA_STRIPE_COLOR));
+    }
+
+    #[test]
+    fn profile_colors_replace_the_default_theme() {
+        let mut profile = test_support::profile("Studio");
+        profile.header_color = "#112233".to_string();
+        profile.accent_color = "#abcdef".to_string();
+        let client = test_support::client(1, "Acme");
+        let project = test_support::project(client.id, "Website");
+
+        let content = generator()
+            .generate_markdown(&invoice(1), &[line_item(1, "Work", 1.0)], &profile, &client, &project, Balances::default())
+            .unwrap();
+        assert!(content.contains("background-color: #112233;"));
+        assert!(content.contains("color: #abcdef;\"><h2>$100.00</h2>"));
+        assert!(!content.contains(crate::models::DEFAULT_HEADER_COLOR));
+        assert!(!content.contains(crate::models::DEFAULT_ACCENT_COLOR));
+    }
+
+    // Bytes of a PDF written by write_text_pdf and its number of pages
+    fn written_pdf(documents: &[String]) -> (Vec<u8>, usize) {
+        static WRITTEN: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = WRITTEN.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("text-pdf-{}-{}.pdf", std::process::id(), n));
+        write_text_pdf(path.to_str().unwrap(), documents).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let pages = String::from_utf8_lossy(&bytes).matches("/Type /Page\n").count();
+        (bytes, pages)
+    }
+
+    #[test]
+    fn text_pdfs_are_real_pdfs() {
+        let (bytes, pages) = written_pdf(&["Invoice #1\nTotal: $100.00".to_string()]);
+        assert!(bytes.starts_with(b"%PDF"));
+        assert_eq!(pages, 1);
+    }
+
+    #[test]
+    fn long_text_continues_onto_more_pages() {
+        let lines_per_page = ((PDF_PAGE_HEIGHT - 2.0 * PDF_MARGIN) / PDF_LEADING) as usize;
+        let full_page = vec!["Line"; lines_per_page].join("\n");
+        let overflowing = vec!["Line"; lines_per_page + 1].join("\n");
+
+        assert_eq!(written_pdf(std::slice::from_ref(&full_page)).1, 1);
+        assert_eq!(written_pdf(&[overflowing.clone(), String::new()]).1, 2);
+        // Every document starts on a page of its own
+        assert_eq!(written_pdf(&[overflowing, full_page, "Short".to_string()]).1, 4);
+    }
+
+    #[test]
+    fn characters_outside_win_ansi_become_question_marks() {
+        assert_eq!(win_ansi_bytes("Total: €5 é"), b"Total: \x805 \xe9");
+        assert_eq!(win_ansi_bytes("✓ 中 \t"), b"? ? ?");
+    }
+}
@@ -0,0 +1,153 @@
+/// Text printed on a generated invoice, translated by the client's language
+#[derive(Clone, Copy)]
+pub enum Label {
+    Invoice,
+    SubmittedOn,
+    InvoiceFor,
+    PayableTo,
+    AccountNumber,
+    RoutingNumber,
+    InvoiceNumber,
+    Description,
+    Hours,
+    HourlyRate,
+    TotalPrice,
+    Amount,
+    Subtotal,
+    Tax,
+    Total,
+    PreviousBalance,
+    TotalDueIncludingPrior,
+    AmountPaid,
+    BalanceDue,
+}
+
+/// Language invoices are printed in when the client doesn't set one
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Label in the given ISO 639-1 language, falling back to English when the language
+/// or this particular label hasn't been translated
+pub fn label(language: &str, label: Label) -> &'static str {
+    let translated = match language.trim().to_lowercase().as_str() {
+        "es" => spanish(label),
+        "fr" => french(label),
+        "de" => german(label),
+        _ => None,
+    };
+    translated.unwrap_or_else(|| english(label))
+}
+
+fn english(label: Label) -> &'static str {
+    match label {
+        Label::Invoice => "Invoice",
+        Label::SubmittedOn => "Submitted on",
+        Label::InvoiceFor => "Invoice for",
+        Label::PayableTo => "Payable to",
+        Label::AccountNumber => "Account Number",
+        Label::RoutingNumber => "Routing Number",
+        Label::InvoiceNumber => "Invoice #",
+        Label::Description => "Description",
+        Label::Hours => "Hours",
+        Label::HourlyRate => "Hourly rate",
+        Label::TotalPrice => "Total price",
+        Label::Amount => "Amount",
+        Label::Subtotal => "Subtotal",
+        Label::Tax => "Tax",
+        Label::Total => "Total",
+        Label::PreviousBalance => "Previous Balance",
+        Label::TotalDueIncludingPrior => "Total Due Including Prior",
+        Label::AmountPaid => "Amount Paid",
+        Label::BalanceDue => "Balance Due",
+    }
+}
+
+fn spanish(label: Label) -> Option<&'static str> {
+    Some(match label {
+        Label::Invoice => "Factura",
+        Label::SubmittedOn => "Emitida el",
+        Label::InvoiceFor => "Facturar a",
+        Label::PayableTo => "Pagadero a",
+        Label::AccountNumber => "Número de cuenta",
+        Label::RoutingNumber => "Número de ruta",
+        Label::InvoiceNumber => "Factura n.º",
+        Label::Description => "Descripción",
+        Label::Hours => "Horas",
+        Label::HourlyRate => "Tarifa por hora",
+        Label::TotalPrice => "Precio total",
+        Label::Amount => "Importe",
+        Label::Subtotal => "Subtotal",
+        Label::Tax => "IVA",
+        Label::Total => "Total",
+        Label::PreviousBalance => "Saldo anterior",
+        Label::TotalDueIncludingPrior => "Total adeudado con saldo anterior",
+        Label::AmountPaid => "Importe pagado",
+        Label::BalanceDue => "Saldo pendiente",
+    })
+}
+
+fn french(label: Label) -> Option<&'static str> {
+    Some(match label {
+        Label::Invoice => "Facture",
+        Label::SubmittedOn => "Émise le",
+        Label::InvoiceFor => "Facturé à",
+        Label::PayableTo => "À l'ordre de",
+        Label::AccountNumber => "Numéro de compte",
+        Label::RoutingNumber => "Code banque",
+        Label::InvoiceNumber => "Facture n°",
+        Label::Description => "Description",
+        Label::Hours => "Heures",
+        Label::HourlyRate => "Taux horaire",
+        Label::TotalPrice => "Prix total",
+        Label::Amount => "Montant",
+        Label::Subtotal => "Sous-total",
+        Label::Tax => "TVA",
+        Label::Total => "Total",
+        Label::PreviousBalance => "Solde précédent",
+        Label::TotalDueIncludingPrior => "Total dû, solde précédent inclus",
+        Label::AmountPaid => "Montant payé",
+        Label::BalanceDue => "Solde dû",
+    })
+}
+
+fn german(label: Label) -> Option<&'static str> {
+    Some(match label {
+        Label::Invoice => "Rechnung",
+        Label::SubmittedOn => "Ausgestellt am",
+        Label::InvoiceFor => "Rechnung an",
+        Label::PayableTo => "Zahlbar an",
+        Label::AccountNumber => "Kontonummer",
+        Label::RoutingNumber => "Bankleitzahl",
+        Label::InvoiceNumber => "Rechnungsnr.",
+        Label::Description => "Beschreibung",
+        Label::Hours => "Stunden",
+        Label::HourlyRate => "Stundensatz",
+        Label::TotalPrice => "Gesamtpreis",
+        Label::Amount => "Betrag",
+        Label::Subtotal => "Zwischensumme",
+        Label::Tax => "MwSt.",
+        Label::Total => "Summe",
+        Label::PreviousBalance => "Vorheriger Saldo",
+        Label::TotalDueIncludingPrior => "Gesamtbetrag inkl. Vorsaldo",
+        Label::AmountPaid => "Bereits bezahlt",
+        Label::BalanceDue => "Offener Betrag",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_translated_by_language_code() {
+        assert_eq!(label("de", Label::Hours), "Stunden");
+        assert_eq!(label(" FR ", Label::Invoice), "Facture");
+        assert_eq!(label("es", Label::BalanceDue), "Saldo pendiente");
+    }
+
+    #[test]
+    fn unknown_languages_fall_back_to_english() {
+        assert_eq!(label("pt", Label::PayableTo), "Payable to");
+        assert_eq!(label("", Label::Total), "Total");
+        assert_eq!(label(DEFAULT_LANGUAGE, Label::Total), "Total");
+    }
+}
@@ -1,30 +1,45 @@
 mod config;
 mod db;
+mod error;
 mod models;
 mod ui;
 mod invoice_gen;
+mod export;
+mod import;
+mod profile_cache;
+mod money;
+mod labels;
+#[cfg(test)]
+mod test_support;
 
 use std::io;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
 };
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
 
+use crate::error::AppError;
 use crate::ui::{
     profiles::{ProfilesState, ProfileAction, render_profiles, handle_input as handle_profiles_input},
-    clients::{ClientsState, ClientAction, render_clients, handle_input as handle_clients_input, load_clients_by_profile},
-    projects::{ProjectsState, ProjectAction, render_projects, handle_input as handle_projects_input, load_projects_by_client},
-    invoices::{InvoicesState, InvoiceAction, render_invoices, handle_input as handle_invoices_input, load_invoices_by_project},
-    invoice_wizard::{InvoiceWizardState, InvoiceWizardAction, render_invoice_wizard, handle_input as handle_invoice_wizard_input, save_invoice_with_line_items, get_invoice_with_line_items},
+    clients::{ClientsState, ClientAction, render_clients, handle_input as handle_clients_input, load_clients_by_profile, load_client_outstanding_totals},
+    projects::{ProjectsState, ProjectAction, render_projects, handle_input as handle_projects_input, load_projects_by_client, load_project_summaries},
+    time_entries_preview::groups_to_line_items,
+    invoices::{InvoicesState, InvoiceAction, render_invoices, handle_input as handle_invoices_input, load_invoice_page, load_invoice_totals, load_invoice_paid_amounts, delete_invoice},
+    email_wizard::test_smtp_connection,
+    invoice_wizard::{InvoiceWizardState, InvoiceWizardAction, shift_months, render_invoice_wizard, handle_input as handle_invoice_wizard_input, save_invoice_with_line_items, get_invoice_with_line_items},
     profile_wizard::{ProfileWizardState, ProfileWizardAction, render_profile_wizard, handle_input as handle_profile_wizard_input},
     client_wizard::{ClientWizardState, ClientWizardAction, render_client_wizard, handle_input as handle_client_wizard_input},
     project_wizard::{ProjectWizardState, ProjectWizardAction, render_project_wizard, handle_input as handle_project_wizard_input},
+    settings::{SettingsState, SettingsAction, render_settings, handle_input as handle_settings_input},
+    report::{ReportState, ReportAction, render_report, handle_input as handle_report_input},
+    components::confirm::render_error,
 };
 
 // Represents the current screen in the app
@@ -37,12 +52,21 @@ enum AppScreen {
     ProjectWizard(i32),   // Contains client_id
     Invoices(i32),        // Contains project_id
     InvoiceWizard(i32, Option<i32>),  // Contains project_id and optional invoice_id
+    Settings,
+    Report,
 }
 
 // Main application state
 struct AppState {
     db: db::Database,
+    config: config::Config,
     screen: AppScreen,
+    profile_ids: Vec<i32>,          // Ordered profile ids for quick switching
+    current_profile: Option<usize>, // Index into profile_ids of the active profile
+    pandoc_available: bool,         // Whether invoices can be converted to real PDFs
+    clipboard: Option<arboard::Clipboard>, // Kept open so copied text stays available on Linux
+    cancel: CancellationToken,      // Cancelled on exit, shared with spawned tasks
+    tasks: TaskTracker,             // Spawned tasks, awaited on exit
     profiles_state: Option<ProfilesState>,
     profile_wizard_state: Option<ProfileWizardState>,
     clients_state: Option<ClientsState>,
@@ -51,13 +75,23 @@ struct AppState {
     project_wizard_state: Option<ProjectWizardState>,
     invoices_state: Option<InvoicesState>,
     invoice_wizard_state: Option<InvoiceWizardState>,
+    settings_state: Option<SettingsState>,
+    report_state: Option<ReportState>,
+    error_message: Option<String>,  // Recoverable error shown over the current screen
 }
 
 impl AppState {
-    fn new(db: db::Database) -> Self {
+    fn new(db: db::Database, config: config::Config) -> Self {
         Self {
             db,
+            config,
             screen: AppScreen::Profiles,
+            profile_ids: Vec::new(),
+            current_profile: None,
+            pandoc_available: false,
+            clipboard: None,
+            cancel: CancellationToken::new(),
+            tasks: TaskTracker::new(),
             profiles_state: None,
             profile_wizard_state: None,
             clients_state: None,
@@ -66,6 +100,9 @@ impl AppState {
             project_wizard_state: None,
             invoices_state: None,
             invoice_wizard_state: None,
+            settings_state: None,
+            report_state: None,
+            error_message: None,
         }
     }
 }
@@ -76,10 +113,6 @@ async fn main() -> Result<()> {
     let config = config::init()?;
     println!("Initializing invoice manager...");
     
-    // Initialize database connection
-    let db = db::init(&config).await?;
-    println!("Database connection established");
-    
     // Setup terminal
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -87,14 +120,17 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     
-    // Create app state
-    let mut app_state = AppState::new(db);
-    
-    // Initialize the profiles state
-    load_profiles_screen(&mut app_state).await?;
+    // Show the cached profiles list while the database connection is established
+    let cached_profiles = config.cache_profiles
+        .then(|| profile_cache::load(&config.profile_cache_path))
+        .flatten();
+    if let Some(profiles) = cached_profiles {
+        let mut cached_state = ProfilesState::new(profiles);
+        terminal.draw(|f| render_profiles(f, &mut cached_state))?;
+    }
     
-    // Run the main app loop
-    let result = run_app(&mut terminal, &mut app_state).await;
+    // Connect to the database and run the main app loop
+    let result = start_app(&mut terminal, config).await;
     
     // Restore terminal
     terminal::disable_raw_mode()?;
@@ -107,7 +143,7 @@ async fn main() -> Result<()> {
     
     // Show any error message
     if let Err(err) = result {
-        println!("Error: {}", err);
+        println!("Error: {:#}", err);
     }
     
     println!("Thanks for using Invoice Manager!");
@@ -115,6 +151,55 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+async fn start_app<B: Backend>(terminal: &mut Terminal<B>, config: config::Config) -> Result<()> {
+    // Initialize database connection
+    let db = db::init(&config).await?;
+    
+    // Create app state
+    let mut app_state = AppState::new(db, config);
+    
+    // Initialize the profiles state, replacing any cached list on screen
+    load_profiles_screen(&mut app_state).await?;
+    
+    // Let the user know up front when PDFs will fall back to plain text
+    app_state.pandoc_available = invoice_gen::pandoc_available();
+    if let Some(state) = app_state.profiles_state.as_mut().filter(|_| !app_state.pandoc_available) {
+        state.show_notice(
+            "PDF Generation",
+            "pandoc was not found. Emailed invoices will attach a plain text PDF without the invoice layout. Install pandoc (https://pandoc.org/installing.html) to generate formatted PDFs.".to_string(),
+        );
+    }
+    
+    let result = run_app(terminal, &mut app_state).await;
+    
+    // Clean up even when the app loop failed. Errors are returned rather than printed so
+    // they're shown once the terminal is restored.
+    let cleanup = shutdown(&mut app_state).await;
+    
+    match (result, cleanup) {
+        (Err(e), Err(cleanup_error)) => Err(anyhow::anyhow!("{:#}\nError: {:#}", e, cleanup_error)),
+        (result, cleanup) => result.and(cleanup),
+    }
+}
+
+// Finish outstanding work before exiting: cancel spawned tasks and wait for them to wind
+// down, remove any generated invoice files left by an open email wizard and let in-flight
+// queries complete before the connections close
+async fn shutdown(app_state: &mut AppState) -> Result<()> {
+    app_state.cancel.cancel();
+    app_state.tasks.close();
+    app_state.tasks.wait().await;
+    
+    let cleanup = match &mut app_state.invoices_state {
+        Some(state) => state.force_close_email_wizard().await,
+        None => Ok(()),
+    };
+    
+    app_state.db.close().await;
+    
+    cleanup.context("Cleaning up generated invoice files failed")
+}
+
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app_state: &mut AppState) -> Result<()> {
     loop {
         // Render current screen
@@ -160,22 +245,47 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app_state: &mut AppStat
                         render_invoice_wizard(f, state);
                     }
                 }
+                AppScreen::Settings => {
+                    if let Some(state) = &mut app_state.settings_state {
+                        render_settings(f, state);
+                    }
+                }
+                AppScreen::Report => {
+                    if let Some(state) = &mut app_state.report_state {
+                        render_report(f, state);
+                    }
+                }
+            }
+            
+            if let Some(error) = &app_state.error_message {
+                render_error(f, "Something Went Wrong", error);
             }
         })?;
         
+        // Any key dismisses the error, without reaching the screen underneath
+        if app_state.error_message.is_some() {
+            if let Event::Key(_) = event::read()? {
+                app_state.error_message = None;
+            }
+            continue;
+        }
+        
         // Handle input for current screen
-        let should_quit = match app_state.screen {
-            AppScreen::Profiles => handle_profiles_screen(app_state).await?,
-            AppScreen::ProfileWizard => handle_profile_wizard_screen(app_state).await?,
-            AppScreen::Clients(_) => handle_clients_screen(app_state).await?,
-            AppScreen::ClientWizard(_) => handle_client_wizard_screen(app_state).await?,
-            AppScreen::Projects(_) => handle_projects_screen(app_state).await?,
-            AppScreen::ProjectWizard(_) => handle_project_wizard_screen(app_state).await?,
-            AppScreen::Invoices(_) => handle_invoices_screen(app_state).await?,
-            AppScreen::InvoiceWizard(_, _) => handle_invoice_wizard_screen(app_state).await?,
+        let result = match app_state.screen {
+            AppScreen::Profiles => handle_profiles_screen(app_state).await,
+            AppScreen::ProfileWizard => handle_profile_wizard_screen(app_state).await,
+            AppScreen::Clients(_) => handle_clients_screen(app_state).await,
+            AppScreen::ClientWizard(_) => handle_client_wizard_screen(app_state).await,
+            AppScreen::Projects(_) => handle_projects_screen(app_state).await,
+            AppScreen::ProjectWizard(_) => handle_project_wizard_screen(app_state).await,
+            AppScreen::Invoices(_) => handle_invoices_screen(app_state).await,
+            AppScreen::InvoiceWizard(_, _) => handle_invoice_wizard_screen(app_state).await,
+            AppScreen::Settings => handle_settings_screen(app_state).await,
+            AppScreen::Report => handle_report_screen(app_state).await,
         };
         
-        if should_quit {
+        // Show recoverable errors, such as a failed query, and keep the session going
+        if recover(result, &mut app_state.error_message)? {
             break;
         }
     }
@@ -183,9 +293,41 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app_state: &mut AppStat
     Ok(())
 }
 
+// Whether to quit after a screen handled its input. Recoverable errors are kept to be shown
+// over the screen rather than ending the session, fatal ones are returned.
+fn recover(result: Result<bool>, error_message: &mut Option<String>) -> Result<bool> {
+    match result {
+        Ok(should_quit) => Ok(should_quit),
+        Err(e) if is_fatal(&e) => Err(e),
+        Err(e) => {
+            *error_message = Some(format!("{:#}", e));
+            Ok(false)
+        }
+    }
+}
+
+// Terminal I/O failures leave nothing to draw on or read keys from. File errors are
+// io::Errors too but only fail the one operation, like everything else.
+fn is_fatal(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<io::Error>().is_some_and(|e| !matches!(
+        e.kind(),
+        io::ErrorKind::NotFound |
+        io::ErrorKind::PermissionDenied |
+        io::ErrorKind::AlreadyExists |
+        io::ErrorKind::InvalidInput |
+        io::ErrorKind::InvalidData
+    ))
+}
+
 async fn load_profiles_screen(app_state: &mut AppState) -> Result<()> {
     // Load profiles from database
     let profiles = app_state.db.load_profiles().await?;
+    app_state.profile_ids = profiles.iter().map(|p| p.id).collect();
+    
+    // Keep the startup cache in sync; a stale cache is harmless so failures are ignored
+    if app_state.config.cache_profiles {
+        profile_cache::save(&app_state.config.profile_cache_path, &profiles).ok();
+    }
     
     // Create profiles state
     app_state.profiles_state = Some(ProfilesState::new(profiles));
@@ -194,6 +336,41 @@ async fn load_profiles_screen(app_state: &mut AppState) -> Result<()> {
     Ok(())
 }
 
+// Switch to the previous or next profile and show its clients
+async fn switch_profile(app_state: &mut AppState, forward: bool) -> Result<()> {
+    let count = app_state.profile_ids.len();
+    if count == 0 {
+        return Ok(());
+    }
+    
+    let index = match app_state.current_profile {
+        Some(i) if forward => (i + 1) % count,
+        Some(i) => (i + count - 1) % count,
+        None => 0,
+    };
+    let profile_id = app_state.profile_ids[index];
+    app_state.current_profile = Some(index);
+    
+    // Tear down any state belonging to the previous profile
+    if let Some(state) = app_state.invoices_state.as_mut().filter(|s| s.is_in_email_wizard()) {
+        state.force_close_email_wizard().await?;
+    }
+    app_state.client_wizard_state = None;
+    app_state.projects_state = None;
+    app_state.project_wizard_state = None;
+    app_state.invoices_state = None;
+    app_state.invoice_wizard_state = None;
+    
+    // Load clients for the new profile
+    let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
+    let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+    let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+    app_state.clients_state = Some(ClientsState::new(profile_id, clients, outstanding).with_currency(currency));
+    app_state.screen = AppScreen::Clients(profile_id);
+    
+    Ok(())
+}
+
 async fn handle_profiles_screen(app_state: &mut AppState) -> Result<bool> {
     if let Some(state) = &mut app_state.profiles_state {
         match handle_profiles_input(state)? {
@@ -201,11 +378,21 @@ async fn handle_profiles_screen(app_state: &mut AppState) -> Result<bool> {
                 return Ok(true);
             }
             Some(ProfileAction::SelectProfile(profile_id)) => {
+                // Make sure the profile still exists before showing its clients
+                if app_state.db.get_profile(profile_id).await.is_err() {
+                    load_profiles_screen(app_state).await?;
+                    return Ok(false);
+                }
+                
+                app_state.current_profile = app_state.profile_ids.iter().position(|&id| id == profile_id);
+                
                 // Load clients for the selected profile
                 let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
+                let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                let currency = app_state.db.get_currency_by_profile(profile_id).await?;
                 
                 // Create clients state
-                app_state.clients_state = Some(ClientsState::new(profile_id, clients));
+                app_state.clients_state = Some(ClientsState::new(profile_id, clients, outstanding).with_currency(currency));
                 app_state.screen = AppScreen::Clients(profile_id);
             }
             Some(ProfileAction::DeleteProfile(profile_id)) => {
@@ -218,9 +405,12 @@ async fn handle_profiles_screen(app_state: &mut AppState) -> Result<bool> {
             Some(ProfileAction::EditProfile(profile_id)) => {
                 // Load the profile from database
                 let profile = app_state.db.get_profile(profile_id).await?;
+                let default_line_items = app_state.db.get_default_line_items(profile_id).await?;
                 
                 // Create profile wizard state for editing
-                app_state.profile_wizard_state = Some(ProfileWizardState::from_existing(profile));
+                app_state.profile_wizard_state = Some(
+                    ProfileWizardState::from_existing(profile).with_default_line_items(default_line_items)
+                );
                 app_state.screen = AppScreen::ProfileWizard;
             }
             Some(ProfileAction::NewProfile) => {
@@ -228,6 +418,69 @@ async fn handle_profiles_screen(app_state: &mut AppState) -> Result<bool> {
                 app_state.profile_wizard_state = Some(ProfileWizardState::new());
                 app_state.screen = AppScreen::ProfileWizard;
             }
+            Some(ProfileAction::TestSmtp) => {
+                // Check the SMTP settings without sending an email
+                let result = test_smtp_connection(&app_state.config);
+                state.show_smtp_test_result(result);
+            }
+            Some(ProfileAction::ExportCsv(profile_id, format, path)) => {
+                // Export the profile's invoices, reporting failures in the popup
+                let result = export::export_accounting_csv(&app_state.db, profile_id, format, &path)
+                    .await
+                    .map(|count| format!("Exported {} invoice(s) to {}", count, path))
+                    .map_err(|e| e.to_string());
+                state.show_result("CSV Export", result);
+            }
+            Some(ProfileAction::CheckOrphans) => {
+                // Look for rows left behind by failed or partial deletes
+                match app_state.db.find_orphans().await {
+                    Ok(report) => state.show_orphan_report(report),
+                    Err(e) => state.show_result("Consistency Check", Err(e.to_string())),
+                }
+            }
+            Some(ProfileAction::CleanOrphans) => {
+                let result = app_state.db.delete_orphans()
+                    .await
+                    .map(|count| format!("Deleted {} orphaned record(s)", count))
+                    .map_err(|e| e.to_string());
+                state.show_result("Orphan Cleanup", result);
+            }
+            Some(ProfileAction::RecomputeTotals) => {
+                // Repair cached invoice totals that drifted from their line items
+                let result = app_state.db.recompute_all_totals()
+                    .await
+                    .map(|count| format!("Corrected the total of {} invoice(s)", count))
+                    .map_err(|e| e.to_string());
+                state.show_result("Recompute Totals", result);
+            }
+            Some(ProfileAction::CopyBankDetails(details)) => {
+                let result = copy_to_clipboard(&mut app_state.clipboard, details)
+                    .map(|_| "Bank details copied to the clipboard".to_string())
+                    .map_err(|e| format!("Could not access the clipboard: {}", e));
+                state.show_result("Copy Bank Details", result);
+            }
+            Some(ProfileAction::CheckDuplicateNumbers(profile_id)) => {
+                match app_state.db.find_duplicate_invoice_numbers(profile_id).await {
+                    Ok(duplicates) => state.show_duplicate_numbers(profile_id, duplicates),
+                    Err(e) => state.show_result("Invoice Numbers", Err(e.to_string())),
+                }
+            }
+            Some(ProfileAction::RenumberInvoices(profile_id)) => {
+                let result = app_state.db.renumber_invoices(profile_id)
+                    .await
+                    .map(|count| format!("Renumbered {} invoice(s)", count))
+                    .map_err(|e| e.to_string());
+                state.show_result("Renumber Invoices", result);
+            }
+            Some(ProfileAction::Report) => {
+                let totals = app_state.db.get_all_profiles_totals(None, None).await?;
+                app_state.report_state = Some(ReportState::new(totals, None, None));
+                app_state.screen = AppScreen::Report;
+            }
+            Some(ProfileAction::Settings) => {
+                app_state.settings_state = Some(SettingsState::new(&app_state.config));
+                app_state.screen = AppScreen::Settings;
+            }
             None => {}
         }
     }
@@ -235,6 +488,19 @@ async fn handle_profiles_screen(app_state: &mut AppState) -> Result<bool> {
     Ok(false)
 }
 
+// Put text on the system clipboard, opening the clipboard on first use
+fn copy_to_clipboard(clipboard: &mut Option<arboard::Clipboard>, text: String) -> Result<()> {
+    if clipboard.is_none() {
+        *clipboard = Some(arboard::Clipboard::new()?);
+    }
+    
+    if let Some(clipboard) = clipboard {
+        clipboard.set_text(text)?;
+    }
+    
+    Ok(())
+}
+
 async fn handle_clients_screen(app_state: &mut AppState) -> Result<bool> {
     if let Some(state) = &mut app_state.clients_state {
         match handle_clients_input(state)? {
@@ -245,9 +511,11 @@ async fn handle_clients_screen(app_state: &mut AppState) -> Result<bool> {
             Some(ClientAction::SelectClient(client_id)) => {
                 // Load projects for the selected client
                 let projects = load_projects_by_client(&app_state.db, client_id).await?;
+                let summaries = load_project_summaries(&app_state.db, client_id).await?;
+                let currency = app_state.db.get_currency_by_client(client_id).await?;
                 
                 // Create projects state
-                app_state.projects_state = Some(ProjectsState::new(client_id, projects));
+                app_state.projects_state = Some(ProjectsState::new(client_id, projects, summaries).with_currency(currency));
                 app_state.screen = AppScreen::Projects(client_id);
             }
             Some(ClientAction::DeleteClient(client_id)) => {
@@ -257,7 +525,9 @@ async fn handle_clients_screen(app_state: &mut AppState) -> Result<bool> {
                 // Reload clients
                 let profile_id = state.profile_id();
                 let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
-                app_state.clients_state = Some(ClientsState::new(profile_id, clients));
+                let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+                app_state.clients_state = Some(ClientsState::new(profile_id, clients, outstanding).with_currency(currency));
             }
             Some(ClientAction::EditClient(client_id)) => {
                 // Load the client from database
@@ -275,6 +545,49 @@ async fn handle_clients_screen(app_state: &mut AppState) -> Result<bool> {
                 app_state.client_wizard_state = Some(ClientWizardState::new(profile_id));
                 app_state.screen = AppScreen::ClientWizard(profile_id);
             }
+            Some(ClientAction::MergeClients(source_id, target_id)) => {
+                // Merge the clients, reporting failures in the popup instead of exiting
+                let result = app_state.db.merge_clients(source_id, target_id).await;
+                
+                // Reload clients
+                let profile_id = state.profile_id();
+                let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
+                let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+                let mut clients_state = ClientsState::new(profile_id, clients, outstanding).with_currency(currency);
+                clients_state.show_merge_result(
+                    result
+                        .map(|moved| format!("Merged client: {} project(s) moved", moved))
+                        .map_err(|e| format!("Failed to merge clients: {}", e))
+                );
+                app_state.clients_state = Some(clients_state);
+            }
+            Some(ClientAction::ExportStatement(client_id)) => {
+                // Bundle the client's unpaid invoices, reporting failures in the popup
+                let result = match export::export_client_statement(&app_state.db, &app_state.config, client_id).await {
+                    Ok(Some(path)) => Ok(format!("Statement saved to {}", path)),
+                    Ok(None) => Ok("This client has no unpaid invoices".to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                state.show_result("Statement", result);
+            }
+            Some(ClientAction::ImportCsv(profile_id, path)) => {
+                // Import the valid rows, then reload so the new clients show up
+                let result = import::import_clients_csv(&app_state.db, profile_id, &path)
+                    .await
+                    .map(|report| report.summary())
+                    .map_err(|e| e.to_string());
+                
+                let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
+                
+                let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+                let mut clients_state = ClientsState::new(profile_id, clients, outstanding).with_currency(currency);
+                clients_state.show_result("CSV Import", result);
+                app_state.clients_state = Some(clients_state);
+            }
+            Some(ClientAction::PreviousProfile) => switch_profile(app_state, false).await?,
+            Some(ClientAction::NextProfile) => switch_profile(app_state, true).await?,
             None => {}
         }
     }
@@ -293,18 +606,24 @@ async fn handle_projects_screen(app_state: &mut AppState) -> Result<bool> {
                 
                 // Reload clients
                 let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
-                app_state.clients_state = Some(ClientsState::new(profile_id, clients));
+                let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+                app_state.clients_state = Some(ClientsState::new(profile_id, clients, outstanding).with_currency(currency));
                 app_state.screen = AppScreen::Clients(profile_id);
             }
             Some(ProjectAction::SelectProject(project_id)) => {
                 // Load invoices for the selected project
-                let invoices = load_invoices_by_project(&app_state.db, project_id).await?;
+                let page = load_invoice_page(&app_state.db, project_id, 0).await?;
+                
+                let totals = load_invoice_totals(&app_state.db, project_id).await?;
+                let paid = load_invoice_paid_amounts(&app_state.db, project_id).await?;
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
                 
                 // Get the project to access its name
                 let project = app_state.db.get_project(project_id).await?;
                 
                 // Create invoices state
-                app_state.invoices_state = Some(InvoicesState::new(project_id, project.name, invoices));
+                app_state.invoices_state = Some(InvoicesState::new(project_id, project.name, page, totals, paid).with_currency(currency));
                 app_state.screen = AppScreen::Invoices(project_id);
             }
             Some(ProjectAction::DeleteProject(project_id)) => {
@@ -314,7 +633,9 @@ async fn handle_projects_screen(app_state: &mut AppState) -> Result<bool> {
                 // Reload projects
                 let client_id = state.client_id();
                 let projects = load_projects_by_client(&app_state.db, client_id).await?;
-                app_state.projects_state = Some(ProjectsState::new(client_id, projects));
+                let summaries = load_project_summaries(&app_state.db, client_id).await?;
+                let currency = app_state.db.get_currency_by_client(client_id).await?;
+                app_state.projects_state = Some(ProjectsState::new(client_id, projects, summaries).with_currency(currency));
             }
             Some(ProjectAction::EditProject(project_id)) => {
                 // Load the project from database
@@ -324,14 +645,18 @@ async fn handle_projects_screen(app_state: &mut AppState) -> Result<bool> {
                 let client_id = project.client_id;
                 
                 // Create project wizard state for editing
-                app_state.project_wizard_state = Some(ProjectWizardState::from_existing(project));
+                app_state.project_wizard_state = Some(ProjectWizardState::from_existing(project)
+                    .with_date_auto_advance(app_state.config.date_auto_advance));
                 app_state.screen = AppScreen::ProjectWizard(client_id);
             }
             Some(ProjectAction::NewProject(client_id)) => {
                 // Create a new project wizard state
-                app_state.project_wizard_state = Some(ProjectWizardState::new(client_id));
+                app_state.project_wizard_state = Some(ProjectWizardState::new(client_id)
+                    .with_date_auto_advance(app_state.config.date_auto_advance));
                 app_state.screen = AppScreen::ProjectWizard(client_id);
             }
+            Some(ProjectAction::PreviousProfile) => switch_profile(app_state, false).await?,
+            Some(ProjectAction::NextProfile) => switch_profile(app_state, true).await?,
             None => {}
         }
     }
@@ -347,7 +672,7 @@ async fn handle_invoices_screen(app_state: &mut AppState) -> Result<bool> {
             state.force_close_email_wizard().await?;
         }
         
-        match handle_invoices_input(&app_state.db, state).await? {
+        match handle_invoices_input(&app_state.db, &app_state.config, state).await? {
             Some(InvoiceAction::Back) => {
                 // Ensure email wizard is properly cleaned up before switching screens
                 if state.is_in_email_wizard() {
@@ -361,37 +686,118 @@ async fn handle_invoices_screen(app_state: &mut AppState) -> Result<bool> {
                 
                 // Reload projects
                 let projects = load_projects_by_client(&app_state.db, client_id).await?;
-                app_state.projects_state = Some(ProjectsState::new(client_id, projects));
+                let summaries = load_project_summaries(&app_state.db, client_id).await?;
+                let currency = app_state.db.get_currency_by_client(client_id).await?;
+                app_state.projects_state = Some(ProjectsState::new(client_id, projects, summaries).with_currency(currency));
                 app_state.screen = AppScreen::Projects(client_id);
             }
+            Some(InvoiceAction::DeleteInvoice(invoice_id)) => {
+                delete_invoice(&app_state.db, invoice_id).await?;
+                
+                // Reload invoices
+                let project_id = state.project_id();
+                let page = load_invoice_page(&app_state.db, project_id, 0).await?;
+                let totals = load_invoice_totals(&app_state.db, project_id).await?;
+                let paid = load_invoice_paid_amounts(&app_state.db, project_id).await?;
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
+                let project_name = state.project_name().to_string();
+                app_state.invoices_state = Some(InvoicesState::new(project_id, project_name, page, totals, paid).with_currency(currency));
+            }
             Some(InvoiceAction::EditInvoice(invoice_id)) => {
                 // Load invoice data
                 let (invoice, line_items) = get_invoice_with_line_items(&app_state.db, invoice_id).await?;
                 let project_id = invoice.project_id;
                 
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
+                
                 // Create invoice wizard state for editing
                 app_state.invoice_wizard_state = Some(InvoiceWizardState::new(
                     project_id,
                     Some(invoice_id),
                     Some(invoice),
                     Some(line_items),
-                ));
+                    &app_state.config,
+                ).with_currency(currency));
                 app_state.screen = AppScreen::InvoiceWizard(project_id, Some(invoice_id));
             }
             Some(InvoiceAction::NewInvoice(project_id)) => {
-                // Create new invoice wizard state
+                // Create new invoice wizard state, starting with the profile's standard line items
+                let default_line_items = app_state.db.get_default_line_items_by_project(project_id).await?;
+                
+                // Line items of the latest invoice can be copied in from the wizard
+                let previous_line_items = match state.latest_invoice_id() {
+                    Some(id) => get_invoice_with_line_items(&app_state.db, id).await?.1,
+                    None => Vec::new(),
+                };
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
+                let number = app_state.db.next_invoice_number(project_id).await?;
+                
+                app_state.invoice_wizard_state = Some(
+                    InvoiceWizardState::new(project_id, None, None, None, &app_state.config)
+                        .with_default_line_items(&default_line_items)
+                        .with_previous_line_items(previous_line_items)
+                        .with_currency(currency)
+                        .with_suggested_number(number)
+                );
+                app_state.screen = AppScreen::InvoiceWizard(project_id, None);
+            }
+            Some(InvoiceAction::InvoiceFromTimeEntries(project_id, groups)) => {
+                let time_entry_ids = groups.iter().flat_map(|group| group.entry_ids()).collect();
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
+                let number = app_state.db.next_invoice_number(project_id).await?;
+                
+                // Create invoice wizard state with the grouped time as line items
+                app_state.invoice_wizard_state = Some(
+                    InvoiceWizardState::new(project_id, None, None, None, &app_state.config)
+                        .with_time_entries(groups_to_line_items(&groups), time_entry_ids)
+                        .with_currency(currency)
+                        .with_suggested_number(number)
+                );
+                app_state.screen = AppScreen::InvoiceWizard(project_id, None);
+            }
+            Some(InvoiceAction::CloneLatestInvoice(invoice_id)) => {
+                // Copy the latest invoice with its dates shifted forward one billing period
+                let (mut invoice, line_items) = get_invoice_with_line_items(&app_state.db, invoice_id).await?;
+                let project_id = invoice.project_id;
+                let months = app_state.config.recurring_period_months;
+                invoice.submit_date = shift_months(invoice.submit_date, months);
+                invoice.due_date = shift_months(invoice.due_date, months);
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
+                let number = app_state.db.next_invoice_number(project_id).await?;
+                
+                // Open the wizard for review as a brand new invoice
                 app_state.invoice_wizard_state = Some(InvoiceWizardState::new(
                     project_id,
                     None,
+                    Some(invoice),
+                    Some(line_items),
+                    &app_state.config,
+                ).with_currency(currency).with_suggested_number(number));
+                app_state.screen = AppScreen::InvoiceWizard(project_id, None);
+            }
+            Some(InvoiceAction::DuplicateInvoice(invoice_id)) => {
+                // Copy the selected invoice and its line items, dated today
+                let (invoice, line_items) = get_invoice_with_line_items(&app_state.db, invoice_id).await?;
+                let project_id = invoice.project_id;
+                let currency = app_state.db.get_currency_by_project(project_id).await?;
+                let number = app_state.db.next_invoice_number(project_id).await?;
+                
+                // Open the wizard for review as a brand new invoice
+                app_state.invoice_wizard_state = Some(InvoiceWizardState::new(
+                    project_id,
                     None,
-                    None,
-                ));
+                    Some(invoice),
+                    Some(line_items),
+                    &app_state.config,
+                ).with_fresh_dates().with_currency(currency).with_suggested_number(number));
                 app_state.screen = AppScreen::InvoiceWizard(project_id, None);
             }
             Some(InvoiceAction::EmailInvoice(_)) => {
                 // This is handled within the InvoicesState with its email_wizard_state
                 // in the updated invoices module
             }
+            Some(InvoiceAction::PreviousProfile) => switch_profile(app_state, false).await?,
+            Some(InvoiceAction::NextProfile) => switch_profile(app_state, true).await?,
             None => {}
         }
     }
@@ -406,30 +812,112 @@ async fn handle_invoice_wizard_screen(app_state: &mut AppState) -> Result<bool>
                 // Go back to invoices screen
                 if let AppScreen::InvoiceWizard(project_id, _) = app_state.screen {
                     // Reload invoices
-                    let invoices = load_invoices_by_project(&app_state.db, project_id).await?;
+                    let page = load_invoice_page(&app_state.db, project_id, 0).await?;
+                    let totals = load_invoice_totals(&app_state.db, project_id).await?;
+                    let paid = load_invoice_paid_amounts(&app_state.db, project_id).await?;
+                    let currency = app_state.db.get_currency_by_project(project_id).await?;
                     
                     // Get the project to access its name
                     let project = app_state.db.get_project(project_id).await?;
                     
-                    app_state.invoices_state = Some(InvoicesState::new(project_id, project.name, invoices));
+                    app_state.invoices_state = Some(InvoicesState::new(project_id, project.name, page, totals, paid).with_currency(currency));
                     app_state.screen = AppScreen::Invoices(project_id);
                 }
             }
             Some(InvoiceWizardAction::Save(invoice, line_items)) => {
-                // Save the invoice
-                save_invoice_with_line_items(&app_state.db, &invoice, &line_items).await?;
-                
-                // Go back to invoices screen
-                if let AppScreen::InvoiceWizard(project_id, _) = app_state.screen {
-                    // Reload invoices
-                    let invoices = load_invoices_by_project(&app_state.db, project_id).await?;
-                    
-                    // Get the project to access its name
-                    let project = app_state.db.get_project(project_id).await?;
-                    
-                    app_state.invoices_state = Some(InvoicesState::new(project_id, project.name, invoices));
-                    app_state.screen = AppScreen::Invoices(project_id);
-                }
+                // Save the invoice, billing the time entries it was started from
+                let time_entry_ids = state.time_entry_ids().to_vec();
+                let saved = save_invoice_with_line_items(&app_state.db, &invoice, &line_items, &time_entry_ids).await;
+                finish_invoice_save(app_state, saved).await?;
+            }
+            Some(InvoiceWizardAction::SaveDraft(invoice, line_items)) => {
+                // Drafts may not have any line items yet
+                let time_entry_ids = state.time_entry_ids().to_vec();
+                let saved = app_state.db.save_draft_invoice(&invoice, &line_items, &time_entry_ids).await;
+                finish_invoice_save(app_state, saved).await?;
+            }
+            None => {}
+        }
+    }
+    
+    Ok(false)
+}
+
+// Return to the invoices screen after the wizard saved, or stay in the wizard
+// when another session changed the invoice in the meantime
+async fn finish_invoice_save(app_state: &mut AppState, saved: error::Result<i32>) -> Result<()> {
+    match saved {
+        Ok(_) => {}
+        Err(e @ (AppError::InvoiceModified | AppError::Validation(_))) => {
+            if let Some(state) = &mut app_state.invoice_wizard_state {
+                state.show_save_error(e.to_string());
+            }
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    }
+    
+    // Go back to invoices screen
+    if let AppScreen::InvoiceWizard(project_id, _) = app_state.screen {
+        // Reload invoices
+        let page = load_invoice_page(&app_state.db, project_id, 0).await?;
+        let totals = load_invoice_totals(&app_state.db, project_id).await?;
+        let paid = load_invoice_paid_amounts(&app_state.db, project_id).await?;
+        let currency = app_state.db.get_currency_by_project(project_id).await?;
+        
+        // Get the project to access its name
+        let project = app_state.db.get_project(project_id).await?;
+        
+        app_state.invoices_state = Some(InvoicesState::new(project_id, project.name, page, totals, paid).with_currency(currency));
+        app_state.screen = AppScreen::Invoices(project_id);
+    }
+    
+    Ok(())
+}
+
+async fn handle_report_screen(app_state: &mut AppState) -> Result<bool> {
+    if let Some(state) = &mut app_state.report_state {
+        match handle_report_input(state)? {
+            Some(ReportAction::Back) => {
+                app_state.report_state = None;
+                app_state.screen = AppScreen::Profiles;
+            }
+            Some(ReportAction::Reload(from, to)) => {
+                let totals = app_state.db.get_all_profiles_totals(from, to).await?;
+                app_state.report_state = Some(ReportState::new(totals, from, to));
+            }
+            None => {}
+        }
+    }
+    
+    Ok(false)
+}
+
+async fn handle_settings_screen(app_state: &mut AppState) -> Result<bool> {
+    if let Some(state) = &mut app_state.settings_state {
+        match handle_settings_input(state)? {
+            Some(SettingsAction::Back) => {
+                app_state.settings_state = None;
+                app_state.screen = AppScreen::Profiles;
+            }
+            Some(SettingsAction::Save(mut settings)) => {
+                // Validate everything before writing, then apply in memory right away
+                let result = app_state.config.with_settings(&settings)
+                    .and_then(|config| {
+                        let stored_in_keyring = config.store_password_in_keyring(&mut settings)?;
+                        config::write_env_file(config::ENV_FILE_PATH, &settings)?;
+                        Ok((config, stored_in_keyring))
+                    })
+                    .map(|(config, stored_in_keyring)| {
+                        app_state.config = config;
+                        if stored_in_keyring {
+                            format!("Settings written to {}, SMTP password stored in the keyring", config::ENV_FILE_PATH)
+                        } else {
+                            format!("Settings written to {}", config::ENV_FILE_PATH)
+                        }
+                    })
+                    .map_err(|e| e.to_string());
+                state.show_save_result(result);
             }
             None => {}
         }
@@ -445,14 +933,16 @@ async fn handle_profile_wizard_screen(app_state: &mut AppState) -> Result<bool>
                 // Go back to profiles screen
                 load_profiles_screen(app_state).await?;
             }
-            Some(ProfileWizardAction::Save(profile)) => {
-                if profile.id == 0 {
+            Some(ProfileWizardAction::Save(profile, default_line_items)) => {
+                let profile_id = if profile.id == 0 {
                     // Create new profile
-                    app_state.db.create_profile(&profile).await?;
+                    app_state.db.create_profile(&profile).await?
                 } else {
                     // Update existing profile
                     app_state.db.update_profile(&profile).await?;
-                }
+                    profile.id
+                };
+                app_state.db.set_default_line_items(profile_id, &default_line_items).await?;
                 
                 // Reload profiles
                 load_profiles_screen(app_state).await?;
@@ -472,7 +962,9 @@ async fn handle_client_wizard_screen(app_state: &mut AppState) -> Result<bool> {
                 if let AppScreen::ClientWizard(profile_id) = app_state.screen {
                     // Reload clients
                     let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
-                    app_state.clients_state = Some(ClientsState::new(profile_id, clients));
+                    let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                    let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+                    app_state.clients_state = Some(ClientsState::new(profile_id, clients, outstanding).with_currency(currency));
                     app_state.screen = AppScreen::Clients(profile_id);
                 }
             }
@@ -488,7 +980,9 @@ async fn handle_client_wizard_screen(app_state: &mut AppState) -> Result<bool> {
                 // Reload clients and go back to clients screen
                 if let AppScreen::ClientWizard(profile_id) = app_state.screen {
                     let clients = load_clients_by_profile(&app_state.db, profile_id).await?;
-                    app_state.clients_state = Some(ClientsState::new(profile_id, clients));
+                    let outstanding = load_client_outstanding_totals(&app_state.db, profile_id).await?;
+                    let currency = app_state.db.get_currency_by_profile(profile_id).await?;
+                    app_state.clients_state = Some(ClientsState::new(profile_id, clients, outstanding).with_currency(currency));
                     app_state.screen = AppScreen::Clients(profile_id);
                 }
             }
@@ -506,7 +1000,9 @@ async fn handle_project_wizard_screen(app_state: &mut AppState) -> Result<bool>
                 // Go back to projects screen
                 let client_id = state.client_id();
                 let projects = load_projects_by_client(&app_state.db, client_id).await?;
-                app_state.projects_state = Some(ProjectsState::new(client_id, projects));
+                let summaries = load_project_summaries(&app_state.db, client_id).await?;
+                let currency = app_state.db.get_currency_by_client(client_id).await?;
+                app_state.projects_state = Some(ProjectsState::new(client_id, projects, summaries).with_currency(currency));
                 app_state.screen = AppScreen::Projects(client_id);
             }
             Some(ProjectWizardAction::Save(project)) => {
@@ -520,7 +1016,9 @@ async fn handle_project_wizard_screen(app_state: &mut AppState) -> Result<bool>
                 
                 // Reload projects
                 let projects = load_projects_by_client(&app_state.db, project.client_id).await?;
-                app_state.projects_state = Some(ProjectsState::new(project.client_id, projects));
+                let summaries = load_project_summaries(&app_state.db, project.client_id).await?;
+                let currency = app_state.db.get_currency_by_client(project.client_id).await?;
+                app_state.projects_state = Some(ProjectsState::new(project.client_id, projects, summaries).with_currency(currency));
                 app_state.screen = AppScreen::Projects(project.client_id);
             }
             None => {}
@@ -529,3 +1027,59 @@ async fn handle_project_wizard_screen(app_state: &mut AppState) -> Result<bool>
     
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::test_support::config;
+    use sqlx::PgPool;
+
+    #[test]
+    fn a_failed_query_is_shown_and_the_session_continues() {
+        let mut error_message = None;
+        let failed_query = Err(AppError::Database(sqlx::Error::PoolTimedOut).into());
+
+        assert!(!recover(failed_query, &mut error_message).unwrap());
+        assert!(error_message.is_some_and(|message| message.starts_with("Database error")));
+    }
+
+    #[test]
+    fn file_errors_are_recoverable_but_terminal_errors_are_fatal() {
+        let mut error_message = None;
+        let missing_file = Err(io::Error::new(io::ErrorKind::NotFound, "invoice.pdf").into());
+        assert!(!recover(missing_file, &mut error_message).unwrap());
+
+        let closed_terminal = Err(io::Error::new(io::ErrorKind::BrokenPipe, "stdout").into());
+        assert!(recover(closed_terminal, &mut None).is_err());
+    }
+
+    #[test]
+    fn quitting_is_passed_through() {
+        let mut error_message = None;
+        assert!(recover(Ok(true), &mut error_message).unwrap());
+        assert!(!recover(Ok(false), &mut error_message).unwrap());
+        assert!(error_message.is_none());
+    }
+
+    #[sqlx::test]
+    async fn shutdown_cancels_and_awaits_outstanding_work(pool: PgPool) {
+        let mut app_state = AppState::new(db::Database::from_pool(pool), config());
+        
+        // A task that generated a file and removes it once cancelled
+        let path = std::env::temp_dir().join(format!("shutdown-{}.pdf", std::process::id()));
+        std::fs::write(&path, "%PDF-1.4").unwrap();
+        let (cancel, pending) = (app_state.cancel.clone(), path.clone());
+        app_state.tasks.spawn(async move {
+            cancel.cancelled().await;
+            tokio::task::yield_now().await;
+            std::fs::remove_file(pending).unwrap();
+        });
+        
+        shutdown(&mut app_state).await.unwrap();
+        assert!(app_state.cancel.is_cancelled());
+        assert!(app_state.tasks.is_empty());
+        assert!(!path.exists());
+        assert!(app_state.db.get_pool().is_closed());
+    }
+}
@@ -6,4 +6,6 @@ pub struct Client {
     pub address: Option<String>,
     pub email: String,
     pub profile_id: i32,
+    pub show_bank_details: bool,
+    pub language: String, // ISO 639-1 code invoice labels are printed in
 } 
\ No newline at end of file
@@ -0,0 +1,5 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct DuplicateInvoiceNumber {
+    pub number: i32,
+    pub count: i64, // Number of invoices sharing the number
+}
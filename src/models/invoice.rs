@@ -1,4 +1,4 @@
-#[derive(sqlx::FromRow, Debug)]
+#[derive(sqlx::FromRow, serde::Serialize, Debug, Clone)]
 
 pub struct Invoice {
     pub id: i32,
@@ -8,4 +8,109 @@ pub struct Invoice {
     pub due_date: chrono::NaiveDate,
     pub rate: f64,
     pub status: String,
-}
\ No newline at end of file
+    pub bill_to_override: Option<String>,
+    pub internal_notes: Option<String>,
+    pub billing_type: String,
+    pub version: i32, // Incremented on every update to detect concurrent edits
+    pub last_emailed_at: Option<chrono::NaiveDateTime>,
+    pub last_emailed_to: Option<String>,
+    pub paid_date: Option<chrono::NaiveDate>, // When the invoice was marked paid
+    pub tax_rate: Option<f64>, // Tax or VAT percentage added to the line items, None for no tax
+    pub notes: Option<String>, // Shown to the client below the totals, unlike internal_notes
+}
+
+impl Invoice {
+    /// Whether line items carry their own amount instead of hours billed at the rate
+    pub fn is_flat_fee(&self) -> bool {
+        self.billing_type == "Flat"
+    }
+
+    /// Days from today until the invoice falls due, negative once it's overdue
+    pub fn days_until_due(&self, today: chrono::NaiveDate) -> i64 {
+        (self.due_date - today).num_days()
+    }
+
+    /// Whether the invoice has been approved and isn't cancelled, which emailing requires
+    pub fn can_be_emailed(&self) -> bool {
+        matches!(self.status.as_str(), "Approved" | "Sent" | "Paid")
+    }
+
+    /// Whether a payment can be recorded against the invoice. Drafts and cancelled invoices
+    /// aren't waiting on one, and paid ones have nothing left to pay.
+    pub fn awaits_payment(&self) -> bool {
+        !matches!(self.status.as_str(), "Draft" | "Cancelled" | "Paid")
+    }
+
+    /// Status the invoice moves to once it's emailed. Paid invoices keep theirs, so
+    /// re-sending one as a receipt doesn't make it outstanding again.
+    pub fn status_after_sending(&self) -> &str {
+        match self.status.as_str() {
+            "Draft" | "Approved" => "Sent",
+            status => status,
+        }
+    }
+
+    /// Tax percentage charged on this invoice, None when it has no tax or a zero rate
+    pub fn applied_tax_rate(&self) -> Option<f64> {
+        self.tax_rate.filter(|rate| *rate != 0.0)
+    }
+
+    /// Amount billed for a line item of this invoice
+    pub fn line_item_amount(&self, item: &super::InvoiceLineItem) -> rust_decimal::Decimal {
+        crate::money::line_item_amount(self.is_flat_fee(), self.rate, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{date, invoice};
+
+    use super::*;
+
+    fn with_status(status: &str) -> Invoice {
+        Invoice { status: status.to_string(), ..invoice(1) }
+    }
+
+    #[test]
+    fn days_until_due_counts_down_past_zero() {
+        // Due 2024-02-14
+        assert_eq!(invoice(1).days_until_due(date(2024, 2, 4)), 10);
+        assert_eq!(invoice(1).days_until_due(date(2024, 2, 14)), 0);
+        assert_eq!(invoice(1).days_until_due(date(2024, 3, 1)), -16);
+    }
+
+    #[test]
+    fn emailing_a_draft_is_blocked() {
+        assert!(!with_status("Draft").can_be_emailed());
+    }
+
+    #[test]
+    fn emailing_an_approved_invoice_proceeds() {
+        assert!(with_status("Approved").can_be_emailed());
+        assert!(with_status("Sent").can_be_emailed());
+        assert!(with_status("Paid").can_be_emailed());
+    }
+
+    #[test]
+    fn emailing_a_cancelled_invoice_is_blocked() {
+        assert!(!with_status("Cancelled").can_be_emailed());
+    }
+
+    #[test]
+    fn only_approved_and_sent_invoices_take_payments() {
+        assert!(with_status("Approved").awaits_payment());
+        assert!(with_status("Sent").awaits_payment());
+        assert!(!with_status("Draft").awaits_payment());
+        assert!(!with_status("Cancelled").awaits_payment());
+        assert!(!with_status("Paid").awaits_payment());
+    }
+
+    #[test]
+    fn sending_moves_only_unsent_invoices_to_sent() {
+        assert_eq!(with_status("Draft").status_after_sending(), "Sent");
+        assert_eq!(with_status("Approved").status_after_sending(), "Sent");
+        assert_eq!(with_status("Sent").status_after_sending(), "Sent");
+        assert_eq!(with_status("Paid").status_after_sending(), "Paid");
+        assert_eq!(with_status("Cancelled").status_after_sending(), "Cancelled");
+    }
+}
@@ -0,0 +1,6 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct InvoiceEmailLogEntry {
+    pub sent_at: chrono::NaiveDateTime,
+    pub recipient: String,
+    pub success: bool, // False when the SMTP server didn't accept the email
+}
@@ -0,0 +1,10 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct InvoiceExportRow {
+    pub number: i32,
+    pub client_name: String,
+    pub project_name: String,
+    pub submit_date: chrono::NaiveDate,
+    pub due_date: chrono::NaiveDate,
+    pub status: String,
+    pub total: f64,
+}
@@ -1,8 +1,23 @@
-#[derive(sqlx::FromRow, Debug, Clone)]
+#[derive(sqlx::FromRow, serde::Serialize, Debug, Clone)]
 
 pub struct InvoiceLineItem {
     pub id: i32,
     pub invoice_id: i32,
     pub description: String,
     pub hours: f64,
+    pub amount: f64,
+    pub rate_override: Option<f64>, // Hourly rate for this item only, None uses the invoice rate
+    pub item_type: String, // "Hourly" bills hours at the rate, "Fixed" bills the amount as entered
+}
+
+impl InvoiceLineItem {
+    /// Whether the item bills its amount as entered instead of hours at a rate
+    pub fn is_fixed(&self) -> bool {
+        self.item_type == "Fixed"
+    }
+
+    /// Hourly rate this item is billed at on an invoice with the given rate
+    pub fn effective_rate(&self, invoice_rate: f64) -> f64 {
+        self.rate_override.unwrap_or(invoice_rate)
+    }
 }
\ No newline at end of file
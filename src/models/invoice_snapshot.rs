@@ -0,0 +1,105 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct InvoiceSnapshot {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub total: f64,
+    pub snapshot: String, // JSON of the invoice, line items and total as sent
+}
+
+impl InvoiceSnapshot {
+    /// The invoice as it was sent, read back from the stored JSON, with amounts in the
+    /// given currency. Falls back to the raw JSON if it can't be parsed.
+    pub fn lines(&self, currency: &str) -> Vec<String> {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&self.snapshot) else {
+            return self.snapshot.lines().map(str::to_string).collect();
+        };
+        let invoice = &value["invoice"];
+        let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+        let flat_fee = invoice["billing_type"] == "Flat";
+        let rate = invoice["rate"].as_f64().unwrap_or_default();
+
+        let mut lines = vec![
+            format!("Invoice #{}", invoice["number"]),
+            format!("Submitted {}, due {}", text(&invoice["submit_date"]), text(&invoice["due_date"])),
+        ];
+        if let Some(bill_to) = invoice["bill_to_override"].as_str() {
+            lines.push(format!("Bill to: {}", bill_to.replace('\n', ", ")));
+        }
+        lines.push(String::new());
+
+        let items = value["line_items"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for (i, item) in items.iter().enumerate() {
+            let description = text(&item["description"]);
+            // Snapshots from before fixed-amount items have no item type
+            if flat_fee || item["item_type"] == "Fixed" {
+                let amount = item["amount"].as_f64().unwrap_or_default();
+                lines.push(format!("{}. {}: {}", i + 1, description, crate::money::format_money_in(amount, currency)));
+            } else {
+                let hours = item["hours"].as_f64().unwrap_or_default();
+                let item_rate = item["rate_override"].as_f64().unwrap_or(rate);
+                lines.push(format!("{}. {}: {} hours at {}", i + 1, description, hours, crate::money::format_money_in(item_rate, currency)));
+            }
+        }
+        if let Some(tax_rate) = invoice["tax_rate"].as_f64().filter(|rate| *rate != 0.0) {
+            lines.push(format!("Tax: {}%", crate::money::format_tax_rate(tax_rate)));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("Total: {}", crate::money::format_money_in(self.total, currency)));
+        if let Some(notes) = invoice["notes"].as_str() {
+            lines.push(String::new());
+            lines.extend(notes.lines().map(str::to_string));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Invoice, InvoiceLineItem};
+    use crate::test_support::{invoice, line_item};
+
+    fn snapshot_of(invoice: &Invoice, line_items: &[InvoiceLineItem], total: f64) -> InvoiceSnapshot {
+        InvoiceSnapshot {
+            created_at: chrono::Utc::now(),
+            total,
+            snapshot: serde_json::json!({ "invoice": invoice, "line_items": line_items, "total": total }).to_string(),
+        }
+    }
+
+    #[test]
+    fn lines_show_the_invoice_as_sent() {
+        let mut hosting = line_item(2, "Hosting", 0.0);
+        hosting.item_type = "Fixed".to_string();
+        hosting.amount = 50.0;
+        let invoice = Invoice { notes: Some("Thanks!".to_string()), ..invoice(12) };
+        let snapshot = snapshot_of(&invoice, &[line_item(1, "Design", 2.0), hosting], 250.0);
+
+        assert_eq!(snapshot.lines("USD"), [
+            "Invoice #12",
+            "Submitted 2024-01-15, due 2024-02-14",
+            "",
+            "1. Design: 2 hours at $100.00",
+            "2. Hosting: $50.00",
+            "",
+            "Total: $250.00",
+            "",
+            "Thanks!",
+        ]);
+    }
+
+    #[test]
+    fn amounts_are_shown_in_the_profile_currency() {
+        let snapshot = snapshot_of(&invoice(12), &[line_item(1, "Design", 2.0)], 200.0);
+        let lines = snapshot.lines("EUR");
+
+        assert!(lines.contains(&"1. Design: 2 hours at €100.00".to_string()));
+        assert!(lines.contains(&"Total: €200.00".to_string()));
+    }
+
+    #[test]
+    fn unreadable_snapshots_are_shown_raw() {
+        let snapshot = InvoiceSnapshot { created_at: chrono::Utc::now(), total: 0.0, snapshot: "not json".to_string() };
+        assert_eq!(snapshot.lines("USD"), ["not json"]);
+    }
+}
@@ -3,9 +3,25 @@ mod client;
 mod project;
 mod invoice;
 mod invoice_line_item;
+mod invoice_export_row;
+mod orphan_report;
+mod invoice_snapshot;
+mod invoice_email_log_entry;
+mod time_entry;
+mod duplicate_invoice_number;
+mod profile_totals;
+mod project_summary;
 
-pub use profile::Profile; 
+pub use profile::{Profile, is_hex_color, DEFAULT_ACCENT_COLOR, DEFAULT_HEADER_COLOR}; 
 pub use client::Client; 
 pub use project::Project; 
 pub use invoice::Invoice; 
-pub use invoice_line_item::InvoiceLineItem; 
\ No newline at end of file
+pub use invoice_line_item::InvoiceLineItem; 
+pub use invoice_export_row::InvoiceExportRow; 
+pub use orphan_report::OrphanReport; 
+pub use invoice_snapshot::InvoiceSnapshot; 
+pub use invoice_email_log_entry::InvoiceEmailLogEntry; 
+pub use time_entry::TimeEntry; 
+pub use duplicate_invoice_number::DuplicateInvoiceNumber; 
+pub use profile_totals::ProfileTotals; 
+pub use project_summary::ProjectSummary; 
\ No newline at end of file
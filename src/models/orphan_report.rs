@@ -0,0 +1,18 @@
+// Ids of rows whose parent record no longer exists
+#[derive(Debug, Clone, Default)]
+pub struct OrphanReport {
+    pub client_ids: Vec<i32>,
+    pub project_ids: Vec<i32>,
+    pub invoice_ids: Vec<i32>,
+    pub line_item_ids: Vec<i32>,
+}
+
+impl OrphanReport {
+    pub fn total(&self) -> usize {
+        self.client_ids.len() + self.project_ids.len() + self.invoice_ids.len() + self.line_item_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+}
@@ -1,3 +1,8 @@
+/// Color of the divider at the top of generated invoices
+pub const DEFAULT_HEADER_COLOR: &str = "#343876";
+/// Color of the submitted date and totals on generated invoices
+pub const DEFAULT_ACCENT_COLOR: &str = "#e83e8c";
+
 #[derive(sqlx::FromRow, Debug, Clone)]
 pub struct Profile {
     pub id: i32,
@@ -8,4 +13,46 @@ pub struct Profile {
     pub bank_name: String,
     pub bank_account_number: String,
     pub bank_routing_number: String,
-} 
\ No newline at end of file
+    pub header_color: String,
+    pub accent_color: String,
+    pub currency: String, // ISO 4217 code of the currency invoices are in
+}
+
+impl Profile {
+    /// Profile with only its id and name known, as read back from the profiles cache
+    pub fn named(id: i32, name: String) -> Self {
+        Self {
+            id,
+            name,
+            phonenumber: String::new(),
+            address: None,
+            email: String::new(),
+            bank_name: String::new(),
+            bank_account_number: String::new(),
+            bank_routing_number: String::new(),
+            header_color: DEFAULT_HEADER_COLOR.to_string(),
+            accent_color: DEFAULT_ACCENT_COLOR.to_string(),
+            currency: crate::money::DEFAULT_CURRENCY.to_string(),
+        }
+    }
+}
+
+/// Whether a color is written as #RRGGBB, the only format accepted for invoice colors
+pub fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_six_digit_hex_colors_are_accepted() {
+        for color in [DEFAULT_HEADER_COLOR, DEFAULT_ACCENT_COLOR, "#ABCDEF", "#000000"] {
+            assert!(is_hex_color(color), "{}", color);
+        }
+        for color in ["", "#fff", "343876", "#34387g", "#3438766", "red", "#e83e8"] {
+            assert!(!is_hex_color(color), "{}", color);
+        }
+    }
+}
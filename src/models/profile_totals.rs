@@ -0,0 +1,8 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ProfileTotals {
+    pub profile_name: String,
+    pub currency: String, // Currency of the profile, which all its amounts are in
+    pub billed: f64,      // All invoices except cancelled ones
+    pub paid: f64,        // Invoices marked paid and partial payments on the rest
+    pub outstanding: f64, // Billed but not yet paid
+}
@@ -0,0 +1,6 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ProjectSummary {
+    pub project_id: i32,
+    pub invoice_count: i64, // All invoices of the project
+    pub billed: f64,        // Total of the invoices except cancelled ones
+}
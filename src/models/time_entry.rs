@@ -0,0 +1,7 @@
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TimeEntry {
+    pub id: i32,
+    pub entry_date: chrono::NaiveDate,
+    pub description: String,
+    pub hours: f64,
+}
@@ -0,0 +1,199 @@
+use std::fmt::Display;
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::models::{Invoice, InvoiceLineItem};
+
+// Rates, hours and amounts are stored as NUMERIC(10, 2) but read into f64, so anything
+// past a few decimal places is float noise
+const STORED_PRECISION: u32 = 6;
+
+/// Currency of profiles that don't set one
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// Format an amount in the given currency, with its symbol and whole cents
+pub fn format_money_in<T: Display>(amount: T, currency: &str) -> String {
+    format!("{}{:.2}", currency_symbol(currency), amount)
+}
+
+/// Symbol written in front of amounts in a currency. Currencies without a well known
+/// symbol use their code followed by a space.
+pub fn currency_symbol(currency: &str) -> String {
+    let code = currency.trim().to_ascii_uppercase();
+    let symbol = match code.as_str() {
+        "USD" => "$",
+        "EUR" => "€",
+        "GBP" => "£",
+        "JPY" => "¥",
+        "INR" => "₹",
+        "CAD" => "CA$",
+        "AUD" => "A$",
+        "NZD" => "NZ$",
+        _ => return format!("{} ", code),
+    };
+    symbol.to_string()
+}
+
+/// Whether a currency is written as a three letter ISO 4217 code like EUR
+pub fn is_currency_code(currency: &str) -> bool {
+    currency.len() == 3 && currency.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Parse a typed or pasted number, ignoring thousands separators and spaces
+/// ("1,250.00", "1 250"). None when what's left isn't a finite number.
+pub fn parse_number(input: &str) -> Option<f64> {
+    let cleaned: String = input
+        .chars()
+        .filter(|c| *c != ',' && !c.is_whitespace())
+        .collect();
+    cleaned.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// Convert a stored money or hours value to a decimal for exact arithmetic
+pub fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default().round_dp(STORED_PRECISION)
+}
+
+/// Round to whole cents, half away from zero like PostgreSQL's ROUND on numeric
+pub fn round_cents(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Convert back to f64 where a value goes into a column or an f64 API
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
+
+/// Amount billed for a single line item, rounded to cents so the lines always add up
+/// to the total shown
+pub fn line_item_amount(flat_fee: bool, rate: f64, item: &InvoiceLineItem) -> Decimal {
+    if flat_fee || item.is_fixed() {
+        round_cents(to_decimal(item.amount))
+    } else {
+        round_cents(to_decimal(item.hours) * to_decimal(item.effective_rate(rate)))
+    }
+}
+
+/// Total of all line items of an invoice, before tax
+pub fn invoice_subtotal(invoice: &Invoice, line_items: &[InvoiceLineItem]) -> Decimal {
+    line_items.iter().map(|item| invoice.line_item_amount(item)).sum()
+}
+
+/// Tax on a subtotal at a percentage rate, rounded to cents like the cached invoice total
+pub fn tax_amount(subtotal: Decimal, tax_rate: Option<f64>) -> Decimal {
+    match tax_rate {
+        Some(rate) => round_cents(subtotal * to_decimal(rate) / Decimal::ONE_HUNDRED),
+        None => Decimal::ZERO,
+    }
+}
+
+/// Figures an invoice adds up to below its line items
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakdown {
+    pub subtotal: Decimal,
+    /// Tax percentage and the tax it adds, None when the invoice has no tax
+    pub tax: Option<(f64, Decimal)>,
+    pub total: Decimal,
+}
+
+/// Subtotal, tax and total of an invoice, shown the same way in the wizard and on
+/// generated invoices
+pub fn compute_breakdown(invoice: &Invoice, line_items: &[InvoiceLineItem]) -> Breakdown {
+    let subtotal = invoice_subtotal(invoice, line_items);
+    let tax = invoice.applied_tax_rate().map(|rate| (rate, tax_amount(subtotal, Some(rate))));
+    Breakdown {
+        subtotal,
+        tax,
+        total: subtotal + tax.map_or(Decimal::ZERO, |(_, tax)| tax),
+    }
+}
+
+/// Amount due for an invoice, its line items plus any tax
+pub fn invoice_total(invoice: &Invoice, line_items: &[InvoiceLineItem]) -> Decimal {
+    compute_breakdown(invoice, line_items).total
+}
+
+/// Tax percentage as written on invoices, without trailing zeros ("20", "7.5")
+pub fn format_tax_rate(rate: f64) -> String {
+    to_decimal(rate).normalize().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{invoice, line_item};
+
+    #[test]
+    fn parses_grouped_numbers() {
+        assert_eq!(parse_number("1,250.00"), Some(1250.0));
+        assert_eq!(parse_number("1 250"), Some(1250.0));
+        assert_eq!(parse_number(" 12.5 "), Some(12.5));
+        // Amounts too large for an f64 or not numbers at all are rejected
+        assert_eq!(parse_number("inf"), None);
+        assert_eq!(parse_number("NaN"), None);
+        assert_eq!(parse_number("1e400"), None);
+    }
+
+    #[test]
+    fn breakdown_adds_tax_on_top_of_the_subtotal() {
+        let items = [line_item(1, "Design", 2.5), line_item(2, "Meetings", 1.25)];
+        let untaxed = compute_breakdown(&invoice(1), &items);
+        assert_eq!(untaxed, Breakdown { subtotal: Decimal::new(37500, 2), tax: None, total: Decimal::new(37500, 2) });
+
+        let taxed = compute_breakdown(&Invoice { tax_rate: Some(7.5), ..invoice(1) }, &items);
+        assert_eq!(taxed.tax, Some((7.5, Decimal::new(2813, 2))));
+        assert_eq!(taxed.total, Decimal::new(40313, 2));
+
+        // A zero rate is the same as no tax
+        assert_eq!(compute_breakdown(&Invoice { tax_rate: Some(0.0), ..invoice(1) }, &items), untaxed);
+    }
+
+    #[test]
+    fn totals_of_many_line_items_are_exact() {
+        let tenths: Vec<_> = (0..1000).map(|id| line_item(id, "Call", 0.1)).collect();
+        assert_eq!(invoice_total(&invoice(1), &tenths), Decimal::new(1000000, 2));
+
+        // Each line is rounded to cents before adding, 0.33h at 33.33 is 11.00
+        let thirds = Invoice { rate: 33.33, ..invoice(1) };
+        let items: Vec<_> = (0..300).map(|id| line_item(id, "Review", 0.33)).collect();
+        assert_eq!(invoice_total(&thirds, &items), Decimal::new(330000, 2));
+
+        let flat = Invoice { billing_type: "Flat".to_string(), ..invoice(1) };
+        let fees: Vec<_> = (0..999).map(|id| InvoiceLineItem { amount: 0.01, ..line_item(id, "Fee", 0.0) }).collect();
+        assert_eq!(invoice_total(&flat, &fees), Decimal::new(999, 2));
+    }
+
+    #[test]
+    fn overridden_rates_apply_to_their_own_line_only() {
+        let items = [
+            InvoiceLineItem { rate_override: Some(150.0), ..line_item(1, "Senior", 2.0) },
+            line_item(2, "Junior", 3.0),
+            InvoiceLineItem { rate_override: Some(62.5), ..line_item(3, "Support", 1.5) },
+        ];
+
+        assert_eq!(invoice(1).line_item_amount(&items[0]), Decimal::new(30000, 2));
+        assert_eq!(invoice(1).line_item_amount(&items[1]), Decimal::new(30000, 2));
+        assert_eq!(invoice(1).line_item_amount(&items[2]), Decimal::new(9375, 2));
+        assert_eq!(invoice_total(&invoice(1), &items), Decimal::new(69375, 2));
+    }
+
+    #[test]
+    fn flat_fee_totals_ignore_hours_and_rate() {
+        let flat = Invoice { billing_type: "Flat".to_string(), ..invoice(1) };
+        let items = [
+            InvoiceLineItem { amount: 1500.0, ..line_item(1, "Website", 3.0) },
+            InvoiceLineItem { amount: 249.995, ..line_item(2, "Hosting", 0.0) },
+        ];
+
+        assert_eq!(invoice_total(&flat, &items), Decimal::new(175000, 2));
+        assert_eq!(invoice_total(&invoice(1), &items), Decimal::new(30000, 2));
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_number() {
+        assert_eq!(parse_number("12.5.6"), None);
+        assert_eq!(parse_number("abc"), None);
+        assert_eq!(parse_number(""), None);
+    }
+}
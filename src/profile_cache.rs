@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::fs::File;
+
+use crate::models::Profile;
+
+/// The part of a profile kept in the cache. Only what the startup list shows is written,
+/// so bank details and contact information never end up on disk.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CachedProfile {
+    id: i32,
+    name: String,
+}
+
+/// Read the cached profiles list, with only the id and name of each profile filled in.
+/// Returns None if the cache is missing or unreadable.
+pub fn load(path: &str) -> Option<Vec<Profile>> {
+    let file = File::open(path).ok()?;
+    let mut reader = csv::Reader::from_reader(file);
+    
+    let cached = reader.deserialize().collect::<Result<Vec<CachedProfile>, _>>().ok()?;
+    Some(cached.into_iter().map(|profile| Profile::named(profile.id, profile.name)).collect())
+}
+
+/// Replace the cached profiles list
+pub fn save(path: &str, profiles: &[Profile]) -> Result<()> {
+    let file = File::create(path)?;
+    let mut writer = csv::Writer::from_writer(file);
+    
+    for profile in profiles {
+        writer.serialize(CachedProfile { id: profile.id, name: profile.name.clone() })?;
+    }
+    
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn only_ids_and_names_are_cached() {
+        let path = std::env::temp_dir().join(format!("profiles-cache-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let profile = Profile { id: 3, ..test_support::profile("Studio") };
+
+        save(path, &[profile]).unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        let loaded = load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        // Bank details and contact information stay out of the file
+        assert_eq!(written, "id,name\n3,Studio\n");
+        assert_eq!(loaded.iter().map(|p| (p.id, p.name.as_str())).collect::<Vec<_>>(), [(3, "Studio")]);
+    }
+}
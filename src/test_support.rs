@@ -0,0 +1,90 @@
+// Fixtures shared by the unit tests
+
+use chrono::NaiveDate;
+
+use crate::config::Config;
+use crate::models::{Client, Invoice, InvoiceLineItem, Profile, Project};
+
+/// Config with every optional setting at its default
+pub fn config() -> Config {
+    envy::from_iter::<_, Config>([("DATABASE_URL".to_string(), "postgres://localhost/invoices".to_string())])
+        .expect("default config")
+}
+
+pub fn profile(name: &str) -> Profile {
+    Profile {
+        id: 0,
+        name: name.to_string(),
+        phonenumber: "555-0100".to_string(),
+        address: Some("1 Main St".to_string()),
+        email: "billing@example.com".to_string(),
+        bank_name: "Bank".to_string(),
+        bank_account_number: "12345678".to_string(),
+        bank_routing_number: "87654321".to_string(),
+        header_color: crate::models::DEFAULT_HEADER_COLOR.to_string(),
+        accent_color: crate::models::DEFAULT_ACCENT_COLOR.to_string(),
+        currency: crate::money::DEFAULT_CURRENCY.to_string(),
+    }
+}
+
+pub fn client(profile_id: i32, name: &str) -> Client {
+    Client {
+        id: 0,
+        name: name.to_string(),
+        phone: "555-0199".to_string(),
+        address: Some("2 Side St".to_string()),
+        email: "accounts@client.example".to_string(),
+        profile_id,
+        show_bank_details: true,
+        language: "en".to_string(),
+    }
+}
+
+pub fn project(client_id: i32, name: &str) -> Project {
+    Project {
+        id: 0,
+        client_id,
+        name: name.to_string(),
+        start_date: date(2024, 1, 1),
+        end_date: None,
+    }
+}
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+/// Saved hourly Draft invoice at a rate of 100 with no tax or notes
+pub fn invoice(id: i32) -> Invoice {
+    Invoice {
+        id,
+        project_id: 1,
+        number: id,
+        submit_date: date(2024, 1, 15),
+        due_date: date(2024, 2, 14),
+        rate: 100.0,
+        status: "Draft".to_string(),
+        bill_to_override: None,
+        internal_notes: None,
+        billing_type: "Hourly".to_string(),
+        version: 1,
+        last_emailed_at: None,
+        last_emailed_to: None,
+        paid_date: None,
+        tax_rate: None,
+        notes: None,
+    }
+}
+
+/// Hourly line item billed at the invoice rate
+pub fn line_item(id: i32, description: &str, hours: f64) -> InvoiceLineItem {
+    InvoiceLineItem {
+        id,
+        invoice_id: 1,
+        description: description.to_string(),
+        hours,
+        amount: 0.0,
+        rate_override: None,
+        item_type: "Hourly".to_string(),
+    }
+}
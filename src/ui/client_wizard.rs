@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -9,7 +9,23 @@ use tui::{
     Frame,
 };
 
+use crate::labels::DEFAULT_LANGUAGE;
 use crate::models::Client;
+use crate::ui::components::confirm::render_confirmation;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::{footer_spans, value_or_hint};
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Move between fields"),
+    ("Enter", "Edit the selected field, or save it while editing"),
+    ("Enter on Bank Details", "Show or hide bank details on the client's invoices"),
+    ("Ctrl+Z", "Undo changes to the field being edited"),
+    ("S", "Save the client"),
+    ("Ctrl+R", "Start a new client over"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Cancel editing, or leave without saving"),
+];
 
 pub enum ClientWizardAction {
     Cancel,
@@ -22,6 +38,8 @@ pub enum ClientField {
     Email,
     Phone,
     Address,
+    Language,
+    BankDetails,
 }
 
 pub struct ClientWizardState {
@@ -29,6 +47,10 @@ pub struct ClientWizardState {
     pub client: Client,
     pub current_field: ClientField,
     pub editing: bool,
+    pub confirm_reset: bool,
+    pub show_help: bool,
+    // Value of the field when editing started, restored by undo
+    edit_start_value: String,
 }
 
 impl ClientWizardState {
@@ -42,9 +64,14 @@ impl ClientWizardState {
                 email: String::new(),
                 phone: String::new(),
                 address: Some(String::new()),
+                show_bank_details: true,
+                language: DEFAULT_LANGUAGE.to_string(),
             },
             current_field: ClientField::Name,
             editing: false,
+            confirm_reset: false,
+            show_help: false,
+            edit_start_value: String::new(),
         }
     }
 
@@ -54,6 +81,9 @@ impl ClientWizardState {
             client,
             current_field: ClientField::Name,
             editing: false,
+            confirm_reset: false,
+            show_help: false,
+            edit_start_value: String::new(),
         }
     }
 
@@ -63,6 +93,33 @@ impl ClientWizardState {
 
     pub fn toggle_editing(&mut self) {
         self.editing = !self.editing;
+        if self.editing {
+            self.edit_start_value = self.current_field_value().cloned().unwrap_or_default();
+        }
+    }
+
+    // Put the field being edited back to its value from when editing started
+    pub fn undo_edit(&mut self) {
+        if !self.editing {
+            return;
+        }
+        
+        let start_value = self.edit_start_value.clone();
+        if let Some(field_value) = self.current_field_value() {
+            *field_value = start_value;
+        }
+    }
+
+    // The text of the current field, None for the bank details toggle
+    fn current_field_value(&mut self) -> Option<&mut String> {
+        Some(match self.current_field {
+            ClientField::Name => &mut self.client.name,
+            ClientField::Email => &mut self.client.email,
+            ClientField::Phone => &mut self.client.phone,
+            ClientField::Address => self.client.address.get_or_insert_with(String::new),
+            ClientField::Language => &mut self.client.language,
+            ClientField::BankDetails => return None,
+        })
     }
 
     pub fn next_field(&mut self) {
@@ -70,34 +127,35 @@ impl ClientWizardState {
             ClientField::Name => ClientField::Email,
             ClientField::Email => ClientField::Phone,
             ClientField::Phone => ClientField::Address,
-            ClientField::Address => ClientField::Name,
+            ClientField::Address => ClientField::Language,
+            ClientField::Language => ClientField::BankDetails,
+            ClientField::BankDetails => ClientField::Name,
         };
     }
 
     pub fn previous_field(&mut self) {
         self.current_field = match self.current_field {
-            ClientField::Name => ClientField::Address,
+            ClientField::Name => ClientField::BankDetails,
             ClientField::Email => ClientField::Name,
             ClientField::Phone => ClientField::Email,
             ClientField::Address => ClientField::Phone,
+            ClientField::Language => ClientField::Address,
+            ClientField::BankDetails => ClientField::Language,
         };
     }
 
+    // Whether invoices for this client include the bank account block
+    pub fn toggle_bank_details(&mut self) {
+        self.client.show_bank_details = !self.client.show_bank_details;
+    }
+
     pub fn edit_current_field(&mut self, key: KeyCode) {
         if !self.editing {
             return;
         }
 
-        let field_value = match self.current_field {
-            ClientField::Name => &mut self.client.name,
-            ClientField::Email => &mut self.client.email,
-            ClientField::Phone => &mut self.client.phone,
-            ClientField::Address => {
-                if self.client.address.is_none() {
-                    self.client.address = Some(String::new());
-                }
-                self.client.address.as_mut().unwrap()
-            }
+        let Some(field_value) = self.current_field_value() else {
+            return;
         };
 
         match key {
@@ -111,10 +169,34 @@ impl ClientWizardState {
         }
     }
 
+    // Whether anything was entered since the wizard opened
+    pub fn is_dirty(&self) -> bool {
+        !self.client.name.is_empty() ||
+        !self.client.email.is_empty() ||
+        !self.client.phone.is_empty() ||
+        self.client.address.as_ref().is_some_and(|address| !address.is_empty()) ||
+        self.client.language != DEFAULT_LANGUAGE ||
+        !self.client.show_bank_details
+    }
+
+    // Start a new client over, asking first if anything would be lost
+    pub fn request_reset(&mut self) {
+        if self.is_dirty() {
+            self.confirm_reset = true;
+        } else {
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.profile_id);
+    }
+
     pub fn is_valid(&self) -> bool {
         !self.client.name.is_empty() &&
         !self.client.email.is_empty() &&
-        !self.client.phone.is_empty()
+        !self.client.phone.is_empty() &&
+        !self.client.language.trim().is_empty()
     }
 }
 
@@ -150,15 +232,25 @@ pub fn render_client_wizard<B: Backend>(f: &mut Frame<B>, state: &mut ClientWiza
 
     // Help text
     let help_text = if state.editing {
-        "Enter - Save field | Esc - Cancel editing"
+        "Enter - Save field | Ctrl+Z - Undo changes | Esc - Cancel editing"
+    } else if state.client.id == 0 {
+        "Enter - Edit field | Up/Down - Navigate fields | S - Save client | Ctrl+R - Reset | Esc - Cancel"
     } else {
         "Enter - Edit field | Up/Down - Navigate fields | S - Save client | Esc - Cancel"
     };
     
-    let help = Paragraph::new(help_text)
+    let help = Paragraph::new(footer_spans(help_text, chunks[2].width.saturating_sub(2) as usize))
         .style(Style::default().fg(Color::Gray))
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(help, chunks[2]);
+
+    if state.confirm_reset {
+        render_confirmation(f, "Reset", "Clear all fields and start over?");
+    }
+
+    if state.show_help {
+        render_help(f, HELP_ENTRIES);
+    }
 }
 
 fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ClientWizardState, area: Rect) {
@@ -167,16 +259,31 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ClientWizardState, area
         "Email",
         "Phone",
         "Address",
+        "Invoice Language",
+        "Show Bank Details",
+    ];
+
+    // Shown in empty fields as an example of what to enter
+    let field_hints = [
+        "e.g. Acme Corp",
+        "e.g. billing@acme.com",
+        "e.g. +1 555 123 4567",
+        "e.g. 42 Market St, Springfield",
+        "e.g. en, es, fr or de",
+        "",
     ];
 
     // Create a new empty string for the case when address is None
     let empty_string = String::new();
+    let bank_details = if state.client.show_bank_details { "Yes" } else { "No" }.to_string();
     
     let field_values = [
         &state.client.name,
         &state.client.email,
         &state.client.phone,
         state.client.address.as_ref().unwrap_or(&empty_string),
+        &state.client.language,
+        &bank_details,
     ];
 
     let items: Vec<ListItem> = field_names
@@ -204,7 +311,7 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ClientWizardState, area
                 
                 Spans::from(vec![
                     Span::styled(format!("{}: ", name), style),
-                    Span::raw(value.as_str()),
+                    value_or_hint(value, field_hints[i]),
                 ])
             };
 
@@ -221,7 +328,25 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ClientWizardState, area
 
 pub fn handle_input(state: &mut ClientWizardState) -> Result<Option<ClientWizardAction>> {
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+
+        if state.confirm_reset {
+            state.confirm_reset = false;
+            if key.code == KeyCode::Char('y') {
+                state.reset();
+            }
+            return Ok(None);
+        }
+
         match key.code {
+            KeyCode::Char('?') if !state.editing => {
+                state.show_help = true;
+            }
             KeyCode::Esc => {
                 if state.editing {
                     state.toggle_editing();
@@ -229,6 +354,9 @@ pub fn handle_input(state: &mut ClientWizardState) -> Result<Option<ClientWizard
                     return Ok(Some(ClientWizardAction::Cancel));
                 }
             }
+            KeyCode::Enter if state.current_field == ClientField::BankDetails => {
+                state.toggle_bank_details();
+            }
             KeyCode::Enter => {
                 if state.editing {
                     state.toggle_editing();
@@ -242,6 +370,13 @@ pub fn handle_input(state: &mut ClientWizardState) -> Result<Option<ClientWizard
             KeyCode::Down if !state.editing => {
                 state.next_field();
             }
+            KeyCode::Char('r') if !state.editing && state.client.id == 0 &&
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.request_reset();
+            }
+            KeyCode::Char('z') if state.editing && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.undo_edit();
+            }
             KeyCode::Char('s') if !state.editing => {
                 if state.is_valid() {
                     return Ok(Some(ClientWizardAction::Save(state.client.clone())));
@@ -255,4 +390,33 @@ pub fn handle_input(state: &mut ClientWizardState) -> Result<Option<ClientWizard
     }
 
     Ok(None)
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::client;
+
+    #[test]
+    fn undo_restores_the_value_from_before_the_edit() {
+        let mut state = ClientWizardState::from_existing(client(1, "Acme"));
+        state.toggle_editing();
+        for _ in 0..4 {
+            state.edit_current_field(KeyCode::Backspace);
+        }
+        state.edit_current_field(KeyCode::Char('X'));
+
+        state.undo_edit();
+        assert_eq!(state.client.name, "Acme");
+    }
+
+    #[test]
+    fn undo_only_applies_while_editing() {
+        let mut state = ClientWizardState::from_existing(client(1, "Acme"));
+        state.toggle_editing();
+        state.edit_current_field(KeyCode::Char('!'));
+        state.toggle_editing();
+
+        state.undo_edit();
+        assert_eq!(state.client.name, "Acme!");
+    }
+}
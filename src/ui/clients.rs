@@ -1,26 +1,67 @@
 use anyhow::Result;
+use std::collections::HashMap;
 use crossterm::event::{self, Event, KeyCode};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
 use crate::models::Client;
+use crate::money;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::{footer_spans, truncate_to_width};
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Select a client"),
+    ("Enter", "View the client's projects"),
+    ("N", "New client"),
+    ("E", "Edit the selected client"),
+    ("D", "Delete the selected client"),
+    ("M", "Merge the selected client into another one"),
+    ("S", "Export a statement PDF of unpaid invoices"),
+    ("I", "Import clients from a CSV file"),
+    ("/", "Filter clients by name"),
+    ("[ / ]", "Switch to the previous or next profile"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Clear the filter, or go back to profiles"),
+    ("Q", "Back to profiles"),
+];
+
+// Longest email or phone shown in the clients list before truncating
+const CONTACT_FIELD_WIDTH: usize = 32;
 
 // Represents the state of the client selection screen
 pub struct ClientsState {
     profile_id: i32,
     clients: Vec<Client>,
+    // Indices into clients that match the name filter, in display order
+    visible: Vec<usize>,
+    filter: String,
+    // Whether the name filter is being typed
+    filtering: bool,
+    outstanding: HashMap<i32, f64>, // Unpaid balances keyed by client id, missing when nothing is owed
+    // Currency of the profile that balances are shown in
+    currency: String,
     list_state: ListState,
     show_delete_confirmation: bool,
+    // Selection in the merge target picker, present while picking a target
+    merge_picker_state: Option<ListState>,
+    show_merge_confirmation: bool,
+    // Path typed into the CSV import prompt, present while the prompt is open
+    import_path: Option<String>,
+    message_title: String,
+    show_error: Option<String>,
+    show_success: Option<String>,
+    show_help: bool,
 }
 
 impl ClientsState {
-    pub fn new(profile_id: i32, clients: Vec<Client>) -> Self {
+    pub fn new(profile_id: i32, clients: Vec<Client>, outstanding: HashMap<i32, f64>) -> Self {
         let mut list_state = ListState::default();
         if !clients.is_empty() {
             list_state.select(Some(0));
@@ -28,20 +69,38 @@ impl ClientsState {
         
         Self {
             profile_id,
+            visible: (0..clients.len()).collect(),
             clients,
+            filter: String::new(),
+            filtering: false,
+            outstanding,
+            currency: money::DEFAULT_CURRENCY.to_string(),
             list_state,
             show_delete_confirmation: false,
+            merge_picker_state: None,
+            show_merge_confirmation: false,
+            import_path: None,
+            message_title: String::new(),
+            show_error: None,
+            show_success: None,
+            show_help: false,
         }
     }
 
+    // Show balances in the currency of the profile
+    pub fn with_currency(mut self, currency: String) -> Self {
+        self.currency = currency;
+        self
+    }
+
     pub fn next(&mut self) {
-        if self.clients.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
 
         let i = match self.list_state.selected() {
             Some(i) => {
-                if i >= self.clients.len() - 1 {
+                if i >= self.visible.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -53,14 +112,14 @@ impl ClientsState {
     }
 
     pub fn previous(&mut self) {
-        if self.clients.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
 
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.clients.len() - 1
+                    self.visible.len() - 1
                 } else {
                     i - 1
                 }
@@ -75,7 +134,10 @@ impl ClientsState {
     }
 
     pub fn selected_client(&self) -> Option<&Client> {
-        self.list_state.selected().and_then(|i| self.clients.get(i))
+        self.list_state
+            .selected()
+            .and_then(|i| self.visible.get(i))
+            .and_then(|&i| self.clients.get(i))
     }
     
     pub fn selected_client_id(&self) -> Option<i32> {
@@ -85,6 +147,103 @@ impl ClientsState {
     pub fn profile_id(&self) -> i32 {
         self.profile_id
     }
+    
+    // Clients the selected client can be merged into
+    pub fn merge_candidates(&self) -> Vec<&Client> {
+        let source_id = self.selected_client_id();
+        self.clients.iter().filter(|c| Some(c.id) != source_id).collect()
+    }
+    
+    pub fn start_merge(&mut self) {
+        let mut picker_state = ListState::default();
+        if !self.merge_candidates().is_empty() {
+            picker_state.select(Some(0));
+        }
+        self.merge_picker_state = Some(picker_state);
+        self.show_merge_confirmation = false;
+    }
+    
+    pub fn cancel_merge(&mut self) {
+        self.merge_picker_state = None;
+        self.show_merge_confirmation = false;
+    }
+    
+    pub fn is_picking_merge_target(&self) -> bool {
+        self.merge_picker_state.is_some()
+    }
+    
+    pub fn next_merge_target(&mut self) {
+        let len = self.merge_candidates().len();
+        if let Some(picker_state) = &mut self.merge_picker_state {
+            if len == 0 {
+                return;
+            }
+            let i = match picker_state.selected() {
+                Some(i) if i < len - 1 => i + 1,
+                _ => 0,
+            };
+            picker_state.select(Some(i));
+        }
+    }
+    
+    pub fn previous_merge_target(&mut self) {
+        let len = self.merge_candidates().len();
+        if let Some(picker_state) = &mut self.merge_picker_state {
+            if len == 0 {
+                return;
+            }
+            let i = match picker_state.selected() {
+                Some(i) if i > 0 => i - 1,
+                _ => len - 1,
+            };
+            picker_state.select(Some(i));
+        }
+    }
+    
+    pub fn selected_merge_target(&self) -> Option<&Client> {
+        let index = self.merge_picker_state.as_ref()?.selected()?;
+        self.merge_candidates().get(index).copied()
+    }
+    
+    // Report the outcome of a merge in a popup
+    pub fn show_merge_result(&mut self, result: Result<String, String>) {
+        self.show_result("Merge", result);
+    }
+    
+    // Show the outcome of an operation in a popup with the given title
+    pub fn show_result(&mut self, title: &str, result: Result<String, String>) {
+        self.message_title = title.to_string();
+        match result {
+            Ok(message) => self.show_success = Some(message),
+            Err(error) => self.show_error = Some(error),
+        }
+    }
+    
+    // Show only clients whose name contains the filter, ignoring case
+    pub fn set_filter(&mut self, filter: &str) {
+        let previous = self.selected_client_id();
+        let needle = filter.to_lowercase();
+        
+        self.filter = filter.to_string();
+        self.visible = self.clients
+            .iter()
+            .enumerate()
+            .filter(|(_, client)| client.name.to_lowercase().contains(&needle))
+            .map(|(i, _)| i)
+            .collect();
+        
+        // Keep the same client selected when it still matches
+        let selected = self.visible
+            .iter()
+            .position(|&i| Some(self.clients[i].id) == previous)
+            .or(if self.visible.is_empty() { None } else { Some(0) });
+        self.list_state.select(selected);
+    }
+    
+    pub fn clear_filter(&mut self) {
+        self.filtering = false;
+        self.set_filter("");
+    }
 }
 
 pub enum ClientAction {
@@ -93,15 +252,24 @@ pub enum ClientAction {
     EditClient(i32), // Contains client_id
     DeleteClient(i32), // Contains client_id
     SelectClient(i32), // Contains client_id
+    MergeClients(i32, i32), // Contains source client_id and target client_id
+    ExportStatement(i32), // Contains client_id
+    ImportCsv(i32, String), // Contains profile_id and the CSV path
+    PreviousProfile,
+    NextProfile,
 }
 
 // DB operations for clients
 pub async fn load_clients_by_profile(db: &crate::db::Database, profile_id: i32) -> Result<Vec<Client>> {
-    db.load_clients_by_profile(profile_id).await
+    Ok(db.load_clients_by_profile(profile_id).await?)
+}
+
+pub async fn load_client_outstanding_totals(db: &crate::db::Database, profile_id: i32) -> Result<HashMap<i32, f64>> {
+    Ok(db.get_client_outstanding_totals(profile_id).await?)
 }
 
 pub async fn delete_client(db: &crate::db::Database, id: i32) -> Result<()> {
-    db.delete_client(id).await
+    Ok(db.delete_client(id).await?)
 }
 
 pub fn render_clients<B: Backend>(frame: &mut Frame<B>, state: &mut ClientsState) {
@@ -116,17 +284,54 @@ pub fn render_clients<B: Backend>(frame: &mut Frame<B>, state: &mut ClientsState
         ].as_ref())
         .split(size);
 
-    // Create and render the clients list
+    // Create and render the clients list, leaving room for the borders
+    let name_width = chunks[0].width.saturating_sub(2) as usize;
     let items: Vec<ListItem> = state
-        .clients
+        .visible
         .iter()
-        .map(|client| {
-            ListItem::new(Spans::from(vec![Span::raw(&client.name)]))
+        .map(|&i| {
+            let client = &state.clients[i];
+            // Second line with the contact details, skipping any that are missing
+            let contact = [client.email.as_str(), client.phone.as_str()]
+                .iter()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(|value| truncate_to_width(value, CONTACT_FIELD_WIDTH))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            
+            // Outstanding balance beside the name, red while anything is owed
+            let outstanding = state.outstanding.get(&client.id).copied().unwrap_or(0.0);
+            let (badge, badge_color) = if money::round_cents(money::to_decimal(outstanding)).is_zero() {
+                (format!(" {} outstanding", money::format_money_in(0, &state.currency)), Color::DarkGray)
+            } else {
+                (format!(" {} outstanding", money::format_money_in(outstanding, &state.currency)), Color::Red)
+            };
+            let badge_width = badge.chars().count();
+            
+            ListItem::new(vec![
+                Spans::from(vec![
+                    Span::raw(truncate_to_width(&client.name, name_width.saturating_sub(badge_width))),
+                    Span::styled(badge, Style::default().fg(badge_color)),
+                ]),
+                Spans::from(vec![Span::styled(
+                    format!("  {}", if contact.is_empty() { "No contact details" } else { &contact }),
+                    Style::default().fg(Color::DarkGray),
+                )]),
+            ])
         })
         .collect();
 
+    let title = if state.filtering {
+        format!("Clients (filter: {}|)", state.filter)
+    } else if !state.filter.is_empty() {
+        format!("Clients (filter: {})", state.filter)
+    } else {
+        "Clients".to_string()
+    };
+
     let clients_list = List::new(items)
-        .block(Block::default().title("Clients").borders(Borders::ALL))
+        .block(Block::default().title(title).borders(Borders::ALL))
         .highlight_style(
             Style::default()
                 .bg(Color::Blue)
@@ -137,13 +342,17 @@ pub fn render_clients<B: Backend>(frame: &mut Frame<B>, state: &mut ClientsState
     frame.render_stateful_widget(clients_list, chunks[0], &mut state.list_state);
 
     // Create and render the buttons
-    let buttons_text = if state.selected_client().is_some() {
-        format!("<N> New Client | <E> Edit Client | <D> Delete Client | <Enter> View Projects | <Esc> Back")
+    let buttons_text = if state.filtering {
+        "<Enter> Done | <Esc> Clear filter".to_string()
+    } else if !state.filter.is_empty() {
+        "<Enter> View Projects | </> Edit Filter | <Esc> Clear Filter".to_string()
+    } else if state.selected_client().is_some() {
+        format!("<N> New Client | <E> Edit Client | <D> Delete Client | <M> Merge Into | <S> Statement PDF | <I> Import CSV | <Enter> View Projects | </> Filter | <[/]> Switch Profile | <Esc> Back")
     } else {
-        format!("<N> New Client | <Esc> Back")
+        format!("<N> New Client | <I> Import CSV | <[/]> Switch Profile | <Esc> Back")
     };
 
-    let buttons = Paragraph::new(buttons_text)
+    let buttons = Paragraph::new(footer_spans(&buttons_text, chunks[1].width as usize))
         .block(Block::default().borders(Borders::TOP))
         .style(Style::default().fg(Color::White));
 
@@ -153,6 +362,31 @@ pub fn render_clients<B: Backend>(frame: &mut Frame<B>, state: &mut ClientsState
     if state.show_delete_confirmation {
         render_delete_confirmation(frame, size);
     }
+    
+    // Render merge target picker and confirmation if needed
+    if state.is_picking_merge_target() {
+        if state.show_merge_confirmation {
+            render_merge_confirmation(frame, size, state);
+        } else {
+            render_merge_picker(frame, size, state);
+        }
+    }
+    
+    // Render import prompt if needed
+    if let Some(path) = &state.import_path {
+        render_import_prompt(frame, size, path);
+    }
+    
+    // Render merge result popup if needed
+    if let Some(error) = &state.show_error {
+        render_message(frame, size, &format!("{} Failed", state.message_title), error, Color::Red);
+    } else if let Some(message) = &state.show_success {
+        render_message(frame, size, &state.message_title, message, Color::Green);
+    }
+    
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
 }
 
 fn render_delete_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect) {
@@ -172,6 +406,89 @@ fn render_delete_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect) {
     frame.render_widget(popup, popup_area);
 }
 
+fn render_merge_picker<B: Backend>(frame: &mut Frame<B>, size: Rect, state: &mut ClientsState) {
+    let popup_area = centered_rect(50, 50, size);
+    
+    let source_name = state.selected_client().map(|c| c.name.clone()).unwrap_or_default();
+    let items: Vec<ListItem> = state
+        .merge_candidates()
+        .iter()
+        .map(|client| ListItem::new(Spans::from(vec![Span::raw(client.name.clone())])))
+        .collect();
+    
+    let picker = List::new(items)
+        .block(Block::default().title(format!("Merge \"{}\" into... (<Enter> Select, <Esc> Cancel)", source_name)).borders(Borders::ALL))
+        .style(Style::default().fg(Color::White).bg(Color::Black))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+    
+    frame.render_widget(Clear, popup_area);
+    if let Some(picker_state) = &mut state.merge_picker_state {
+        frame.render_stateful_widget(picker, popup_area, picker_state);
+    }
+}
+
+fn render_merge_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect, state: &ClientsState) {
+    let popup_area = centered_rect(50, 20, size);
+    
+    let source_name = state.selected_client().map(|c| c.name.as_str()).unwrap_or_default();
+    let target_name = state.selected_merge_target().map(|c| c.name.as_str()).unwrap_or_default();
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(format!("Merge \"{}\" into \"{}\"?", source_name, target_name)),
+        Spans::from(""),
+        Spans::from(format!("All projects will be moved and \"{}\" will be deleted.", source_name)),
+        Spans::from(""),
+        Spans::from("<Y> Yes  <N> No"),
+    ])
+    .block(Block::default().title("Confirm Merge").borders(Borders::ALL))
+    .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_import_prompt<B: Backend>(frame: &mut Frame<B>, size: Rect, path: &str) {
+    let popup_area = centered_rect(60, 25, size);
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from("Columns: name,email,phone,address"),
+        Spans::from(vec![
+            Span::raw("CSV path: "),
+            Span::styled(format!("{}|", path), Style::default().fg(Color::Yellow)),
+        ]),
+        Spans::from(""),
+        Spans::from("<Enter> Import | <Esc> Cancel"),
+    ])
+    .block(Block::default().title("Import Clients from CSV").borders(Borders::ALL))
+    .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_message<B: Backend>(frame: &mut Frame<B>, size: Rect, title: &str, message: &str, color: Color) {
+    let popup_area = centered_rect(60, 20, size);
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(message),
+        Spans::from(""),
+        Spans::from("Press any key to continue"),
+    ])
+    .block(Block::default().title(title).borders(Borders::ALL))
+    .style(Style::default().fg(color).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
 // Helper function to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -195,7 +512,59 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
 pub fn handle_input(state: &mut ClientsState) -> Result<Option<ClientAction>> {
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+        
+        // Any key dismisses the merge result popup
+        if state.show_error.is_some() || state.show_success.is_some() {
+            state.show_error = None;
+            state.show_success = None;
+            return Ok(None);
+        }
+        
+        if state.is_picking_merge_target() {
+            return Ok(handle_merge_input(state, key.code));
+        }
+        
+        if state.import_path.is_some() {
+            return Ok(handle_import_input(state, key.code));
+        }
+        
+        // Typing the name filter
+        if state.filtering {
+            match key.code {
+                KeyCode::Char(c) => {
+                    let filter = format!("{}{}", state.filter, c);
+                    state.set_filter(&filter);
+                }
+                KeyCode::Backspace => {
+                    let mut filter = state.filter.clone();
+                    filter.pop();
+                    state.set_filter(&filter);
+                }
+                KeyCode::Enter => state.filtering = false,
+                KeyCode::Esc => state.clear_filter(),
+                KeyCode::Down => state.next(),
+                KeyCode::Up => state.previous(),
+                _ => {}
+            }
+            return Ok(None);
+        }
+        
         match key.code {
+            KeyCode::Char('?') if !state.show_delete_confirmation => {
+                state.show_help = true;
+            }
+            KeyCode::Char('/') if !state.show_delete_confirmation => {
+                state.filtering = true;
+            }
+            KeyCode::Esc if !state.show_delete_confirmation && !state.filter.is_empty() => {
+                state.clear_filter();
+            }
             KeyCode::Char('q') | KeyCode::Esc => {
                 if state.show_delete_confirmation {
                     state.toggle_delete_confirmation();
@@ -228,6 +597,27 @@ pub fn handle_input(state: &mut ClientsState) -> Result<Option<ClientAction>> {
                     }
                 }
             }
+            KeyCode::Char('[') if !state.show_delete_confirmation => {
+                return Ok(Some(ClientAction::PreviousProfile));
+            }
+            KeyCode::Char(']') if !state.show_delete_confirmation => {
+                return Ok(Some(ClientAction::NextProfile));
+            }
+            KeyCode::Char('s') if !state.show_delete_confirmation => {
+                if let Some(id) = state.selected_client_id() {
+                    return Ok(Some(ClientAction::ExportStatement(id)));
+                }
+            }
+            KeyCode::Char('i') if !state.show_delete_confirmation => {
+                state.import_path = Some("clients.csv".to_string());
+            }
+            KeyCode::Char('m') if !state.show_delete_confirmation && state.selected_client().is_some() => {
+                if state.merge_candidates().is_empty() {
+                    state.show_error = Some("There are no other clients to merge into".to_string());
+                } else {
+                    state.start_merge();
+                }
+            }
             KeyCode::Down => {
                 if !state.show_delete_confirmation {
                     state.next();
@@ -249,4 +639,61 @@ pub fn handle_input(state: &mut ClientsState) -> Result<Option<ClientAction>> {
         }
     }
     Ok(None)
-} 
\ No newline at end of file
+} 
+
+// Handle keys while the merge target picker or its confirmation is open
+fn handle_merge_input(state: &mut ClientsState, key: KeyCode) -> Option<ClientAction> {
+    if state.show_merge_confirmation {
+        match key {
+            KeyCode::Char('y') => {
+                let source_id = state.selected_client_id();
+                let target_id = state.selected_merge_target().map(|c| c.id);
+                state.cancel_merge();
+                if let (Some(source_id), Some(target_id)) = (source_id, target_id) {
+                    return Some(ClientAction::MergeClients(source_id, target_id));
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {
+                state.show_merge_confirmation = false;
+            }
+            _ => {}
+        }
+        return None;
+    }
+    
+    match key {
+        KeyCode::Esc => state.cancel_merge(),
+        KeyCode::Down => state.next_merge_target(),
+        KeyCode::Up => state.previous_merge_target(),
+        KeyCode::Enter if state.selected_merge_target().is_some() => {
+            state.show_merge_confirmation = true;
+        }
+        _ => {}
+    }
+    
+    None
+}
+
+// Handle keys while the CSV import prompt is open
+fn handle_import_input(state: &mut ClientsState, key: KeyCode) -> Option<ClientAction> {
+    let path = state.import_path.as_mut()?;
+    
+    match key {
+        KeyCode::Esc => {
+            state.import_path = None;
+        }
+        KeyCode::Backspace => {
+            path.pop();
+        }
+        KeyCode::Char(c) => {
+            path.push(c);
+        }
+        KeyCode::Enter if !path.trim().is_empty() => {
+            let path = state.import_path.take()?;
+            return Some(ClientAction::ImportCsv(state.profile_id, path.trim().to_string()));
+        }
+        _ => {}
+    }
+    
+    None
+}
@@ -0,0 +1,64 @@
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Spans,
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+// Yes/no popup drawn over the current screen
+pub fn render_confirmation<B: Backend>(frame: &mut Frame<B>, title: &str, message: &str) {
+    let popup_area = centered_rect(60, 20, frame.size());
+
+    let confirmation = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(message),
+        Spans::from(""),
+        Spans::from("Press Y to confirm, or any other key to go back"),
+    ])
+    .block(Block::default().title(title).borders(Borders::ALL))
+    .style(Style::default().fg(Color::Yellow).bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(confirmation, popup_area);
+}
+
+// Error popup drawn over the current screen, dismissed with any key
+pub fn render_error<B: Backend>(frame: &mut Frame<B>, title: &str, message: &str) {
+    let popup_area = centered_rect(60, 25, frame.size());
+
+    let error = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(message),
+        Spans::from(""),
+        Spans::from("Press any key to continue"),
+    ])
+    .block(Block::default().title(title).borders(Borders::ALL))
+    .style(Style::default().fg(Color::Red).bg(Color::Black))
+    .wrap(Wrap { trim: true });
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(error, popup_area);
+}
+
+// Area of the given size in percent, centered in r, for popups drawn over a screen
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
@@ -22,6 +22,10 @@ pub struct DateInputState {
     pub editing: bool,
     pub date_part: DatePart,
     pub current_date_input: String,
+    // Move on by itself once a part is fully typed
+    pub auto_advance: bool,
+    // Set when auto-advance finished typing the day, cleared by take_completed
+    completed: bool,
 }
 
 impl DateInputState {
@@ -31,9 +35,21 @@ impl DateInputState {
             editing: false,
             date_part: DatePart::Year,
             current_date_input: String::new(),
+            auto_advance: false,
+            completed: false,
         }
     }
 
+    pub fn with_auto_advance(mut self, auto_advance: bool) -> Self {
+        self.auto_advance = auto_advance;
+        self
+    }
+
+    // Whether the whole date was just typed and the field should be committed
+    pub fn take_completed(&mut self) -> bool {
+        std::mem::take(&mut self.completed)
+    }
+
     pub fn toggle_editing(&mut self) {
         self.editing = !self.editing;
         if self.editing {
@@ -77,12 +93,17 @@ impl DateInputState {
                         if self.current_date_input.len() == 4 {
                             if let Ok(new_year) = self.current_date_input.parse::<i32>() {
                                 if new_year >= 1900 && new_year <= 2100 {
+                                    // Clamp the day so Feb 29 becomes Feb 28 in a non-leap year
+                                    let day = day.min(days_in_month(new_year, month));
                                     if let Some(new_date) = NaiveDate::from_ymd_opt(new_year, month, day) {
                                         self.date = new_date;
                                     }
                                 }
                             }
                             self.current_date_input.clear();
+                            if self.auto_advance {
+                                self.next_date_part();
+                            }
                         } else if self.current_date_input.len() > 4 {
                             self.current_date_input = self.current_date_input.chars().rev().take(4).collect::<String>().chars().rev().collect();
                         }
@@ -92,12 +113,17 @@ impl DateInputState {
                         if self.current_date_input.len() == 2 {
                             if let Ok(new_month) = self.current_date_input.parse::<u32>() {
                                 if new_month >= 1 && new_month <= 12 {
+                                    // Clamp the day so e.g. Jan 31 -> month 02 gives Feb 28/29
+                                    let day = day.min(days_in_month(year, new_month));
                                     if let Some(new_date) = NaiveDate::from_ymd_opt(year, new_month, day) {
                                         self.date = new_date;
                                     }
                                 }
                             }
                             self.current_date_input.clear();
+                            if self.auto_advance {
+                                self.next_date_part();
+                            }
                         }
                     }
                     DatePart::Day => {
@@ -112,6 +138,7 @@ impl DateInputState {
                                 }
                             }
                             self.current_date_input.clear();
+                            self.completed = self.auto_advance;
                         }
                     }
                 }
@@ -171,4 +198,169 @@ fn days_in_month(year: i32, month: u32) -> u32 {
         }
         _ => 30, // Default case (shouldn't happen with valid input)
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn editing(date: NaiveDate) -> DateInputState {
+        let mut state = DateInputState::new(date);
+        state.toggle_editing();
+        state
+    }
+
+    fn type_digits(state: &mut DateInputState, digits: &str) {
+        for c in digits.chars() {
+            state.handle_input(KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn typing_a_full_date_sets_each_part() {
+        let mut state = editing(date(2024, 1, 15));
+        type_digits(&mut state, "2025");
+        state.handle_input(KeyCode::Right);
+        type_digits(&mut state, "03");
+        state.handle_input(KeyCode::Right);
+        type_digits(&mut state, "07");
+        assert_eq!(state.date, date(2025, 3, 7));
+        assert!(state.current_date_input.is_empty());
+    }
+
+    #[test]
+    fn auto_advance_moves_through_the_parts_and_completes() {
+        let mut state = editing(date(2024, 1, 15)).with_auto_advance(true);
+        type_digits(&mut state, "2025");
+        assert!(state.date_part == DatePart::Month);
+        type_digits(&mut state, "03");
+        assert!(state.date_part == DatePart::Day);
+        assert!(!state.take_completed());
+        type_digits(&mut state, "07");
+        assert_eq!(state.date, date(2025, 3, 7));
+        assert!(state.take_completed());
+        assert!(!state.take_completed());
+    }
+
+    #[test]
+    fn rejects_february_30() {
+        let mut state = editing(date(2024, 2, 10));
+        state.date_part = DatePart::Day;
+        type_digits(&mut state, "30");
+        assert_eq!(state.date, date(2024, 2, 10));
+    }
+
+    #[test]
+    fn accepts_february_29_only_in_leap_years() {
+        let mut state = editing(date(2024, 2, 10));
+        state.date_part = DatePart::Day;
+        type_digits(&mut state, "29");
+        assert_eq!(state.date, date(2024, 2, 29));
+
+        let mut state = editing(date(2023, 2, 10));
+        state.date_part = DatePart::Day;
+        type_digits(&mut state, "29");
+        assert_eq!(state.date, date(2023, 2, 10));
+    }
+
+    #[test]
+    fn days_in_month_follows_month_length_and_leap_years() {
+        assert_eq!(days_in_month(2023, 1), 31);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+    }
+
+    #[test]
+    fn last_day_is_accepted_and_the_day_after_rejected() {
+        let mut state = editing(date(2023, 4, 1));
+        state.date_part = DatePart::Day;
+        type_digits(&mut state, "31");
+        assert_eq!(state.date, date(2023, 4, 1));
+        type_digits(&mut state, "30");
+        assert_eq!(state.date, date(2023, 4, 30));
+    }
+
+    #[test]
+    fn out_of_range_parts_are_ignored() {
+        let mut state = editing(date(2024, 1, 15));
+        type_digits(&mut state, "1850");
+        assert_eq!(state.date, date(2024, 1, 15));
+        state.handle_input(KeyCode::Right);
+        type_digits(&mut state, "13");
+        assert_eq!(state.date, date(2024, 1, 15));
+        state.handle_input(KeyCode::Right);
+        type_digits(&mut state, "00");
+        assert_eq!(state.date, date(2024, 1, 15));
+    }
+
+    #[test]
+    fn changing_the_month_clamps_the_day_to_its_length() {
+        let mut state = editing(date(2023, 1, 31));
+        state.date_part = DatePart::Month;
+        type_digits(&mut state, "02");
+        assert_eq!(state.date, date(2023, 2, 28));
+
+        let mut state = editing(date(2024, 1, 31));
+        state.date_part = DatePart::Month;
+        type_digits(&mut state, "04");
+        assert_eq!(state.date, date(2024, 4, 30));
+    }
+
+    #[test]
+    fn changing_the_year_clamps_february_29() {
+        let mut state = editing(date(2024, 2, 29));
+        type_digits(&mut state, "2023");
+        assert_eq!(state.date, date(2023, 2, 28));
+    }
+
+    #[test]
+    fn backspace_removes_the_last_typed_digit() {
+        let mut state = editing(date(2024, 1, 15));
+        type_digits(&mut state, "209");
+        state.handle_input(KeyCode::Backspace);
+        assert_eq!(state.current_date_input, "20");
+        type_digits(&mut state, "25");
+        assert_eq!(state.date, date(2025, 1, 15));
+
+        state.handle_input(KeyCode::Backspace);
+        assert!(state.current_date_input.is_empty());
+        assert_eq!(state.date, date(2025, 1, 15));
+    }
+
+    #[test]
+    fn input_is_ignored_while_not_editing() {
+        let mut state = DateInputState::new(date(2024, 1, 15));
+        type_digits(&mut state, "2025");
+        assert_eq!(state.date, date(2024, 1, 15));
+        assert!(state.current_date_input.is_empty());
+    }
+
+    #[test]
+    fn display_string_marks_the_part_being_edited() {
+        let mut state = DateInputState::new(date(2024, 1, 5));
+        assert_eq!(state.get_display_string(), "2024-01-05");
+
+        state.toggle_editing();
+        assert_eq!(state.get_display_string(), "2024[YYYY]-01-05");
+        type_digits(&mut state, "20");
+        assert_eq!(state.get_display_string(), "2024[20]-01-05");
+
+        state.handle_input(KeyCode::Right);
+        assert_eq!(state.get_display_string(), "2024-01[MM]-05");
+        type_digits(&mut state, "1");
+        assert_eq!(state.get_display_string(), "2024-01[1]-05");
+
+        state.handle_input(KeyCode::Right);
+        assert_eq!(state.get_display_string(), "2024-01-05[DD]");
+
+        state.toggle_editing();
+        assert_eq!(state.get_display_string(), "2024-01-05");
+    }
+}
@@ -0,0 +1,59 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::ui::components::text::truncate_to_width;
+
+// Popup listing every key binding of the current screen as (keys, what they do) pairs,
+// drawn over the screen while ? is toggled on
+pub fn render_help<B: Backend>(frame: &mut Frame<B>, entries: &[(&str, &str)]) {
+    let size = frame.size();
+    let key_width = entries.iter().map(|(keys, _)| keys.width()).max().unwrap_or(0);
+    let content_width = entries
+        .iter()
+        .map(|(_, action)| key_width + 2 + action.width())
+        .max()
+        .unwrap_or(0);
+
+    // Sized to the entries plus borders, a blank line and the closing hint
+    let width = (content_width as u16 + 4).max(40).min(size.width);
+    let height = (entries.len() as u16 + 4).min(size.height);
+    let popup_area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let inner_width = width.saturating_sub(2) as usize;
+    let mut lines: Vec<Spans> = entries
+        .iter()
+        .map(|(keys, action)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!(" {:<width$}  ", keys, width = key_width),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(truncate_to_width(action, inner_width.saturating_sub(key_width + 3))),
+            ])
+        })
+        .collect();
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        " Press ? or Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let help = Paragraph::new(lines)
+        .block(Block::default().title("Keyboard Shortcuts").borders(Borders::ALL))
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(help, popup_area);
+}
@@ -1 +1,4 @@
-pub mod date_input; 
\ No newline at end of file
+pub mod confirm;
+pub mod date_input;
+pub mod help_overlay;
+pub mod text;
@@ -0,0 +1,87 @@
+use tui::{
+    style::{Color, Style},
+    text::{Span, Spans},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+// Shorten text to fit in `width` terminal columns, marking the cut with an ellipsis.
+// Widths are display widths, so wide characters such as CJK count as two columns.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    // Leave one column for the ellipsis
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width - 1 {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+// A form field's value, or a dim example of the expected format when it's empty.
+// Only used for display, the hint never becomes part of the value.
+pub fn value_or_hint<'a>(value: &'a str, hint: &'a str) -> Span<'a> {
+    if value.is_empty() {
+        Span::styled(hint, Style::default().fg(Color::DarkGray))
+    } else {
+        Span::raw(value)
+    }
+}
+
+const HELP_HINT: &str = "Press ? for help";
+
+// A screen's footer: its action hints followed by a dim pointer to the help overlay.
+// On narrow terminals the action hints are cut first, then the help pointer is dropped.
+pub fn footer_spans(hints: &str, width: usize) -> Spans<'static> {
+    let separator = " | ";
+    let hint_width = separator.width() + HELP_HINT.width();
+    // Keep at least a few columns of the screen's own hints before showing the pointer
+    if width < hint_width + 12 {
+        return Spans::from(truncate_to_width(hints, width));
+    }
+
+    Spans::from(vec![
+        Span::raw(truncate_to_width(hints, width - hint_width)),
+        Span::raw(separator),
+        Span::styled(HELP_HINT, Style::default().fg(Color::DarkGray)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_that_fits_is_unchanged() {
+        assert_eq!(truncate_to_width("Acme", 4), "Acme");
+        assert_eq!(truncate_to_width("Acme", 10), "Acme");
+        assert_eq!(truncate_to_width("", 0), "");
+    }
+
+    #[test]
+    fn long_text_ends_in_an_ellipsis_within_the_width() {
+        assert_eq!(truncate_to_width("Acme Corporation", 8), "Acme Co…");
+        assert_eq!(truncate_to_width("Acme", 1), "…");
+        assert_eq!(truncate_to_width("Acme", 0), "");
+    }
+
+    #[test]
+    fn multi_byte_characters_are_cut_on_their_display_width() {
+        // Accented letters are one column, CJK characters are two
+        assert_eq!(truncate_to_width("Café Müller", 6), "Café …");
+        assert_eq!(truncate_to_width("株式会社テスト", 7), "株式会…");
+        assert_eq!(truncate_to_width("株式会社テスト", 6), "株式…");
+        assert_eq!(truncate_to_width("株式会社テスト", 6).width(), 5);
+    }
+}
@@ -1,21 +1,46 @@
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode};
+use unicode_width::UnicodeWidthStr;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::Spans,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
+use rust_decimal::Decimal;
 use std::path::Path;
 use std::fs;
 use lettre::{
-    Message, SmtpTransport, Transport, message::{MultiPart, SinglePart, Attachment, header},
-    transport::smtp::authentication::Credentials,
+    Message, SmtpTransport, Transport, message::{MultiPart, SinglePart, Attachment, Mailbox, header},
+    transport::smtp::{
+        authentication::Credentials,
+        client::{Tls, TlsParameters},
+        SmtpTransportBuilder,
+    },
 };
 
+use crate::config::{Config, LineItemSort, SmtpEncryption};
+use crate::error::{self, AppError};
+use crate::invoice_gen::{escape_html, generate_plaintext, Balances};
 use crate::models::{Invoice, InvoiceLineItem};
+use crate::ui::components::confirm::render_confirmation;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::footer_spans;
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Tab / Shift+Tab", "Next or previous field"),
+    ("Enter", "Next field, a new line in the message, or send"),
+    ("Ctrl+O", "Load the message from a file"),
+    ("Ctrl+T", "Include the invoice inline as HTML"),
+    ("Ctrl+P", "Attach the invoice PDF"),
+    ("Ctrl+A", "Allow sending an invoice with a $0 total"),
+    ("Ctrl+V", "Preview the invoice as plain text"),
+    ("?", "Show or hide this help, from the send button"),
+    ("Esc", "Cancel"),
+];
 
 // Represents the state of the email wizard
 pub struct EmailWizardState {
@@ -23,6 +48,9 @@ pub struct EmailWizardState {
     invoice: Option<Invoice>,
     line_items: Option<Vec<InvoiceLineItem>>,
     recipient_email: String,
+    // Comma separated copy recipients, left out of the email when empty
+    cc: String,
+    bcc: String,
     subject: String,
     message: String,
     current_field: EmailField,
@@ -33,12 +61,33 @@ pub struct EmailWizardState {
     generated_pdf_path: Option<String>,
     // Flag to indicate the wizard should be dismissed
     dismissing: bool,
+    // Path being typed when loading the message body from a file
+    message_file_prompt: Option<String>,
+    // Send the invoice as the HTML body, with the PDF attachment becoming optional
+    inline_html: bool,
+    attach_pdf: bool,
+    generated_html: Option<String>,
+    // Only asking for a single confirmation before sending, the fields are already filled in
+    quick_send: bool,
+    // Intentional $0 or negative invoices, such as credits, may be sent
+    allow_zero_total: bool,
+    // Recipients outside these domains need an extra confirmation, empty allows all
+    allowed_domains: Vec<String>,
+    confirm_external: bool,
+    // Scroll offset of the plain text invoice preview, None when it's hidden
+    preview_scroll: Option<u16>,
+    line_item_sort: LineItemSort,
+    // Currency of the invoice's profile, used for the amounts in the message and preview
+    currency: String,
+    show_help: bool,
 }
 
 // Represents the current field being edited
 #[derive(Clone, Copy, PartialEq)]
 pub enum EmailField {
     RecipientEmail,
+    Cc,
+    Bcc,
     Subject,
     Message,
     None,
@@ -57,6 +106,8 @@ impl EmailWizardState {
             invoice: None,
             line_items: None,
             recipient_email: String::new(),
+            cc: String::new(),
+            bcc: String::new(),
             subject: String::new(),
             message: String::new(),
             current_field: EmailField::RecipientEmail,
@@ -65,12 +116,99 @@ impl EmailWizardState {
             generated_md_path: None,
             generated_pdf_path: None,
             dismissing: false,
+            message_file_prompt: None,
+            inline_html: false,
+            attach_pdf: true,
+            generated_html: None,
+            quick_send: false,
+            allow_zero_total: false,
+            allowed_domains: Vec::new(),
+            confirm_external: false,
+            preview_scroll: None,
+            line_item_sort: LineItemSort::default(),
+            currency: crate::money::DEFAULT_CURRENCY.to_string(),
+            show_help: false,
         }
     }
 
-    pub fn load_invoice(&mut self, invoice: Invoice, line_items: Vec<InvoiceLineItem>, project_name: String, client_email: String) {
+    pub fn with_inline_html(mut self, inline_html: bool) -> Self {
+        self.inline_html = inline_html;
+        self
+    }
+
+    pub fn with_allowed_domains(mut self, allowed_domains: Vec<String>) -> Self {
+        self.allowed_domains = allowed_domains;
+        self
+    }
+
+    // Order of the line items in the preview, matching the generated invoice
+    pub fn with_line_item_sort(mut self, line_item_sort: LineItemSort) -> Self {
+        self.line_item_sort = line_item_sort;
+        self
+    }
+
+    pub fn toggle_preview(&mut self) {
+        self.preview_scroll = match self.preview_scroll {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    // Plain text version of the invoice being sent, empty until it's loaded
+    pub fn preview_text(&self) -> String {
+        self.loaded_invoice()
+            .map(|(invoice, line_items)| generate_plaintext(invoice, line_items, self.line_item_sort, &self.currency))
+            .unwrap_or_default()
+    }
+
+    // Move the preview by the given number of lines, stopping at the last line
+    fn scroll_preview(&mut self, lines: i32) {
+        let last_line = self.preview_text().lines().count().saturating_sub(1) as i32;
+        if let Some(scroll) = &mut self.preview_scroll {
+            *scroll = (*scroll as i32 + lines).clamp(0, last_line) as u16;
+        }
+    }
+
+    // Whether any recipient is outside the allowed domains, and sending needs confirming
+    pub fn is_external_recipient(&self) -> bool {
+        self.external_domain().is_some()
+    }
+
+    // Domain of the first recipient, including copies, that isn't in the allowed domains
+    fn external_domain(&self) -> Option<&str> {
+        if self.allowed_domains.is_empty() {
+            return None;
+        }
+        
+        std::iter::once(self.recipient_email.as_str())
+            .chain(address_list(&self.cc))
+            .chain(address_list(&self.bcc))
+            .map(|email| recipient_domain(email).unwrap_or_default())
+            .find(|domain| !self.allowed_domains.iter().any(|allowed| allowed.trim().eq_ignore_ascii_case(domain)))
+    }
+
+    pub fn toggle_inline_html(&mut self) {
+        self.inline_html = !self.inline_html;
+        // Without the HTML body the PDF is the only copy of the invoice
+        if !self.inline_html {
+            self.attach_pdf = true;
+        }
+    }
+
+    pub fn toggle_allow_zero_total(&mut self) {
+        self.allow_zero_total = !self.allow_zero_total;
+    }
+
+    pub fn toggle_attach_pdf(&mut self) {
+        if self.inline_html {
+            self.attach_pdf = !self.attach_pdf;
+        }
+    }
+
+    pub fn load_invoice(&mut self, invoice: Invoice, line_items: Vec<InvoiceLineItem>, project_name: String, client_email: String, currency: String) {
         // Extract values we need for formatting before moving the invoice
         let invoice_number = invoice.number;
+        self.currency = currency;
         
         // Set default subject with project name instead of ID
         self.subject = format!("Invoice #{} for {}", invoice_number, project_name);
@@ -93,7 +231,7 @@ impl EmailWizardState {
                 Invoice Number: {}\n\
                 Submit Date: {}\n\
                 Due Date: {}\n\
-                Total Amount: ${:.2}\n\n\
+                Total Amount: {}\n\n\
                 Thank you for your business.\n\
                 Please let me know if you have any questions.\n\n\
                 Regards,\n\
@@ -102,24 +240,53 @@ impl EmailWizardState {
                 invoice.number,
                 invoice.submit_date.format("%Y-%m-%d"),
                 invoice.due_date.format("%Y-%m-%d"),
-                self.calculate_total_amount(),
+                crate::money::format_money_in(self.calculate_total_amount(), &self.currency),
             )
         } else {
             String::new()
         }
     }
     
-    fn calculate_total_amount(&self) -> f64 {
+    // Replace the message with the contents of a file, filling in invoice placeholders
+    pub fn load_message_from_file(&mut self, path: &str) -> Result<(), String> {
+        let path = path.trim();
+        if !Path::new(path).is_file() {
+            return Err(format!("File not found: {}", path));
+        }
+        
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        
+        self.message = self.fill_placeholders(&content);
+        Ok(())
+    }
+    
+    // Substitute {invoice_number}, {submit_date}, {due_date} and {total} in a message template
+    fn fill_placeholders(&self, template: &str) -> String {
+        if let Some(invoice) = &self.invoice {
+            template
+                .replace("{invoice_number}", &invoice.number.to_string())
+                .replace("{submit_date}", &invoice.submit_date.format("%Y-%m-%d").to_string())
+                .replace("{due_date}", &invoice.due_date.format("%Y-%m-%d").to_string())
+                .replace("{total}", &crate::money::format_money_in(self.calculate_total_amount(), &self.currency))
+        } else {
+            template.to_string()
+        }
+    }
+    
+    fn calculate_total_amount(&self) -> Decimal {
         if let (Some(invoice), Some(line_items)) = (&self.invoice, &self.line_items) {
-            line_items.iter().map(|item| item.hours * invoice.rate).sum()
+            crate::money::invoice_total(invoice, line_items)
         } else {
-            0.0
+            Decimal::ZERO
         }
     }
     
     pub fn next_field(&mut self) {
         match self.current_field {
-            EmailField::RecipientEmail => self.current_field = EmailField::Subject,
+            EmailField::RecipientEmail => self.current_field = EmailField::Cc,
+            EmailField::Cc => self.current_field = EmailField::Bcc,
+            EmailField::Bcc => self.current_field = EmailField::Subject,
             EmailField::Subject => self.current_field = EmailField::Message,
             EmailField::Message => self.current_field = EmailField::None,
             EmailField::None => {}
@@ -129,7 +296,9 @@ impl EmailWizardState {
     pub fn previous_field(&mut self) {
         match self.current_field {
             EmailField::RecipientEmail => {},
-            EmailField::Subject => self.current_field = EmailField::RecipientEmail,
+            EmailField::Cc => self.current_field = EmailField::RecipientEmail,
+            EmailField::Bcc => self.current_field = EmailField::Cc,
+            EmailField::Subject => self.current_field = EmailField::Bcc,
             EmailField::Message => self.current_field = EmailField::Subject,
             EmailField::None => self.current_field = EmailField::Message,
         }
@@ -144,6 +313,14 @@ impl EmailWizardState {
                     self.recipient_email.push(input);
                 }
             },
+            EmailField::Cc | EmailField::Bcc => {
+                let field = if self.current_field == EmailField::Cc { &mut self.cc } else { &mut self.bcc };
+                if input == '\u{7f}' { // Backspace
+                    field.pop();
+                } else {
+                    field.push(input);
+                }
+            },
             EmailField::Subject => {
                 if input == '\u{7f}' { // Backspace
                     self.subject.pop();
@@ -174,14 +351,74 @@ impl EmailWizardState {
             return Err("Invalid email address".into());
         }
         
+        // Every copy recipient has to be a valid address on its own
+        parse_mailboxes(&self.cc).map_err(|e| format!("Invalid CC address {}", e))?;
+        parse_mailboxes(&self.bcc).map_err(|e| format!("Invalid BCC address {}", e))?;
+        
         // Validate subject
         if self.subject.is_empty() {
             return Err("Subject cannot be empty".into());
         }
         
+        // Drafts are reviewed and approved before they go out
+        if self.invoice.as_ref().is_some_and(|invoice| invoice.status == "Draft") {
+            return Err("Invoice is still a draft. Approve it before sending".into());
+        }
+        
+        // Drafts can be saved without line items, but there's nothing to bill yet
+        if self.line_items.as_ref().is_some_and(|items| items.is_empty()) {
+            return Err("Invoice has no line items. Finish the draft before sending it".into());
+        }
+        
+        // Usually line items with zero hours, rather than a credit
+        let total = self.calculate_total_amount();
+        if total <= Decimal::ZERO && !self.allow_zero_total {
+            return Err(format!(
+                "Invoice total is {}. Fix the line items, or press Ctrl+A to send it anyway",
+                crate::money::format_money_in(total, &self.currency)
+            ));
+        }
+        
         Ok(())
     }
     
+    // Skip the full wizard, using the message template file and the pre-filled recipient.
+    // Falls back to the full wizard, explaining why, when either isn't usable.
+    pub fn start_quick_send(&mut self, message_file: Option<&str>) {
+        let result = message_file
+            .ok_or_else(|| "no QUICK_SEND_MESSAGE_FILE is configured".to_string())
+            .and_then(|path| {
+                self.validate()?;
+                self.load_message_from_file(path)
+            });
+        
+        match result {
+            Ok(()) => self.quick_send = true,
+            Err(e) => self.show_error = Some(format!("Quick send unavailable, {}", e)),
+        }
+    }
+    
+    pub fn is_quick_send(&self) -> bool {
+        self.quick_send
+    }
+    
+    pub fn recipient_email(&self) -> &str {
+        &self.recipient_email
+    }
+    
+    // Everyone the email goes to, as recorded in the sent history. Copies are listed
+    // after the recipient when there are any, e.g. "a@x.com; cc: b@x.com, c@x.com; bcc: d@x.com"
+    pub fn all_recipients(&self) -> String {
+        let mut recipients = self.recipient_email.trim().to_string();
+        for (label, list) in [("cc", &self.cc), ("bcc", &self.bcc)] {
+            let addresses: Vec<&str> = address_list(list).collect();
+            if !addresses.is_empty() {
+                recipients.push_str(&format!("; {}: {}", label, addresses.join(", ")));
+            }
+        }
+        recipients
+    }
+    
     // Clean up any generated files
     pub fn cleanup_files(&self) -> Result<()> {
         if let Some(md_path) = &self.generated_md_path {
@@ -219,6 +456,14 @@ impl EmailWizardState {
     pub fn has_success_message(&self) -> bool {
         self.show_success.is_some()
     }
+    
+    // The invoice and line items loaded into the wizard, i.e. what gets sent
+    pub fn loaded_invoice(&self) -> Option<(&Invoice, &[InvoiceLineItem])> {
+        match (&self.invoice, &self.line_items) {
+            (Some(invoice), Some(line_items)) => Some((invoice, line_items.as_slice())),
+            _ => None,
+        }
+    }
 }
 
 // Clean up on drop to ensure we always clean up files even if there's an error
@@ -239,6 +484,7 @@ pub fn render_email_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut EmailWi
         .constraints([
             Constraint::Length(3), // Title
             Constraint::Length(3), // Email recipient
+            Constraint::Length(3), // CC and BCC
             Constraint::Length(3), // Subject
             Constraint::Min(8),    // Message body
             Constraint::Length(3), // Buttons/navigation
@@ -271,6 +517,29 @@ pub fn render_email_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut EmailWi
     
     frame.render_widget(email_field, chunks[1]);
     
+    // Render the CC and BCC fields side by side
+    let copy_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[2]);
+    
+    for (field, value, title, area) in [
+        (EmailField::Cc, &state.cc, "CC (comma separated)", copy_chunks[0]),
+        (EmailField::Bcc, &state.bcc, "BCC (comma separated)", copy_chunks[1]),
+    ] {
+        let style = if state.current_field == field {
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        
+        let copy_field = Paragraph::new(value.clone())
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        
+        frame.render_widget(copy_field, area);
+    }
+    
     // Render subject field
     let subject_style = if state.current_field == EmailField::Subject {
         Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
@@ -282,7 +551,7 @@ pub fn render_email_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut EmailWi
         .style(subject_style)
         .block(Block::default().borders(Borders::ALL).title("Subject"));
     
-    frame.render_widget(subject_field, chunks[2]);
+    frame.render_widget(subject_field, chunks[3]);
     
     // Render message field
     let message_style = if state.current_field == EmailField::Message {
@@ -291,23 +560,75 @@ pub fn render_email_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut EmailWi
         Style::default()
     };
     
-    let message_field = Paragraph::new(state.message.clone())
+    let message_title = match (state.inline_html, state.attach_pdf) {
+        (false, _) => "Message (PDF attached)",
+        (true, true) => "Message (invoice inline as HTML, PDF attached)",
+        (true, false) => "Message (invoice inline as HTML, no attachment)",
+    };
+    let message_title = if state.allow_zero_total {
+        format!("{} - sending with a $0 total", message_title)
+    } else {
+        message_title.to_string()
+    };
+    // Wrapped within the box, with a cursor at the end while it's being typed
+    let message_text = if state.current_field == EmailField::Message {
+        format!("{}|", state.message)
+    } else {
+        state.message.clone()
+    };
+    // Scroll so the end of a long message stays in view while typing
+    let scroll = if state.current_field == EmailField::Message {
+        let inner_width = chunks[4].width.saturating_sub(2).max(1) as usize;
+        let wrapped_lines: usize = message_text
+            .split('\n')
+            .map(|line| line.width().div_ceil(inner_width).max(1))
+            .sum();
+        wrapped_lines.saturating_sub(chunks[4].height.saturating_sub(2) as usize) as u16
+    } else {
+        0
+    };
+    let message_field = Paragraph::new(message_text)
         .style(message_style)
-        .block(Block::default().borders(Borders::ALL).title("Message"));
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(Block::default().borders(Borders::ALL).title(message_title));
     
-    frame.render_widget(message_field, chunks[3]);
+    frame.render_widget(message_field, chunks[4]);
     
     // Render navigation/buttons
     let buttons_text = match state.current_field {
-        EmailField::None => "<Enter> Send | <Tab> Back to Fields | <Esc> Cancel",
-        _ => "<Tab> Next Field | <Shift+Tab> Previous Field | <Enter> Send | <Esc> Cancel",
+        EmailField::None => "<Enter> Send | <Tab> Back to Fields | <Ctrl+O> Load Message File | <Ctrl+T> Inline HTML | <Ctrl+P> Attach PDF | <Ctrl+V> Preview | <Esc> Cancel",
+        EmailField::Message => "<Enter> New Line | <Tab> Next Field | <Shift+Tab> Previous Field | <Ctrl+O> Load Message File | <Ctrl+T> Inline HTML | <Ctrl+P> Attach PDF | <Ctrl+V> Preview | <Esc> Cancel",
+        _ => "<Tab> Next Field | <Shift+Tab> Previous Field | <Enter> Send | <Ctrl+O> Load Message File | <Ctrl+T> Inline HTML | <Ctrl+P> Attach PDF | <Ctrl+V> Preview | <Esc> Cancel",
     };
     
-    let buttons = Paragraph::new(buttons_text)
+    let buttons = Paragraph::new(footer_spans(buttons_text, chunks[5].width as usize))
         .block(Block::default().borders(Borders::TOP))
         .style(Style::default().fg(Color::White));
     
-    frame.render_widget(buttons, chunks[4]);
+    frame.render_widget(buttons, chunks[5]);
+    
+    // Plain text preview of the invoice over the form
+    if let Some(scroll) = state.preview_scroll {
+        render_preview(frame, size, &state.preview_text(), scroll);
+    }
+    
+    // Show the message file prompt if needed
+    if let Some(path) = &state.message_file_prompt {
+        render_message_file_prompt(frame, size, path);
+    }
+    
+    // Confirm sending outside the allowed domains
+    if state.confirm_external {
+        render_confirmation(
+            frame,
+            "External Recipient",
+            &format!(
+                "{} is not in ALLOWED_EMAIL_DOMAINS. Send the invoice there anyway?",
+                state.external_domain().unwrap_or_default()
+            ),
+        );
+    }
     
     // Show error if needed
     if let Some(error) = &state.show_error {
@@ -318,6 +639,40 @@ pub fn render_email_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut EmailWi
     if let Some(message) = &state.show_success {
         render_success(frame, size, message);
     }
+    
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
+}
+
+fn render_message_file_prompt<B: Backend>(frame: &mut Frame<B>, size: Rect, path: &str) {
+    let popup_area = centered_rect(60, 20, size);
+    
+    let prompt = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(format!("Path: {}|", path)),
+        Spans::from(""),
+        Spans::from("<Enter> Load | <Esc> Cancel"),
+    ])
+    .block(Block::default().title("Load Message From File").borders(Borders::ALL))
+    .style(Style::default().fg(Color::Yellow).bg(Color::Black));
+    
+    frame.render_widget(prompt, popup_area);
+}
+
+fn render_preview<B: Backend>(frame: &mut Frame<B>, size: Rect, text: &str, scroll: u16) {
+    let popup_area = centered_rect(80, 80, size);
+    
+    let preview = Paragraph::new(text.to_string())
+        .block(
+            Block::default()
+                .title("Invoice Preview - Up/Down/PgUp/PgDn Scroll | Ctrl+V/Esc Close")
+                .borders(Borders::ALL),
+        )
+        .scroll((scroll, 0));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(preview, popup_area);
 }
 
 fn render_error<B: Backend>(frame: &mut Frame<B>, size: Rect, error: &str) {
@@ -372,25 +727,117 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 pub fn handle_input(state: &mut EmailWizardState) -> Result<Option<EmailWizardAction>> {
+    let event = event::read()?;
+    handle_event(state, event)
+}
+
+// Apply a terminal event to the wizard, split from reading it so key sequences can be replayed
+fn handle_event(state: &mut EmailWizardState, event: Event) -> Result<Option<EmailWizardAction>> {
     // Clear any existing error message
     state.show_error = None;
     state.show_success = None;
     
-    if let Event::Key(key) = event::read()? {
+    if let Event::Key(key) = event {
+        // Sending to an external domain was confirmed, or is cancelled with any other key
+        if state.confirm_external {
+            state.confirm_external = false;
+            return Ok((key.code == KeyCode::Char('y')).then_some(EmailWizardAction::Send));
+        }
+        
+        // Quick send only asks for confirmation, any problem sending shows up in the full wizard
+        if state.quick_send {
+            state.quick_send = false;
+            if key.code != KeyCode::Char('y') {
+                return Ok(Some(EmailWizardAction::Cancel));
+            }
+            state.confirm_external = state.is_external_recipient();
+            return Ok((!state.confirm_external).then_some(EmailWizardAction::Send));
+        }
+        
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+        
+        // Scrolling the invoice preview until it's closed
+        if state.preview_scroll.is_some() {
+            match key.code {
+                KeyCode::Up => state.scroll_preview(-1),
+                KeyCode::Down => state.scroll_preview(1),
+                KeyCode::PageUp => state.scroll_preview(-10),
+                KeyCode::PageDown => state.scroll_preview(10),
+                KeyCode::Esc => state.toggle_preview(),
+                KeyCode::Char('v') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                    state.toggle_preview();
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+        
+        // Typing a path to load the message body from
+        if let Some(path) = &mut state.message_file_prompt {
+            match key.code {
+                KeyCode::Char(c) => path.push(c),
+                KeyCode::Backspace => {
+                    path.pop();
+                }
+                KeyCode::Esc => state.message_file_prompt = None,
+                KeyCode::Enter => {
+                    let path = path.clone();
+                    match state.load_message_from_file(&path) {
+                        Ok(()) => {
+                            state.message_file_prompt = None;
+                            state.current_field = EmailField::Message;
+                        }
+                        Err(e) => state.show_error = Some(e),
+                    }
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+        
         match key.code {
+            KeyCode::Char('o') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                state.message_file_prompt = Some(String::new());
+            }
+            KeyCode::Char('t') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                state.toggle_inline_html();
+            }
+            KeyCode::Char('p') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                state.toggle_attach_pdf();
+            }
+            KeyCode::Char('a') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                state.toggle_allow_zero_total();
+            }
+            KeyCode::Char('v') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                state.toggle_preview();
+            }
             KeyCode::Backspace => {
                 state.handle_input('\u{7f}'); // Pass backspace char
             }
             KeyCode::Esc => {
                 return Ok(Some(EmailWizardAction::Cancel));
             }
+            // Every other field takes ? as typed text
+            KeyCode::Char('?') if state.current_field == EmailField::None => {
+                state.show_help = true;
+            }
             KeyCode::Char(c) => {
                 state.handle_input(c);
             }
+            KeyCode::Enter if state.current_field == EmailField::Message => {
+                // Enter writes a new line in the message body, Tab moves on
+                state.handle_input('\n');
+            }
             KeyCode::Enter => {
                 if state.current_field == EmailField::None {
                     // Try to send email
                     match state.validate() {
+                        Ok(_) if state.is_external_recipient() => state.confirm_external = true,
                         Ok(_) => return Ok(Some(EmailWizardAction::Send)),
                         Err(e) => state.show_error = Some(e),
                     }
@@ -412,37 +859,84 @@ pub fn handle_input(state: &mut EmailWizardState) -> Result<Option<EmailWizardAc
     Ok(None)
 }
 
+// Domain part of an email address, None when there's no '@'
+fn recipient_domain(email: &str) -> Option<&str> {
+    email.trim().rsplit_once('@').map(|(_, domain)| domain)
+}
+
+// Addresses of a comma separated recipient list, skipping empty entries
+fn address_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|address| !address.is_empty())
+}
+
+// Parse a comma separated recipient list, naming the first address that isn't valid
+fn parse_mailboxes(list: &str) -> Result<Vec<Mailbox>, String> {
+    address_list(list)
+        .map(|address| address.parse().map_err(|e| format!("{}: {}", address, e)))
+        .collect()
+}
+
 // Function to generate invoice files when the email wizard is opened
 pub async fn generate_invoice_files(
     db: &crate::db::Database,
+    config: &Config,
     state: &mut EmailWizardState
 ) -> Result<()> {
     if let (Some(invoice), Some(line_items)) = (&state.invoice, &state.line_items) {
         // Get the project for this invoice
-        let project = db.get_project(invoice.project_id).await?;
+        let project = match db.get_project(invoice.project_id).await {
+            Ok(project) => project,
+            Err(e) => {
+                state.show_error = Some(missing_record(e, "The project for this invoice no longer exists")?);
+                return Ok(());
+            }
+        };
         
         // Get the client that owns the project
-        let client = db.get_client(project.client_id).await?;
+        let client = match db.get_client(project.client_id).await {
+            Ok(client) => client,
+            Err(e) => {
+                state.show_error = Some(missing_record(e, "The client for this invoice no longer exists")?);
+                return Ok(());
+            }
+        };
         
         // Get the profile that owns the client
-        let profile = db.get_profile(client.profile_id).await?;
+        let profile = match db.get_profile(client.profile_id).await {
+            Ok(profile) => profile,
+            Err(e) => {
+                state.show_error = Some(missing_record(e, "The profile for this invoice no longer exists")?);
+                return Ok(());
+            }
+        };
         
-        // Ensure the invoices directory exists
-        let invoices_dir = "invoices";
-        if !Path::new(invoices_dir).exists() {
-            fs::create_dir_all(invoices_dir)?;
-        }
+        // Get the client's unpaid balance from other invoices if enabled
+        let previous_balance = if config.show_previous_balance {
+            Some(db.get_client_outstanding_balance(client.id, invoice.id).await?)
+        } else {
+            None
+        };
+        let balances = Balances {
+            previous_balance,
+            amount_paid: db.get_invoice_paid_amount(invoice.id).await?,
+        };
         
-        // Create the invoice generator instance
-        let generator = crate::invoice_gen::InvoiceGenerator::new(invoices_dir)?;
+        // Create the invoice generator instance, which creates the output directory
+        let generator = crate::invoice_gen::InvoiceGenerator::new(config)?;
         
         // Generate the invoice files with the additional information
-        match generator.generate_invoice(invoice, line_items, &profile, &client, &project) {
+        match generator.generate_invoice(invoice, line_items, &profile, &client, &project, balances) {
             Ok((md_path, pdf_path)) => {
                 println!("Invoice files generated on-demand:");
                 println!("Markdown: {}", md_path);
                 println!("PDF: {}", pdf_path);
                 
+                // Rendered now so the inline HTML option can be toggled right up to sending
+                match generator.generate_html(&md_path) {
+                    Ok(html) => state.generated_html = Some(html),
+                    Err(e) => state.show_error = Some(format!("Failed to render invoice HTML: {}", e)),
+                }
+                
                 // Store the paths for later cleanup
                 state.generated_md_path = Some(md_path);
                 state.generated_pdf_path = Some(pdf_path);
@@ -456,63 +950,108 @@ pub async fn generate_invoice_files(
     Ok(())
 }
 
+// Message to show when a record the invoice depends on was deleted, other errors are passed on
+fn missing_record(error: AppError, message: &str) -> Result<String> {
+    match error {
+        AppError::NotFound => Ok(message.to_string()),
+        error => Err(error.into()),
+    }
+}
+
 // Function to send invoice email
-pub async fn send_invoice_email(state: &mut EmailWizardState) -> Result<()> {
+pub async fn send_invoice_email(db: &crate::db::Database, config: &Config, state: &mut EmailWizardState) -> error::Result<()> {
     if let (Some(invoice), Some(_)) = (&state.invoice, &state.line_items) {
-        // Build the file path for the PDF - use the one we generated
-        let pdf_path = if let Some(path) = &state.generated_pdf_path {
-            path.clone()
+        // Read the PDF unless the invoice only goes inline
+        let attachment = if state.attach_pdf {
+            // Build the file path for the PDF - use the one we generated
+            let pdf_path = if let Some(path) = &state.generated_pdf_path {
+                path.clone()
+            } else {
+                // Fallback if not generated yet
+                format!("{}/invoice_{}.pdf", config.invoice_output_dir, invoice.number)
+            };
+            
+            // Check if the PDF file exists
+            if !Path::new(&pdf_path).exists() {
+                state.show_error = Some(format!("PDF file not found: {}", pdf_path));
+                return Ok(());
+            }
+            
+            Some(std::fs::read(&pdf_path)?)
         } else {
-            // Fallback if not generated yet
-            format!("invoices/invoice_{}.pdf", invoice.number)
+            None
         };
         
-        // Check if the PDF file exists
-        if !Path::new(&pdf_path).exists() {
-            state.show_error = Some(format!("PDF file not found: {}", pdf_path));
-            return Ok(());
-        }
+        let html = if state.inline_html {
+            match &state.generated_html {
+                Some(html) => Some(html.as_str()),
+                None => {
+                    state.show_error = Some("Invoice HTML has not been generated".to_string());
+                    return Ok(());
+                }
+            }
+        } else {
+            None
+        };
         
-        // Read PDF file
-        let pdf_content = std::fs::read(&pdf_path)?;
+        // A mistyped recipient is shown in the wizard so it can be corrected
+        let recipient = match state.recipient_email.parse() {
+            Ok(recipient) => recipient,
+            Err(e) => {
+                state.show_error = Some(AppError::Address(e).to_string());
+                return Ok(());
+            }
+        };
         
-        // Determine if it's likely a real PDF (starts with %PDF magic number) or a text file
-        let content_type = if pdf_content.starts_with(b"%PDF") {
-            header::ContentType::parse("application/pdf")?
-        } else {
-            // It's our text fallback
-            header::ContentType::parse("text/plain")?
+        // Sender address from the config, checked before anything is sent
+        let sender = match config.smtp_from() {
+            Some(from) => from.parse().map_err(|e| format!("SMTP_FROM is not a valid address: {}", e)),
+            None => Err("SMTP_FROM is not set".to_string()),
+        };
+        let sender = match sender {
+            Ok(sender) => sender,
+            Err(message) => {
+                state.show_error = Some(format!("SMTP configuration error: {}", message));
+                return Ok(());
+            }
+        };
+        
+        // Copies were checked by validate(), so these only fail when sending skipped it
+        let (cc, bcc) = match (parse_mailboxes(&state.cc), parse_mailboxes(&state.bcc)) {
+            (Ok(cc), Ok(bcc)) => (cc, bcc),
+            (Err(e), _) | (_, Err(e)) => {
+                state.show_error = Some(format!("Invalid copy recipient {}", e));
+                return Ok(());
+            }
         };
         
-        // Create email
-        let email = Message::builder()
-            .from("invoicemanager@example.com".parse()?)
-            .to(state.recipient_email.parse()?)
+        // Create email, leaving out CC and BCC when there are none
+        let mut builder = Message::builder()
+            .from(sender)
+            .to(recipient);
+        for mailbox in cc {
+            builder = builder.cc(mailbox);
+        }
+        for mailbox in bcc {
+            builder = builder.bcc(mailbox);
+        }
+        let email = builder
             .subject(&state.subject)
-            .multipart(
-                MultiPart::mixed()
-                    .singlepart(
-                        SinglePart::plain(state.message.clone())
-                    )
-                    .singlepart(
-                        Attachment::new(format!("invoice_{}.pdf", invoice.number))
-                            .body(pdf_content, content_type)
-                    )
-            )?;
-        
-        // Configure SMTP client - This should come from a config in a real app
-        let smtp_username = "username@example.com"; // Replace with env var or config
-        let smtp_password = "password"; // Replace with env var or config
-        let smtp_server = "smtp.example.com"; // Replace with env var or config
-        
-        let creds = Credentials::new(smtp_username.to_string(), smtp_password.to_string());
-        
-        // Open connection and send email
-        let mailer = SmtpTransport::relay(smtp_server)?
-            .credentials(creds)
-            .build();
-        
-        match mailer.send(&email) {
+            .multipart(build_invoice_body(invoice.number, &state.message, html, attachment)?)?;
+        
+        // Configure SMTP client with the configured server, credentials and encryption
+        let mailer = match build_smtp_transport(config) {
+            Ok(mailer) => mailer,
+            Err(e) => {
+                state.show_error = Some(format!("SMTP configuration error: {}", e));
+                return Ok(());
+            }
+        };
+        
+        let sent = mailer.send(&email);
+        db.log_invoice_email(invoice.id, &state.all_recipients(), chrono::Local::now().naive_local(), sent.is_ok()).await?;
+        
+        match sent {
             Ok(_) => {
                 state.show_success = Some(format!("Email with invoice #{} sent successfully", invoice.number));
                 Ok(())
@@ -526,4 +1065,378 @@ pub async fn send_invoice_email(state: &mut EmailWizardState) -> Result<()> {
         state.show_error = Some("Invoice data is missing".to_string());
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+// Body parts of an invoice email: the plain message, an HTML alternative with the
+// invoice inline when given, and the PDF as an attachment when given
+fn build_invoice_body(invoice_number: i32, message: &str, html: Option<&str>, pdf_content: Option<Vec<u8>>) -> error::Result<MultiPart> {
+    let mut body = MultiPart::mixed().build();
+    
+    match html {
+        Some(invoice_html) => {
+            let message_html = escape_html(message).replace('\n', "<br>\n");
+            body = body.multipart(MultiPart::alternative_plain_html(
+                message.to_string(),
+                format!("<p>{}</p>\n<hr>\n{}", message_html, invoice_html),
+            ));
+        }
+        None => {
+            body = body.singlepart(SinglePart::plain(message.to_string()));
+        }
+    }
+    
+    if let Some(pdf_content) = pdf_content {
+        // Determine if it's likely a real PDF (starts with %PDF magic number) or a text file
+        let content_type = if pdf_content.starts_with(b"%PDF") {
+            header::ContentType::parse("application/pdf")?
+        } else {
+            // It's our text fallback
+            header::ContentType::parse("text/plain")?
+        };
+        
+        body = body.singlepart(
+            Attachment::new(format!("invoice_{}.pdf", invoice_number))
+                .body(pdf_content, content_type)
+        );
+    }
+    
+    Ok(body)
+}
+
+// Pick the transport builder for the configured encryption
+fn smtp_transport_builder(smtp_server: &str, encryption: SmtpEncryption, accept_invalid_certs: bool) -> error::Result<SmtpTransportBuilder> {
+    if !accept_invalid_certs {
+        return Ok(match encryption {
+            SmtpEncryption::Starttls => SmtpTransport::starttls_relay(smtp_server)?,
+            SmtpEncryption::Tls => SmtpTransport::relay(smtp_server)?,
+            SmtpEncryption::None => SmtpTransport::builder_dangerous(smtp_server),
+        });
+    }
+    
+    // Self-signed certificates need custom TLS parameters on an otherwise plain builder
+    let tls_parameters = TlsParameters::builder(smtp_server.to_string())
+        .dangerous_accept_invalid_certs(true)
+        .build()?;
+    
+    Ok(match encryption {
+        SmtpEncryption::Starttls => SmtpTransport::builder_dangerous(smtp_server)
+            .port(587)
+            .tls(Tls::Required(tls_parameters)),
+        SmtpEncryption::Tls => SmtpTransport::builder_dangerous(smtp_server)
+            .port(465)
+            .tls(Tls::Wrapper(tls_parameters)),
+        SmtpEncryption::None => SmtpTransport::builder_dangerous(smtp_server),
+    })
+}
+
+// Build the SMTP transport from the server and credentials in the config
+pub fn build_smtp_transport(config: &Config) -> error::Result<SmtpTransport> {
+    let smtp_server = config.smtp_server.as_deref()
+        .ok_or_else(|| AppError::Config("SMTP_SERVER is not set".to_string()))?;
+    let smtp_username = config.smtp_username.as_deref()
+        .ok_or_else(|| AppError::Config("SMTP_USERNAME is not set".to_string()))?;
+    let smtp_password = config.smtp_password()
+        .map_err(|e| AppError::Config(e.to_string()))?;
+    
+    let creds = Credentials::new(smtp_username.to_string(), smtp_password);
+    
+    let mailer = smtp_transport_builder(smtp_server, config.smtp_encryption, config.smtp_accept_invalid_certs)?
+        .credentials(creds)
+        .build();
+    
+    Ok(mailer)
+}
+
+// Connect and authenticate against the configured SMTP server without sending anything.
+// Returns a user-facing message describing the outcome.
+pub fn test_smtp_connection(config: &Config) -> Result<String, String> {
+    let mailer = build_smtp_transport(config)
+        .map_err(|e| format!("SMTP configuration error: {}", e))?;
+    
+    match mailer.test_connection() {
+        Ok(true) => Ok("SMTP connection and login succeeded".to_string()),
+        Ok(false) => Err("Connection failed: server did not respond to NOOP".to_string()),
+        Err(e) => {
+            // 530/534/535 are the authentication failure replies
+            let is_auth_error = e.status()
+                .map(|code| code.to_string().starts_with("53"))
+                .unwrap_or(false);
+            
+            if is_auth_error {
+                Err(format!("Authentication failed: {}", e))
+            } else {
+                Err(format!("Connection failed: {}", e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, invoice, line_item};
+
+    fn approved(line_items: Vec<InvoiceLineItem>) -> EmailWizardState {
+        let mut invoice = invoice(7);
+        invoice.status = "Approved".to_string();
+        let mut state = EmailWizardState::new(invoice.id);
+        state.load_invoice(invoice, line_items, "Website".to_string(), "accounts@client.example".to_string(), "USD".to_string());
+        state
+    }
+
+    #[test]
+    fn loading_a_file_fills_in_the_message() {
+        let path = std::env::temp_dir().join(format!("invoice-message-{}.txt", std::process::id()));
+        fs::write(&path, "Invoice {invoice_number} for {total} is due {due_date}").unwrap();
+
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        let result = state.load_message_from_file(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(state.message, "Invoice 7 for $200.00 is due 2024-02-14");
+    }
+
+    // Inserts a row whose parent doesn't exist, as left behind when the parent is deleted outside the app
+    async fn insert_orphan(db: &crate::db::Database, query: &str) -> i32 {
+        let mut tx = db.get_pool().begin().await.unwrap();
+        sqlx::query("SET LOCAL session_replication_role = replica").execute(&mut *tx).await.unwrap();
+        let id = sqlx::query_scalar(query).fetch_one(&mut *tx).await.unwrap();
+        tx.commit().await.unwrap();
+        id
+    }
+
+    async fn generation_error(db: &crate::db::Database, project_id: i32) -> Option<String> {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        state.invoice.as_mut().unwrap().project_id = project_id;
+        generate_invoice_files(db, &test_support::config(), &mut state).await.unwrap();
+        assert!(state.generated_pdf_path.is_none());
+        state.show_error.take()
+    }
+
+    #[sqlx::test]
+    async fn each_missing_record_gets_its_own_message(pool: sqlx::PgPool) {
+        let db = crate::db::Database::from_pool(pool);
+        assert_eq!(generation_error(&db, 9999).await.as_deref(), Some("The project for this invoice no longer exists"));
+
+        let clientless = insert_orphan(&db, "INSERT INTO projects (client_id, name, start_date) VALUES (9999, 'Website', '2024-01-01') RETURNING id").await;
+        assert_eq!(generation_error(&db, clientless).await.as_deref(), Some("The client for this invoice no longer exists"));
+
+        let profileless_client = insert_orphan(&db, "INSERT INTO clients (name, phone, email, profile_id) VALUES ('Acme', '555-0199', 'a@client.example', 9999) RETURNING id").await;
+        let project_id = db.create_project(&test_support::project(profileless_client, "Website")).await.unwrap();
+        assert_eq!(generation_error(&db, project_id).await.as_deref(), Some("The profile for this invoice no longer exists"));
+    }
+
+    #[sqlx::test]
+    async fn other_lookup_failures_are_passed_on(pool: sqlx::PgPool) {
+        let db = crate::db::Database::from_pool(pool.clone());
+        pool.close().await;
+
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        assert!(generate_invoice_files(&db, &test_support::config(), &mut state).await.is_err());
+        assert!(state.show_error.is_none());
+    }
+
+    // Port and TLS mode the builder ends up with, read from its debug output
+    fn transport_settings(encryption: SmtpEncryption, accept_invalid_certs: bool) -> String {
+        format!("{:?}", smtp_transport_builder("smtp.example.com", encryption, accept_invalid_certs).unwrap())
+    }
+
+    #[test]
+    fn the_sent_history_lists_every_recipient() {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        assert_eq!(state.all_recipients(), "accounts@client.example");
+
+        state.cc = "boss@client.example, , ap@client.example".to_string();
+        state.bcc = " me@studio.example ".to_string();
+        assert_eq!(
+            state.all_recipients(),
+            "accounts@client.example; cc: boss@client.example, ap@client.example; bcc: me@studio.example"
+        );
+    }
+
+    #[test]
+    fn missing_smtp_settings_are_config_errors() {
+        let mut config = test_support::config();
+        assert!(matches!(build_smtp_transport(&config), Err(AppError::Config(message)) if message.contains("SMTP_SERVER")));
+
+        config.smtp_server = Some("smtp.example.com".to_string());
+        assert!(matches!(build_smtp_transport(&config), Err(AppError::Config(message)) if message.contains("SMTP_USERNAME")));
+    }
+
+    #[test]
+    fn each_encryption_option_selects_its_transport() {
+        let starttls = transport_settings(SmtpEncryption::Starttls, false);
+        assert!(starttls.contains("port: 587") && starttls.contains("tls: Required"), "{}", starttls);
+
+        let tls = transport_settings(SmtpEncryption::Tls, false);
+        assert!(tls.contains("port: 465") && tls.contains("tls: Wrapper"), "{}", tls);
+
+        let none = transport_settings(SmtpEncryption::None, false);
+        assert!(none.contains("port: 25") && none.contains("tls: None"), "{}", none);
+    }
+
+    #[test]
+    fn accepting_invalid_certs_keeps_the_encryption() {
+        let starttls = transport_settings(SmtpEncryption::Starttls, true);
+        assert!(starttls.contains("port: 587") && starttls.contains("tls: Required"), "{}", starttls);
+
+        let tls = transport_settings(SmtpEncryption::Tls, true);
+        assert!(tls.contains("port: 465") && tls.contains("tls: Wrapper"), "{}", tls);
+    }
+
+    fn formatted_body(html: Option<&str>, pdf_content: Option<Vec<u8>>) -> String {
+        let body = build_invoice_body(7, "Invoice attached", html, pdf_content).unwrap();
+        String::from_utf8(body.formatted()).unwrap()
+    }
+
+    #[test]
+    fn inline_html_adds_an_html_part_next_to_the_plain_text() {
+        let body = formatted_body(Some("<table><tr><td>Design</td></tr></table>"), None);
+        assert!(body.contains("multipart/alternative"));
+        assert!(body.contains("Content-Type: text/plain"));
+        assert!(body.contains("Content-Type: text/html"));
+        assert!(body.contains("<td>Design</td>"));
+        assert!(!body.contains("invoice_7.pdf"));
+    }
+
+    #[test]
+    fn without_inline_html_the_body_is_plain_text_and_the_pdf() {
+        let body = formatted_body(None, Some(b"%PDF-1.4".to_vec()));
+        assert!(body.contains("Content-Type: text/plain"));
+        assert!(!body.contains("text/html"));
+        assert!(body.contains("filename=\"invoice_7.pdf\""));
+    }
+
+    #[test]
+    fn an_invoice_without_line_items_cant_be_sent() {
+        let state = approved(Vec::new());
+        assert!(state.validate().unwrap_err().contains("no line items"));
+    }
+
+    #[test]
+    fn a_zero_total_blocks_sending_until_allowed() {
+        let mut state = approved(vec![line_item(1, "Work", 0.0)]);
+        assert!(state.validate().unwrap_err().starts_with("Invoice total is $0.00"));
+
+        // Credits and other intentional $0 invoices are sent after Ctrl+A
+        state.toggle_allow_zero_total();
+        assert_eq!(state.validate(), Ok(()));
+    }
+
+    fn press(state: &mut EmailWizardState, key: KeyCode) -> Option<EmailWizardAction> {
+        handle_event(state, Event::Key(crossterm::event::KeyEvent::new(key, crossterm::event::KeyModifiers::NONE))).unwrap()
+    }
+
+    // Approved invoice for accounts@client.example, on the send button
+    fn ready_to_send(allowed_domains: &[&str]) -> EmailWizardState {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)])
+            .with_allowed_domains(allowed_domains.iter().map(|domain| domain.to_string()).collect());
+        state.current_field = EmailField::None;
+        state
+    }
+
+    #[test]
+    fn an_out_of_list_domain_needs_confirming() {
+        let mut state = ready_to_send(&["studio.example"]);
+        assert!(state.is_external_recipient());
+
+        assert!(press(&mut state, KeyCode::Enter).is_none());
+        assert!(state.confirm_external);
+        assert!(matches!(press(&mut state, KeyCode::Char('y')), Some(EmailWizardAction::Send)));
+    }
+
+    #[test]
+    fn declining_the_confirmation_doesnt_send() {
+        let mut state = ready_to_send(&["studio.example"]);
+        press(&mut state, KeyCode::Enter);
+
+        assert!(press(&mut state, KeyCode::Char('n')).is_none());
+        assert!(!state.confirm_external);
+    }
+
+    #[test]
+    fn allowed_domains_and_an_empty_list_send_straight_away() {
+        let mut state = ready_to_send(&["Client.Example"]);
+        assert!(!state.is_external_recipient());
+        assert!(matches!(press(&mut state, KeyCode::Enter), Some(EmailWizardAction::Send)));
+
+        let mut state = ready_to_send(&[]);
+        assert!(matches!(press(&mut state, KeyCode::Enter), Some(EmailWizardAction::Send)));
+    }
+
+    #[test]
+    fn copy_recipients_are_checked_too() {
+        let mut state = ready_to_send(&["client.example"]);
+        state.cc = "boss@client.example, someone@elsewhere.example".to_string();
+        assert_eq!(state.external_domain(), Some("elsewhere.example"));
+    }
+
+    #[test]
+    fn preview_shows_the_invoice_number_and_total() {
+        let state = approved(vec![line_item(1, "Design", 2.0), line_item(2, "Build", 1.5)]);
+        let preview = state.preview_text();
+
+        assert!(preview.starts_with("INVOICE #7\n"));
+        assert!(preview.contains("Design"));
+        assert!(preview.lines().last().unwrap().ends_with("Total: $350.00"));
+        assert!(EmailWizardState::new(7).preview_text().is_empty());
+    }
+
+    #[test]
+    fn preview_scrolling_stops_at_the_first_and_last_line() {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        let last_line = state.preview_text().lines().count() as u16 - 1;
+        state.toggle_preview();
+
+        state.scroll_preview(-5);
+        assert_eq!(state.preview_scroll, Some(0));
+        state.scroll_preview(1000);
+        assert_eq!(state.preview_scroll, Some(last_line));
+        state.toggle_preview();
+        assert_eq!(state.preview_scroll, None);
+    }
+
+    // Generated invoice files left behind by a wizard that's being shut down
+    fn generated_files(state: &mut EmailWizardState, name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+        let dir = std::env::temp_dir();
+        let md_path = dir.join(format!("{}-{}.md", name, std::process::id()));
+        let pdf_path = dir.join(format!("{}-{}.pdf", name, std::process::id()));
+        fs::write(&md_path, "# Invoice").unwrap();
+        fs::write(&pdf_path, "%PDF-1.4").unwrap();
+        state.generated_md_path = Some(md_path.to_string_lossy().into_owned());
+        state.generated_pdf_path = Some(pdf_path.to_string_lossy().into_owned());
+        (md_path, pdf_path)
+    }
+
+    #[test]
+    fn cleanup_removes_the_generated_files() {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        let (md_path, pdf_path) = generated_files(&mut state, "cleanup");
+
+        state.cleanup_files().unwrap();
+        assert!(!md_path.exists());
+        assert!(!pdf_path.exists());
+        // Already removed files are skipped
+        state.cleanup_files().unwrap();
+    }
+
+    #[test]
+    fn dropping_the_wizard_removes_the_generated_files() {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        let (md_path, pdf_path) = generated_files(&mut state, "dropped");
+
+        drop(state);
+        assert!(!md_path.exists());
+        assert!(!pdf_path.exists());
+    }
+
+    #[test]
+    fn loading_a_missing_file_keeps_the_message() {
+        let mut state = approved(vec![line_item(1, "Design", 2.0)]);
+        let message = state.message.clone();
+
+        assert!(state.load_message_from_file("/nonexistent/message.txt").is_err());
+        assert_eq!(state.message, message);
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::Result;
-use chrono::{Local, NaiveDate};
-use crossterm::event::{self, Event, KeyCode};
+use rust_decimal::Decimal;
+use chrono::{Datelike, Local, Months, NaiveDate, Weekday};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use unicode_width::UnicodeWidthStr;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -10,15 +12,49 @@ use tui::{
     Frame,
 };
 
+use crate::config::Config;
+use crate::money;
 use crate::models::{Invoice, InvoiceLineItem};
+use crate::ui::components::confirm::render_confirmation;
 use crate::ui::components::date_input::{DateInputState, DatePart};
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::footer_spans;
+
+// Submit dates further than this many days ahead ask for confirmation on save
+const FUTURE_SUBMIT_DATE_THRESHOLD_DAYS: i64 = 30;
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Move between fields"),
+    ("Enter", "Edit the selected field, or save it while editing"),
+    ("Left/Right", "Switch date part while editing a date"),
+    ("Ctrl+N", "New line in Bill To and the notes"),
+    ("Ctrl+Z", "Undo changes to the field being edited"),
+    ("B", "Switch between hourly and flat fee billing"),
+    ("A / I", "Add a line item, or insert one below the selected item"),
+    ("E / D", "Edit or delete the selected line item"),
+    ("C", "Copy the previous invoice's line items"),
+    ("Tab", "Next field of the line item being edited"),
+    ("Space", "Switch a line item between hourly and a fixed amount"),
+    ("+/-", "Adjust the hours of a line item"),
+    ("S", "Save the invoice"),
+    ("Shift+S", "Save the invoice as a draft"),
+    ("Ctrl+R", "Start a new invoice over"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Cancel editing, or leave without saving"),
+];
 
 // Represents a field in the invoice form
 #[derive(Clone, Copy, PartialEq)]
 pub enum InvoiceField {
+    Number,
     SubmitDate,
     DueDate,
     Rate,
+    TaxRate,
+    BillTo,
+    Notes,
+    InternalNotes,
     LineItems,
 }
 
@@ -26,7 +62,10 @@ pub enum InvoiceField {
 #[derive(Clone, Copy, PartialEq)]
 pub enum LineItemField {
     Description,
+    Type, // Hourly or a fixed amount, only asked on hourly invoices
     Hours,
+    Rate, // Hourly rate for this item only, blank uses the invoice rate
+    Amount,
     None,
 }
 
@@ -34,9 +73,19 @@ pub enum LineItemField {
 pub struct InvoiceWizardState {
     project_id: i32,
     invoice_id: Option<i32>,
+    // Invoice number, 0 until one is suggested or entered
+    number: i32,
     submit_date: NaiveDate,
     due_date: NaiveDate,
     rate: f64,
+    // Flat-fee invoices bill a fixed amount per line item instead of hours at the rate
+    flat_fee: bool,
+    // Tax or VAT percentage added on top of the line items
+    tax_rate: Option<f64>,
+    bill_to: String,
+    // Printed on the invoice below the totals
+    notes: String,
+    internal_notes: String,
     line_items: Vec<InvoiceLineItem>,
     current_field: InvoiceField,
     line_items_list_state: ListState,
@@ -44,39 +93,100 @@ pub struct InvoiceWizardState {
     editing: bool,
     active_input: String,
     show_error: Option<String>,
+    show_warning: Option<String>,
+    // Far-future submit date the user already confirmed saving with
+    confirmed_future_date: Option<NaiveDate>,
     submit_date_state: DateInputState,
     due_date_state: DateInputState,
+    hours_step: f64,
+    // Time entries the line items were created from, billed once the invoice saves
+    time_entry_ids: Vec<i32>,
+    // Due date a fresh invoice starts with, restored on reset
+    default_due_date: NaiveDate,
+    // Number suggested for a new invoice, restored on reset
+    suggested_number: i32,
+    // Typing the day of a date commits it and moves to the next field
+    date_auto_advance: bool,
+    confirm_reset: bool,
+    show_help: bool,
+    // Version of the invoice when it was loaded, checked on save to catch concurrent edits
+    version: i32,
+    // Status and payment date of the invoice being edited, written back unchanged on save
+    status: String,
+    paid_date: Option<NaiveDate>,
+    // Bill to or internal notes when editing started, restored by undo
+    edit_start_value: String,
+    // Line items of the project's most recent invoice, offered for copying into a new one
+    previous_line_items: Vec<InvoiceLineItem>,
+    // Currency of the project's profile that amounts are shown in
+    currency: String,
 }
 
 impl InvoiceWizardState {
-    pub fn new(project_id: i32, invoice_id: Option<i32>, existing_invoice: Option<Invoice>, existing_line_items: Option<Vec<InvoiceLineItem>>) -> Self {
+    pub fn new(project_id: i32, invoice_id: Option<i32>, existing_invoice: Option<Invoice>, existing_line_items: Option<Vec<InvoiceLineItem>>, config: &Config) -> Self {
         let today = Local::now().date_naive();
-        let five_days_later = today + chrono::Duration::days(5);
+        let mut default_due_date = today + chrono::Duration::days(config.default_due_days);
+        if config.due_date_skip_weekends {
+            default_due_date = next_business_day(default_due_date, &config.due_date_holidays);
+        }
         
         let mut state = Self {
             project_id,
             invoice_id,
+            number: 0,
             submit_date: today,
-            due_date: five_days_later,
+            due_date: default_due_date,
             rate: 0.0,
+            flat_fee: false,
+            tax_rate: None,
+            bill_to: String::new(),
+            notes: String::new(),
+            internal_notes: String::new(),
             line_items: Vec::new(),
-            current_field: InvoiceField::SubmitDate,
+            current_field: InvoiceField::Number,
             line_items_list_state: ListState::default(),
             editing_line_item: None,
             editing: false,
             active_input: String::new(),
             show_error: None,
-            submit_date_state: DateInputState::new(today),
-            due_date_state: DateInputState::new(five_days_later),
+            show_warning: None,
+            confirmed_future_date: None,
+            submit_date_state: DateInputState::new(today).with_auto_advance(config.date_auto_advance),
+            due_date_state: DateInputState::new(default_due_date).with_auto_advance(config.date_auto_advance),
+            hours_step: config.hours_step,
+            time_entry_ids: Vec::new(),
+            default_due_date,
+            suggested_number: 0,
+            date_auto_advance: config.date_auto_advance,
+            confirm_reset: false,
+            show_help: false,
+            version: 1,
+            status: "Draft".to_string(),
+            paid_date: None,
+            edit_start_value: String::new(),
+            previous_line_items: Vec::new(),
+            currency: money::DEFAULT_CURRENCY.to_string(),
         };
         
         // If editing an existing invoice, load its data
         if let Some(invoice) = existing_invoice {
+            // Copies of another invoice get a number of their own
+            if invoice_id.is_some() {
+                state.number = invoice.number;
+                state.status = invoice.status.clone();
+                state.paid_date = invoice.paid_date;
+            }
             state.submit_date = invoice.submit_date;
             state.due_date = invoice.due_date;
             state.rate = invoice.rate;
-            state.submit_date_state = DateInputState::new(invoice.submit_date);
-            state.due_date_state = DateInputState::new(invoice.due_date);
+            state.flat_fee = invoice.is_flat_fee();
+            state.tax_rate = invoice.tax_rate;
+            state.bill_to = invoice.bill_to_override.unwrap_or_default();
+            state.notes = invoice.notes.unwrap_or_default();
+            state.internal_notes = invoice.internal_notes.unwrap_or_default();
+            state.submit_date_state = DateInputState::new(invoice.submit_date).with_auto_advance(state.date_auto_advance);
+            state.due_date_state = DateInputState::new(invoice.due_date).with_auto_advance(state.date_auto_advance);
+            state.version = invoice.version;
             
             if let Some(items) = existing_line_items {
                 state.line_items = items;
@@ -89,12 +199,118 @@ impl InvoiceWizardState {
         state
     }
     
+    // Start the invoice from grouped time entries
+    pub fn with_time_entries(mut self, line_items: Vec<InvoiceLineItem>, time_entry_ids: Vec<i32>) -> Self {
+        self.line_items = line_items;
+        if !self.line_items.is_empty() {
+            self.line_items_list_state.select(Some(0));
+        }
+        self.time_entry_ids = time_entry_ids;
+        self
+    }
+    
+    // Start a new invoice with these descriptions as zero-hour line items. Invoices being
+    // edited, or that already have line items, are left as they are.
+    pub fn with_default_line_items(mut self, descriptions: &[String]) -> Self {
+        if self.invoice_id.is_some() || !self.line_items.is_empty() {
+            return self;
+        }
+        
+        self.line_items = descriptions
+            .iter()
+            .enumerate()
+            .map(|(i, description)| InvoiceLineItem {
+                id: i as i32 + 1,
+                invoice_id: 0,
+                description: description.clone(),
+                hours: 0.0,
+                amount: 0.0,
+                rate_override: None,
+                item_type: "Hourly".to_string(),
+            })
+            .collect();
+        if !self.line_items.is_empty() {
+            self.line_items_list_state.select(Some(0));
+        }
+        self
+    }
+    
+    // Start from today's submit date and the default due date, for invoices copied from
+    // an older one
+    pub fn with_fresh_dates(mut self) -> Self {
+        let today = Local::now().date_naive();
+        self.submit_date = today;
+        self.due_date = self.default_due_date;
+        self.submit_date_state = DateInputState::new(today).with_auto_advance(self.date_auto_advance);
+        self.due_date_state = DateInputState::new(self.default_due_date).with_auto_advance(self.date_auto_advance);
+        self
+    }
+    
+    // Number a new invoice gets unless another one is entered
+    pub fn with_suggested_number(mut self, number: i32) -> Self {
+        if self.invoice_id.is_none() {
+            self.number = number;
+            self.suggested_number = number;
+        }
+        self
+    }
+    
+    // Show amounts in the currency of the project's profile
+    pub fn with_currency(mut self, currency: String) -> Self {
+        self.currency = currency;
+        self
+    }
+    
+    // Line items of the project's previous invoice, which C copies into this one
+    pub fn with_previous_line_items(mut self, line_items: Vec<InvoiceLineItem>) -> Self {
+        self.previous_line_items = line_items;
+        self
+    }
+    
+    // Append the previous invoice's line items as new items of this invoice
+    pub fn copy_previous_line_items(&mut self) {
+        if self.previous_line_items.is_empty() {
+            self.show_error = Some("There's no previous invoice to copy line items from.".to_string());
+            return;
+        }
+        
+        let first_new = self.line_items.len();
+        let first_id = self.line_items.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+        let copies: Vec<InvoiceLineItem> = self.previous_line_items
+            .iter()
+            .zip(first_id..)
+            .map(|(item, id)| InvoiceLineItem {
+                id,
+                invoice_id: self.invoice_id.unwrap_or(0), // Will be updated when invoice is saved
+                description: item.description.clone(),
+                hours: item.hours,
+                amount: item.amount,
+                rate_override: item.rate_override,
+                item_type: item.item_type.clone(),
+            })
+            .collect();
+        self.line_items.extend(copies);
+        self.line_items_list_state.select(Some(first_new));
+    }
+    
+    pub fn time_entry_ids(&self) -> &[i32] {
+        &self.time_entry_ids
+    }
+    
+    // Report a save that failed, keeping the wizard open with the user's changes
+    pub fn show_save_error(&mut self, message: String) {
+        self.show_error = Some(message);
+    }
+    
     pub fn toggle_editing(&mut self) {
         self.editing = !self.editing;
         
         // Handle date fields specially
         if self.editing {
             match self.current_field {
+                InvoiceField::Number => {
+                    self.active_input = self.number.to_string();
+                },
                 InvoiceField::SubmitDate => {
                     self.submit_date_state.toggle_editing();
                 },
@@ -104,6 +320,19 @@ impl InvoiceWizardState {
                 InvoiceField::Rate => {
                     self.active_input = self.rate.to_string();
                 },
+                InvoiceField::TaxRate => {
+                    self.active_input = tax_rate_input(self.tax_rate);
+                },
+                InvoiceField::BillTo => {
+                    // Edited in place, remembering the value for undo
+                    self.edit_start_value = self.bill_to.clone();
+                },
+                InvoiceField::Notes => {
+                    self.edit_start_value = self.notes.clone();
+                },
+                InvoiceField::InternalNotes => {
+                    self.edit_start_value = self.internal_notes.clone();
+                },
                 InvoiceField::LineItems => {
                     // Keep line items as they are
                 }
@@ -111,46 +340,184 @@ impl InvoiceWizardState {
         } else {
             self.submit_date_state.editing = false;
             self.due_date_state.editing = false;
-            self.editing_line_item = None;
+            self.discard_line_item_edit();
+            self.active_input.clear();
+        }
+    }
+    
+    // Put the field being edited back to its value from when editing started.
+    // Line item values aren't stored until committed, so they're restored from the item.
+    pub fn undo_edit(&mut self) {
+        if !self.editing {
+            return;
         }
+        
+        match self.current_field {
+            InvoiceField::Number => self.active_input = self.number.to_string(),
+            InvoiceField::Rate => self.active_input = self.rate.to_string(),
+            InvoiceField::TaxRate => self.active_input = tax_rate_input(self.tax_rate),
+            InvoiceField::BillTo => self.bill_to = self.edit_start_value.clone(),
+            InvoiceField::Notes => self.notes = self.edit_start_value.clone(),
+            InvoiceField::InternalNotes => self.internal_notes = self.edit_start_value.clone(),
+            InvoiceField::LineItems => {
+                let Some((idx, field, value)) = &mut self.editing_line_item else {
+                    return;
+                };
+                if let Some(item) = self.line_items.get(*idx) {
+                    *value = match field {
+                        LineItemField::Description => item.description.clone(),
+                        LineItemField::Type => item.item_type.clone(),
+                        LineItemField::Hours => item.hours.to_string(),
+                        LineItemField::Rate => rate_override_input(item),
+                        LineItemField::Amount => item.amount.to_string(),
+                        LineItemField::None => return,
+                    };
+                }
+            }
+            InvoiceField::SubmitDate | InvoiceField::DueDate => {}
+        }
+    }
+    
+    // Abandon the line item being edited. A new item left before its description
+    // was committed is removed rather than kept blank.
+    pub fn discard_line_item_edit(&mut self) {
+        let blank = self.editing_line_item
+            .take()
+            .map(|(idx, _, _)| idx)
+            .filter(|idx| self.line_items.get(*idx).is_some_and(|item| item.description.trim().is_empty()));
+        if let Some(idx) = blank {
+            self.line_items_list_state.select(Some(idx));
+            self.delete_line_item();
+        }
+    }
+    
+    // Position of the first line item without a description, which can't be saved
+    fn first_blank_line_item(&self) -> Option<usize> {
+        self.line_items.iter().position(|item| item.description.trim().is_empty())
     }
     
     pub fn next_field(&mut self) {
         self.current_field = match self.current_field {
+            // The rate isn't used by flat-fee invoices
+            InvoiceField::DueDate if self.flat_fee => InvoiceField::TaxRate,
+            InvoiceField::Number => InvoiceField::SubmitDate,
             InvoiceField::SubmitDate => InvoiceField::DueDate,
             InvoiceField::DueDate => InvoiceField::Rate,
-            InvoiceField::Rate => InvoiceField::LineItems,
-            InvoiceField::LineItems => InvoiceField::SubmitDate,
+            InvoiceField::Rate => InvoiceField::TaxRate,
+            InvoiceField::TaxRate => InvoiceField::BillTo,
+            InvoiceField::BillTo => InvoiceField::Notes,
+            InvoiceField::Notes => InvoiceField::InternalNotes,
+            InvoiceField::InternalNotes => InvoiceField::LineItems,
+            InvoiceField::LineItems => InvoiceField::Number,
         };
     }
     
     pub fn previous_field(&mut self) {
         self.current_field = match self.current_field {
-            InvoiceField::SubmitDate => InvoiceField::LineItems,
+            InvoiceField::TaxRate if self.flat_fee => InvoiceField::DueDate,
+            InvoiceField::Number => InvoiceField::LineItems,
+            InvoiceField::SubmitDate => InvoiceField::Number,
             InvoiceField::DueDate => InvoiceField::SubmitDate,
             InvoiceField::Rate => InvoiceField::DueDate,
-            InvoiceField::LineItems => InvoiceField::Rate,
+            InvoiceField::TaxRate => InvoiceField::Rate,
+            InvoiceField::BillTo => InvoiceField::TaxRate,
+            InvoiceField::Notes => InvoiceField::BillTo,
+            InvoiceField::InternalNotes => InvoiceField::Notes,
+            InvoiceField::LineItems => InvoiceField::InternalNotes,
         };
     }
     
+    pub fn toggle_billing_type(&mut self) {
+        self.flat_fee = !self.flat_fee;
+        if self.flat_fee && self.current_field == InvoiceField::Rate {
+            self.current_field = InvoiceField::TaxRate;
+        }
+    }
+    
+    // Amount billed for a line item under the current billing type
+    fn line_item_amount(&self, item: &InvoiceLineItem) -> Decimal {
+        money::line_item_amount(self.flat_fee, self.rate, item)
+    }
+    
+    // Whether a line item bills an amount rather than hours, as every item of a flat-fee
+    // invoice does
+    fn bills_amount(&self, item: &InvoiceLineItem) -> bool {
+        self.flat_fee || item.is_fixed()
+    }
+    
+    // Line items followed by the total hours and the same subtotal, tax and total the
+    // generated invoice shows
+    fn line_items_summary(&self) -> Vec<String> {
+        if self.line_items.is_empty() {
+            return vec!["No line items added yet".to_string()];
+        }
+        
+        let mut lines: Vec<String> = self.line_items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let amount = money::format_money_in(self.line_item_amount(item), &self.currency);
+                if self.bills_amount(item) {
+                    format!("{}. {}: {}", i + 1, item.description, amount)
+                } else {
+                    format!("{}. {}: {} ({})", i + 1, item.description, hours_text(item, &self.currency), amount)
+                }
+            })
+            .collect();
+        
+        lines.push(String::new());
+        if !self.flat_fee {
+            let total_hours: Decimal = self.line_items
+                .iter()
+                .filter(|item| !item.is_fixed())
+                .map(|item| money::to_decimal(item.hours))
+                .sum();
+            lines.push(format!("Total Hours: {}", total_hours));
+        }
+        let breakdown = money::compute_breakdown(&self.to_invoice(), &self.line_items);
+        if let Some((tax_rate, tax)) = breakdown.tax {
+            lines.push(format!("Subtotal: {}", money::format_money_in(breakdown.subtotal, &self.currency)));
+            lines.push(format!("Tax ({}%): {}", money::format_tax_rate(tax_rate), money::format_money_in(tax, &self.currency)));
+        }
+        lines.push(format!("Total Amount: {}", money::format_money_in(breakdown.total, &self.currency)));
+        lines
+    }
+    
+    // Tax percentage charged, None when there's no tax or it's zero
+    fn applied_tax_rate(&self) -> Option<f64> {
+        self.tax_rate.filter(|rate| *rate != 0.0)
+    }
+    
     pub fn add_line_item(&mut self) {
-        let new_id = if let Some(last) = self.line_items.last() {
-            last.id + 1
-        } else {
-            1
-        };
+        self.insert_line_item_at(self.line_items.len());
+    }
+    
+    // Insert a new line item directly below the selected one, or at the end if none is selected
+    pub fn insert_line_item_below(&mut self) {
+        let index = self.line_items_list_state
+            .selected()
+            .map_or(self.line_items.len(), |i| (i + 1).min(self.line_items.len()));
+        self.insert_line_item_at(index);
+    }
+    
+    fn insert_line_item_at(&mut self, index: usize) {
+        // Items can be inserted anywhere, so the last item doesn't necessarily have the highest id
+        let new_id = self.line_items.iter().map(|item| item.id).max().unwrap_or(0) + 1;
         
         let line_item = InvoiceLineItem {
             id: new_id,
             invoice_id: self.invoice_id.unwrap_or(0), // Will be updated when invoice is saved
             description: String::new(),
             hours: 0.0,
+            amount: 0.0,
+            rate_override: None,
+            item_type: "Hourly".to_string(),
         };
         
-        self.line_items.push(line_item);
-        self.line_items_list_state.select(Some(self.line_items.len() - 1));
+        self.line_items.insert(index, line_item);
+        self.line_items_list_state.select(Some(index));
         self.editing_line_item = Some((
-            self.line_items.len() - 1,
+            index,
             LineItemField::Description,
             String::new(),
         ));
@@ -194,31 +561,79 @@ impl InvoiceWizardState {
         if let Some((idx, field, value)) = &self.editing_line_item {
             let idx = *idx;
             match field {
+                LineItemField::Description if value.trim().is_empty() => {
+                    self.show_error = Some("Description cannot be empty.".to_string());
+                }
                 LineItemField::Description => {
-                    // Save current value and move to Hours field
+                    // Save current value and move to the type, or straight to the amount
+                    // on flat-fee invoices
                     if idx < self.line_items.len() {
                         self.line_items[idx].description = value.clone();
-                        self.editing_line_item = Some((
-                            idx,
-                            LineItemField::Hours,
-                            self.line_items[idx].hours.to_string(),
-                        ));
+                        self.editing_line_item = Some(if self.flat_fee {
+                            (idx, LineItemField::Amount, self.line_items[idx].amount.to_string())
+                        } else {
+                            (idx, LineItemField::Type, self.line_items[idx].item_type.clone())
+                        });
                     }
                 }
-                LineItemField::Hours => {
+                LineItemField::Type => {
+                    // Save the chosen type and move to the Amount or Hours field
+                    if idx < self.line_items.len() {
+                        self.line_items[idx].item_type = value.clone();
+                        self.editing_line_item = Some(if self.line_items[idx].is_fixed() {
+                            (idx, LineItemField::Amount, self.line_items[idx].amount.to_string())
+                        } else {
+                            (idx, LineItemField::Hours, self.line_items[idx].hours.to_string())
+                        });
+                    }
+                }
+                LineItemField::Amount => {
                     // Save current value and finish editing
                     if idx < self.line_items.len() {
-                        match value.parse::<f64>() {
-                            Ok(hours) => {
-                                self.line_items[idx].hours = hours;
+                        match money::parse_number(value) {
+                            Some(amount) => {
+                                self.line_items[idx].amount = amount;
                                 self.editing_line_item = None;
                             }
-                            Err(_) => {
+                            None => {
+                                self.show_error = Some("Invalid amount. Please enter a valid number.".to_string());
+                            }
+                        }
+                    }
+                }
+                LineItemField::Hours => {
+                    // Save current value and move to the item's own rate
+                    if idx < self.line_items.len() {
+                        match money::parse_number(value) {
+                            Some(hours) => {
+                                self.line_items[idx].hours = hours;
+                                self.editing_line_item = Some((idx, LineItemField::Rate, rate_override_input(&self.line_items[idx])));
+                            }
+                            None => {
                                 self.show_error = Some("Invalid hours. Please enter a valid number.".to_string());
                             }
                         }
                     }
                 }
+                LineItemField::Rate => {
+                    // Save current value and finish editing, blank going back to the invoice rate
+                    if idx < self.line_items.len() {
+                        if value.trim().is_empty() {
+                            self.line_items[idx].rate_override = None;
+                            self.editing_line_item = None;
+                        } else {
+                            match money::parse_number(value).filter(|rate| *rate > 0.0) {
+                                Some(rate) => {
+                                    self.line_items[idx].rate_override = Some(rate);
+                                    self.editing_line_item = None;
+                                }
+                                None => {
+                                    self.show_error = Some("Invalid rate. Enter a number > 0, or leave it blank to use the invoice rate.".to_string());
+                                }
+                            }
+                        }
+                    }
+                }
                 LineItemField::None => {}
             }
         }
@@ -230,17 +645,37 @@ impl InvoiceWizardState {
         }
 
         match self.current_field {
+            InvoiceField::Number => {
+                match key {
+                    KeyCode::Char(c) if c.is_ascii_digit() => {
+                        self.active_input.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        self.active_input.pop();
+                    }
+                    _ => {}
+                }
+            }
             InvoiceField::SubmitDate => {
                 self.submit_date_state.handle_input(key);
                 self.submit_date = self.submit_date_state.date;
+                if self.submit_date_state.take_completed() {
+                    self.toggle_editing();
+                    self.next_field();
+                }
             }
             InvoiceField::DueDate => {
                 self.due_date_state.handle_input(key);
                 self.due_date = self.due_date_state.date;
+                if self.due_date_state.take_completed() {
+                    self.toggle_editing();
+                    self.next_field();
+                }
             }
-            InvoiceField::Rate => {
+            InvoiceField::Rate | InvoiceField::TaxRate => {
                 match key {
-                    KeyCode::Char(c) if c.is_digit(10) || c == '.' => {
+                    // Separators are allowed so pasted values like "1,250.00" come through whole
+                    KeyCode::Char(c) if c.is_ascii_digit() || c == '.' || c == ',' || c == ' ' => {
                         self.active_input.push(c);
                     }
                     KeyCode::Backspace => {
@@ -249,9 +684,55 @@ impl InvoiceWizardState {
                     _ => {}
                 }
             }
+            InvoiceField::BillTo => {
+                match key {
+                    KeyCode::Char(c) => {
+                        self.bill_to.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        self.bill_to.pop();
+                    }
+                    _ => {}
+                }
+            }
+            InvoiceField::Notes => {
+                match key {
+                    KeyCode::Char(c) => {
+                        self.notes.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        self.notes.pop();
+                    }
+                    _ => {}
+                }
+            }
+            InvoiceField::InternalNotes => {
+                match key {
+                    KeyCode::Char(c) => {
+                        self.internal_notes.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        self.internal_notes.pop();
+                    }
+                    _ => {}
+                }
+            }
             InvoiceField::LineItems => {
-                if let Some((_, _, ref mut value)) = self.editing_line_item {
+                let hours_step = self.hours_step;
+                if let Some((_, field, ref mut value)) = self.editing_line_item {
                     match key {
+                        // Nudge the hours up or down without retyping
+                        KeyCode::Char('+') | KeyCode::Up if field == LineItemField::Hours => {
+                            *value = nudge_hours(value, hours_step);
+                        }
+                        KeyCode::Char('-') | KeyCode::Down if field == LineItemField::Hours => {
+                            *value = nudge_hours(value, -hours_step);
+                        }
+                        // The type is switched rather than typed
+                        KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if field == LineItemField::Type => {
+                            *value = if value == "Fixed" { "Hourly" } else { "Fixed" }.to_string();
+                        }
+                        _ if field == LineItemField::Type => {}
                         KeyCode::Char(c) => {
                             value.push(c);
                         }
@@ -266,43 +747,158 @@ impl InvoiceWizardState {
     }
     
     pub fn to_invoice(&self) -> Invoice {
-        // Generate a new invoice number (in real app, this would be more sophisticated)
-        let number = self.invoice_id.unwrap_or_else(|| {
-            // Simple logic for demo purposes
-            let timestamp = Local::now().timestamp() as i32;
-            timestamp % 10000 // Last 4 digits of timestamp
-        });
-        
         Invoice {
             id: self.invoice_id.unwrap_or(0), // DB will assign real id for new invoices
             project_id: self.project_id,
-            number,
+            // Checked for clashes when saved, 0 numbers it after the last invoice
+            number: self.number,
             submit_date: self.submit_date,
             due_date: self.due_date,
-            rate: if self.active_input.is_empty() { 
-                self.rate 
-            } else { 
-                self.active_input.parse().unwrap_or(self.rate) 
+            rate: self.rate,
+            // New invoices and copies start as drafts, edits keep the loaded status
+            status: self.status.clone(),
+            bill_to_override: if self.bill_to.trim().is_empty() {
+                None
+            } else {
+                Some(self.bill_to.clone())
             },
-            status: "Draft".to_string(),
+            notes: if self.notes.trim().is_empty() {
+                None
+            } else {
+                Some(self.notes.clone())
+            },
+            internal_notes: if self.internal_notes.trim().is_empty() {
+                None
+            } else {
+                Some(self.internal_notes.clone())
+            },
+            billing_type: if self.flat_fee { "Flat" } else { "Hourly" }.to_string(),
+            version: self.version,
+            // Only recorded when the invoice is emailed, never saved from the wizard
+            last_emailed_at: None,
+            last_emailed_to: None,
+            // Set by marking the invoice paid on the invoices screen
+            paid_date: self.paid_date,
+            tax_rate: self.applied_tax_rate(),
         }
     }
     
-    pub fn is_valid(&self) -> bool {
-        // Basic validation
-        let rate_valid = if self.active_input.is_empty() {
-            self.rate > 0.0
+    // Whether the submit date is far enough ahead of today to look like a typo
+    pub fn is_submit_date_far_future(&self, today: NaiveDate) -> bool {
+        (self.submit_date - today).num_days() > FUTURE_SUBMIT_DATE_THRESHOLD_DAYS
+    }
+    
+    // Whether saving should stop to confirm a far-future submit date first
+    pub fn needs_future_date_confirmation(&self, today: NaiveDate) -> bool {
+        self.is_submit_date_far_future(today) && self.confirmed_future_date != Some(self.submit_date)
+    }
+    
+    // Whether anything was entered since the wizard opened
+    pub fn is_dirty(&self) -> bool {
+        self.number != self.suggested_number ||
+        self.submit_date != Local::now().date_naive() ||
+        self.due_date != self.default_due_date ||
+        self.rate != 0.0 ||
+        self.flat_fee ||
+        self.tax_rate.is_some() ||
+        !self.bill_to.is_empty() ||
+        !self.notes.is_empty() ||
+        !self.internal_notes.is_empty() ||
+        !self.line_items.is_empty()
+    }
+
+    // Start a new invoice over, asking first if anything would be lost
+    pub fn request_reset(&mut self) {
+        if self.is_dirty() {
+            self.confirm_reset = true;
         } else {
-            self.active_input.parse::<f64>().unwrap_or(0.0) > 0.0
-        };
-        
-        !self.line_items.is_empty() && rate_valid
+            self.reset();
+        }
+    }
+
+    // Back to the state of a fresh new(), keeping the project and config defaults
+    pub fn reset(&mut self) {
+        let today = Local::now().date_naive();
+        self.number = self.suggested_number;
+        self.submit_date = today;
+        self.due_date = self.default_due_date;
+        self.rate = 0.0;
+        self.flat_fee = false;
+        self.tax_rate = None;
+        self.bill_to.clear();
+        self.notes.clear();
+        self.internal_notes.clear();
+        self.line_items.clear();
+        self.current_field = InvoiceField::Number;
+        self.line_items_list_state = ListState::default();
+        self.editing_line_item = None;
+        self.editing = false;
+        self.active_input.clear();
+        self.confirmed_future_date = None;
+        self.submit_date_state = DateInputState::new(today).with_auto_advance(self.date_auto_advance);
+        self.due_date_state = DateInputState::new(self.default_due_date).with_auto_advance(self.date_auto_advance);
+        self.time_entry_ids.clear();
+        self.confirm_reset = false;
+    }
+
+    pub fn is_valid(&self) -> bool {
+        // Basic validation. The rate is only ever read from the committed value,
+        // an uncommitted edit buffer is discarded rather than re-parsed.
+        !self.line_items.is_empty() && self.is_valid_draft()
+    }
+    
+    // Drafts may be saved before any line items are added
+    pub fn is_valid_draft(&self) -> bool {
+        self.flat_fee || self.rate > 0.0
+    }
+}
+
+// The date itself if it's a business day, otherwise the first following day that is
+// neither a weekend nor one of the holidays
+pub fn next_business_day(date: NaiveDate, holidays: &[NaiveDate]) -> NaiveDate {
+    let mut day = date;
+    while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) || holidays.contains(&day) {
+        day = day.succ_opt().unwrap_or(day);
+    }
+    day
+}
+
+// Shift a date forward by whole months, clamping to the end of shorter months (Jan 31 -> Feb 28)
+pub fn shift_months(date: NaiveDate, months: u32) -> NaiveDate {
+    date.checked_add_months(Months::new(months)).unwrap_or(date)
+}
+
+// Rate override of a line item as it's edited, empty when it uses the invoice rate
+fn rate_override_input(item: &InvoiceLineItem) -> String {
+    item.rate_override.map(|rate| rate.to_string()).unwrap_or_default()
+}
+
+// Tax rate as it's edited, empty when the invoice has no tax
+fn tax_rate_input(tax_rate: Option<f64>) -> String {
+    tax_rate.map(|rate| rate.to_string()).unwrap_or_default()
+}
+
+// Hours of a line item as listed, along with its own rate when it has one
+fn hours_text(item: &InvoiceLineItem, currency: &str) -> String {
+    match item.rate_override {
+        Some(rate) => format!("{} hours at {}/hour", item.hours, money::format_money_in(rate, currency)),
+        None => format!("{} hours", item.hours),
     }
 }
 
+// Adjust an hours input by delta, treating empty or invalid input as zero and clamping at zero
+fn nudge_hours(value: &str, delta: f64) -> String {
+    let current = money::parse_number(value).unwrap_or(0.0);
+    let adjusted = (current + delta).max(0.0);
+    
+    // Round away floating point noise from repeated steps
+    ((adjusted * 10000.0).round() / 10000.0).to_string()
+}
+
 pub enum InvoiceWizardAction {
     Cancel,
     Save(Invoice, Vec<InvoiceLineItem>),
+    SaveDraft(Invoice, Vec<InvoiceLineItem>),
 }
 
 pub fn render_invoice_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState) {
@@ -337,21 +933,31 @@ pub fn render_invoice_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut Invoi
 
     // Help text
     let help_text = match (state.editing, state.current_field) {
-        (false, _) => "Enter - Edit field | Up/Down - Navigate fields | S - Save invoice | Esc - Cancel",
+        (false, _) if state.invoice_id.is_none() =>
+            "Enter - Edit field | Up/Down - Navigate fields | B - Hourly/Flat fee | S - Save invoice | Shift+S - Save draft | Ctrl+R - Reset | Esc - Cancel",
+        (false, _) => "Enter - Edit field | Up/Down - Navigate fields | B - Hourly/Flat fee | S - Save invoice | Shift+S - Save draft | Esc - Cancel",
         (true, InvoiceField::SubmitDate | InvoiceField::DueDate) => 
             "Enter - Save field | Left/Right - Switch date part | Esc - Cancel editing",
-        (true, InvoiceField::Rate) => 
-            "Enter - Save field | Esc - Cancel editing",
+        (true, InvoiceField::Number | InvoiceField::Rate) => 
+            "Enter - Save field | Ctrl+Z - Undo changes | Esc - Cancel editing",
+        (true, InvoiceField::TaxRate) => 
+            "Enter - Save field (blank for no tax) | Ctrl+Z - Undo changes | Esc - Cancel editing",
+        (true, InvoiceField::BillTo | InvoiceField::Notes | InvoiceField::InternalNotes) => 
+            "Enter - Save field | Ctrl+N - New line | Ctrl+Z - Undo changes | Esc - Cancel editing",
         (true, InvoiceField::LineItems) => {
-            if state.editing_line_item.is_some() {
-                "Enter - Next field | Tab - Next field | Esc - Cancel editing"
+            if let Some((_, LineItemField::Hours, _)) = state.editing_line_item {
+                "Enter - Next field | Tab - Next field | +/- or Up/Down - Adjust hours | Ctrl+Z - Undo changes | Esc - Cancel editing"
+            } else if let Some((_, LineItemField::Type, _)) = state.editing_line_item {
+                "Space or Left/Right - Hourly/Fixed amount | Enter - Next field | Tab - Next field | Esc - Cancel editing"
+            } else if state.editing_line_item.is_some() {
+                "Enter - Next field | Tab - Next field | Ctrl+Z - Undo changes | Esc - Cancel editing"
             } else {
-                "A - Add item | E - Edit selected | D - Delete selected | Enter - Done | Esc - Cancel"
+                "A - Add item | I - Insert below selected | E - Edit selected | D - Delete selected | C - Copy previous invoice's items | Enter - Done | Esc - Cancel"
             }
         }
     };
     
-    let help = Paragraph::new(help_text)
+    let help = Paragraph::new(footer_spans(help_text, chunks[2].width.saturating_sub(2) as usize))
         .style(Style::default().fg(Color::Gray))
         .block(Block::default().borders(Borders::ALL));
     frame.render_widget(help, chunks[2]);
@@ -359,6 +965,14 @@ pub fn render_invoice_wizard<B: Backend>(frame: &mut Frame<B>, state: &mut Invoi
     // Show error if needed
     if let Some(error) = &state.show_error {
         render_error(frame, frame.size(), error);
+    } else if let Some(warning) = &state.show_warning {
+        render_warning(frame, frame.size(), warning);
+    } else if state.confirm_reset {
+        render_confirmation(frame, "Reset", "Clear all fields and start over?");
+    }
+    
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
     }
 }
 
@@ -367,15 +981,41 @@ fn render_form<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState,
         .direction(Direction::Vertical)
         .constraints(
             [
+                Constraint::Length(3),  // Number
                 Constraint::Length(3),  // Submit Date
                 Constraint::Length(3),  // Due Date
                 Constraint::Length(3),  // Rate
+                Constraint::Length(3),  // Tax Rate
+                Constraint::Length(3),  // Bill To
+                Constraint::Length(3),  // Notes and Internal Notes
                 Constraint::Min(6),     // Line Items
             ]
             .as_ref(),
         )
         .split(area);
     
+    // Number
+    let number_style = if state.current_field == InvoiceField::Number {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    
+    let number_value = if state.current_field == InvoiceField::Number && state.editing {
+        format!("{}|", state.active_input)
+    } else if state.number > 0 {
+        state.number.to_string()
+    } else {
+        "Next available".to_string()
+    };
+    
+    let number = Paragraph::new(Spans::from(vec![
+        Span::styled("Invoice Number: ", number_style),
+        Span::raw(number_value),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(number, form_chunks[0]);
+    
     // Submit Date
     let submit_date_style = if state.current_field == InvoiceField::SubmitDate {
         Style::default().fg(Color::Yellow)
@@ -394,7 +1034,7 @@ fn render_form<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState,
         Span::raw(submit_date_value),
     ]))
     .block(Block::default().borders(Borders::ALL));
-    frame.render_widget(submit_date, form_chunks[0]);
+    frame.render_widget(submit_date, form_chunks[1]);
     
     // Due Date
     let due_date_style = if state.current_field == InvoiceField::DueDate {
@@ -414,7 +1054,7 @@ fn render_form<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState,
         Span::raw(due_date_value),
     ]))
     .block(Block::default().borders(Borders::ALL));
-    frame.render_widget(due_date, form_chunks[1]);
+    frame.render_widget(due_date, form_chunks[2]);
     
     // Rate
     let rate_style = if state.current_field == InvoiceField::Rate {
@@ -429,12 +1069,112 @@ fn render_form<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState,
         format!("{:.2}", state.rate)
     };
     
-    let rate = Paragraph::new(Spans::from(vec![
-        Span::styled("Hourly Rate: $", rate_style),
-        Span::raw(rate_value),
+    let rate = Paragraph::new(if state.flat_fee {
+        Spans::from(vec![
+            Span::raw("Billing: "),
+            Span::raw("Flat fee (amount entered per line item)"),
+        ])
+    } else {
+        Spans::from(vec![
+            Span::styled(format!("Hourly Rate: {}", money::currency_symbol(&state.currency)), rate_style),
+            Span::raw(rate_value),
+        ])
+    })
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(rate, form_chunks[3]);
+    
+    // Tax Rate
+    let tax_rate_style = if state.current_field == InvoiceField::TaxRate {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    
+    let tax_rate_value = if state.current_field == InvoiceField::TaxRate && state.editing {
+        format!("{}|", state.active_input)
+    } else {
+        match state.applied_tax_rate() {
+            Some(rate) => format!("{}%", money::format_tax_rate(rate)),
+            None => "No tax".to_string(),
+        }
+    };
+    
+    let tax_rate = Paragraph::new(Spans::from(vec![
+        Span::styled("Tax Rate: ", tax_rate_style),
+        Span::raw(tax_rate_value),
     ]))
     .block(Block::default().borders(Borders::ALL));
-    frame.render_widget(rate, form_chunks[2]);
+    frame.render_widget(tax_rate, form_chunks[4]);
+    
+    // Bill To
+    let bill_to_style = if state.current_field == InvoiceField::BillTo {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    
+    let bill_to_value = if state.current_field == InvoiceField::BillTo && state.editing {
+        format!("{}|", state.bill_to.replace('\n', " / "))
+    } else if state.bill_to.is_empty() {
+        "Client address".to_string()
+    } else {
+        state.bill_to.replace('\n', " / ")
+    };
+    
+    let bill_to = Paragraph::new(Spans::from(vec![
+        Span::styled("Bill To: ", bill_to_style),
+        Span::raw(bill_to_value),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(bill_to, form_chunks[5]);
+    
+    // Notes and Internal Notes share a row
+    let notes_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(form_chunks[6]);
+    
+    let client_notes_style = if state.current_field == InvoiceField::Notes {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    
+    let client_notes_value = if state.current_field == InvoiceField::Notes && state.editing {
+        format!("{}|", state.notes.replace('\n', " / "))
+    } else if state.notes.is_empty() {
+        "None (e.g. payment terms)".to_string()
+    } else {
+        state.notes.replace('\n', " / ")
+    };
+    
+    let client_notes = Paragraph::new(Spans::from(vec![
+        Span::styled("Notes: ", client_notes_style),
+        Span::raw(client_notes_value),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(client_notes, notes_chunks[0]);
+    
+    let notes_style = if state.current_field == InvoiceField::InternalNotes {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+    
+    let notes_value = if state.current_field == InvoiceField::InternalNotes && state.editing {
+        format!("{}|", state.internal_notes.replace('\n', " / "))
+    } else if state.internal_notes.is_empty() {
+        "None (not shown on the invoice)".to_string()
+    } else {
+        state.internal_notes.replace('\n', " / ")
+    };
+    
+    let notes = Paragraph::new(Spans::from(vec![
+        Span::styled("Internal Notes: ", notes_style),
+        Span::raw(notes_value),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(notes, notes_chunks[1]);
     
     // Line Items
     let line_items_block = Block::default()
@@ -452,71 +1192,156 @@ fn render_form<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState,
     
     if state.current_field == InvoiceField::LineItems && state.editing {
         if let Some((idx, field, value)) = &state.editing_line_item {
-            // Editing a line item
-            let line_items_area = line_items_block.inner(form_chunks[3]);
-            frame.render_widget(line_items_block, form_chunks[3]);
+            // Editing a line item as a small form of its own
+            let (idx, field) = (*idx, *field);
+            let stored = state.line_items.get(idx);
+            // The type being chosen decides which boxes follow it
+            let fixed = if field == LineItemField::Type {
+                value == "Fixed"
+            } else {
+                stored.is_some_and(|item| item.is_fixed())
+            };
+            let bills_amount = state.flat_fee || fixed;
+            let quantity_field = if bills_amount { LineItemField::Amount } else { LineItemField::Hours };
+            
+            let line_items_block = line_items_block.title(format!(
+                "Edit Line Item {} of {}",
+                idx + 1,
+                state.line_items.len(),
+            ));
+            let line_items_area = line_items_block.inner(form_chunks[7]);
+            frame.render_widget(line_items_block, form_chunks[7]);
             
+            // Description, the type on hourly invoices, hours or amount, and the item's own
+            // rate for hourly items, followed by the line total preview and the keys
+            let box_count = 2 + usize::from(!state.flat_fee) + usize::from(!bills_amount);
+            let mut constraints = vec![Constraint::Length(3); box_count];
+            constraints.extend([Constraint::Length(1), Constraint::Length(1)]);
             let edit_chunks = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(3),  // Description
-                    Constraint::Length(3),  // Hours
-                ])
+                .constraints(constraints)
                 .split(line_items_area);
             
-            // Description field
-            let desc_style = if *field == LineItemField::Description {
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            // Values shown in each box, the active one being what's typed so far
+            let description = if field == LineItemField::Description {
+                value.clone()
             } else {
-                Style::default()
+                stored.map(|item| item.description.clone()).unwrap_or_default()
             };
-            
-            let desc_value = if *field == LineItemField::Description {
-                format!("{}{}", value, if *field == LineItemField::Description { "|" } else { "" })
-            } else if *idx < state.line_items.len() {
-                state.line_items[*idx].description.clone()
+            let item_type = if fixed { "Fixed amount" } else { "Hourly" }.to_string();
+            let quantity = if field == quantity_field {
+                value.clone()
+            } else if bills_amount {
+                stored.map(|item| item.amount.to_string()).unwrap_or_default()
             } else {
-                String::new()
+                stored.map(|item| item.hours.to_string()).unwrap_or_default()
             };
-            
-            let desc_paragraph = Paragraph::new(Spans::from(vec![
-                Span::raw("Description: "),
-                Span::styled(desc_value, desc_style),
-            ]))
-            .block(Block::default().borders(Borders::ALL));
-            frame.render_widget(desc_paragraph, edit_chunks[0]);
-            
-            // Hours field
-            let hours_style = if *field == LineItemField::Hours {
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            let rate = if field == LineItemField::Rate {
+                value.clone()
             } else {
-                Style::default()
+                stored.map(rate_override_input).unwrap_or_default()
             };
             
-            let hours_value = if *field == LineItemField::Hours {
-                format!("{}{}", value, if *field == LineItemField::Hours { "|" } else { "" })
-            } else if *idx < state.line_items.len() {
-                state.line_items[*idx].hours.to_string()
+            let quantity_label = if bills_amount {
+                format!("Amount ({})", money::currency_symbol(&state.currency))
             } else {
-                String::new()
+                "Hours".to_string()
+            };
+            let rate_label = format!("Rate ({}/hour, blank uses {})", money::currency_symbol(&state.currency), money::format_money_in(state.rate, &state.currency));
+            let mut fields = vec![(LineItemField::Description, "Description", &description)];
+            if !state.flat_fee {
+                fields.push((LineItemField::Type, "Type", &item_type));
+            }
+            fields.push((quantity_field, quantity_label.as_str(), &quantity));
+            if !bills_amount {
+                fields.push((LineItemField::Rate, rate_label.as_str(), &rate));
+            }
+            for (i, (box_field, label, text)) in fields.into_iter().enumerate() {
+                let active = box_field == field;
+                let border_style = if active {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Gray)
+                };
+                let block = Block::default()
+                    .title(if active { format!("{} (editing)", label) } else { label.to_string() })
+                    .borders(Borders::ALL)
+                    .border_style(border_style);
+                let inner = block.inner(edit_chunks[i]);
+                
+                let paragraph = Paragraph::new(text.as_str())
+                    .style(Style::default().fg(Color::White))
+                    .block(block);
+                frame.render_widget(paragraph, edit_chunks[i]);
+                
+                // Put the terminal cursor right after the typed text
+                if active && inner.width > 0 {
+                    let offset = (text.width() as u16).min(inner.width - 1);
+                    frame.set_cursor(inner.x + offset, inner.y);
+                }
+            }
+            
+            // What this line will bill with the values entered so far, a blank rate
+            // meaning the invoice rate
+            let rate_override = if rate.trim().is_empty() { Some(None) } else { money::parse_number(&rate).map(Some) };
+            let preview_item = stored.cloned().zip(money::parse_number(&quantity)).map(|(mut item, number)| {
+                item.item_type = if fixed { "Fixed" } else { "Hourly" }.to_string();
+                if bills_amount {
+                    item.amount = number;
+                } else {
+                    item.hours = number;
+                }
+                item
+            });
+            let preview = match (preview_item, rate_override) {
+                (Some(item), _) if bills_amount => format!("Line total: {}", money::format_money_in(state.line_item_amount(&item), &state.currency)),
+                (Some(mut item), Some(rate_override)) => {
+                    item.rate_override = rate_override;
+                    format!(
+                        "Line total: {} ({} hours at {}/hour)",
+                        money::format_money_in(state.line_item_amount(&item), &state.currency),
+                        item.hours,
+                        money::format_money_in(item.effective_rate(state.rate), &state.currency),
+                    )
+                }
+                (Some(_), None) => "Line total: enter a valid rate, or leave it blank".to_string(),
+                _ => format!("Line total: enter a valid number of {}", if bills_amount { "amount" } else { "hours" }),
             };
+            frame.render_widget(
+                Paragraph::new(preview).style(Style::default().fg(Color::Cyan)),
+                edit_chunks[box_count],
+            );
             
-            let hours_paragraph = Paragraph::new(Spans::from(vec![
-                Span::raw("Hours: "),
-                Span::styled(hours_value, hours_style),
-            ]))
-            .block(Block::default().borders(Borders::ALL));
-            frame.render_widget(hours_paragraph, edit_chunks[1]);
+            let keys = match field {
+                LineItemField::Description => {
+                    format!("Enter/Tab - Next: {} | Esc - Cancel", if state.flat_fee { "Amount" } else { "Type" })
+                }
+                LineItemField::Type => {
+                    format!("Space - Switch type | Enter/Tab - Next: {} | Esc - Cancel", if fixed { "Amount" } else { "Hours" })
+                }
+                LineItemField::Hours => "Enter/Tab - Next: Rate | Esc - Cancel".to_string(),
+                _ => "Enter/Tab - Save line item | Esc - Cancel".to_string(),
+            };
+            frame.render_widget(
+                Paragraph::new(keys).style(Style::default().fg(Color::Gray)),
+                edit_chunks[box_count + 1],
+            );
             
         } else {
             // Viewing line items with controls
             let line_items = state.line_items
                 .iter()
-                .map(|item| {
-                    ListItem::new(format!("{}: {} hours (${:.2})", 
-                                      item.description, 
-                                      item.hours, 
-                                      item.hours * state.rate))
+                .enumerate()
+                .map(|(i, item)| {
+                    if state.bills_amount(item) {
+                        ListItem::new(format!("{}. {}: {}", i + 1, item.description, money::format_money_in(state.line_item_amount(item), &state.currency)))
+                    } else {
+                        ListItem::new(format!("{}. {}: {} ({})", 
+                                          i + 1,
+                                          item.description, 
+                                          hours_text(item, &state.currency), 
+                                          money::format_money_in(state.line_item_amount(item), &state.currency)))
+                    }
                 })
                 .collect::<Vec<_>>();
             
@@ -524,35 +1349,16 @@ fn render_form<B: Backend>(frame: &mut Frame<B>, state: &mut InvoiceWizardState,
                 .block(line_items_block)
                 .highlight_style(Style::default().bg(Color::Blue).fg(Color::White));
             
-            frame.render_stateful_widget(list, form_chunks[3], &mut state.line_items_list_state);
+            frame.render_stateful_widget(list, form_chunks[7], &mut state.line_items_list_state);
         }
     } else {
         // Just showing line items as part of the form
-        let mut content = Vec::new();
-        
-        // Calculate total
-        let total_hours: f64 = state.line_items.iter().map(|item| item.hours).sum();
-        let total_amount = total_hours * state.rate;
-        
-        if state.line_items.is_empty() {
-            content.push(Spans::from("No line items added yet"));
-        } else {
-            for item in &state.line_items {
-                content.push(Spans::from(format!("- {}: {} hours (${:.2})", 
-                                        item.description, 
-                                        item.hours, 
-                                        item.hours * state.rate)));
-            }
-            
-            content.push(Spans::from(""));
-            content.push(Spans::from(format!("Total Hours: {}", total_hours)));
-            content.push(Spans::from(format!("Total Amount: ${:.2}", total_amount)));
-        }
+        let content: Vec<Spans> = state.line_items_summary().into_iter().map(Spans::from).collect();
         
         let paragraph = Paragraph::new(content)
             .block(line_items_block);
         
-        frame.render_widget(paragraph, form_chunks[3]);
+        frame.render_widget(paragraph, form_chunks[7]);
     }
 }
 
@@ -571,6 +1377,21 @@ fn render_error<B: Backend>(frame: &mut Frame<B>, size: Rect, error: &str) {
     frame.render_widget(error_msg, popup_area);
 }
 
+fn render_warning<B: Backend>(frame: &mut Frame<B>, size: Rect, warning: &str) {
+    let popup_area = centered_rect(60, 20, size);
+    
+    let warning_msg = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(warning),
+        Spans::from(""),
+        Spans::from("Press S again to save anyway, or any other key to go back"),
+    ])
+    .block(Block::default().title("Warning").borders(Borders::ALL))
+    .style(Style::default().fg(Color::Yellow));
+    
+    frame.render_widget(warning_msg, popup_area);
+}
+
 // Helper function to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -593,11 +1414,59 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 pub fn handle_input(state: &mut InvoiceWizardState) -> Result<Option<InvoiceWizardAction>> {
-    // Clear any existing error message
+    let event = event::read()?;
+    handle_event(state, event)
+}
+
+// Apply a terminal event to the wizard, split from reading it so key sequences can be replayed
+fn handle_event(state: &mut InvoiceWizardState, event: Event) -> Result<Option<InvoiceWizardAction>> {
+    // Clear any existing error or warning message
     state.show_error = None;
+    let was_warning = state.show_warning.take().is_some();
     
-    if let Event::Key(key) = event::read()? {
+    if let Event::Key(key) = event {
+        // Any key other than S backs out of the future date confirmation and does nothing else
+        if was_warning && key.code != KeyCode::Char('s') {
+            state.confirmed_future_date = None;
+            return Ok(None);
+        }
+        
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+        
+        if state.confirm_reset {
+            state.confirm_reset = false;
+            if key.code == KeyCode::Char('y') {
+                state.reset();
+            }
+            return Ok(None);
+        }
+        
         match key.code {
+            // Line items are browsed in editing mode, where ? isn't typed into anything
+            KeyCode::Char('?') if !state.editing || 
+                                  (state.current_field == InvoiceField::LineItems && state.editing_line_item.is_none()) => {
+                state.show_help = true;
+            }
+            KeyCode::Char('n') if state.editing && state.current_field == InvoiceField::BillTo && 
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.bill_to.push('\n');
+            }
+            KeyCode::Char('n') if state.editing && state.current_field == InvoiceField::Notes && 
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.notes.push('\n');
+            }
+            KeyCode::Char('n') if state.editing && state.current_field == InvoiceField::InternalNotes && 
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.internal_notes.push('\n');
+            }
+            KeyCode::Char('z') if state.editing && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.undo_edit();
+            }
             KeyCode::Esc => {
                 if state.editing {
                     state.toggle_editing();
@@ -607,14 +1476,36 @@ pub fn handle_input(state: &mut InvoiceWizardState) -> Result<Option<InvoiceWiza
             }
             KeyCode::Enter => {
                 if state.editing {
-                    if state.current_field == InvoiceField::Rate {
+                    if state.current_field == InvoiceField::Number {
+                        // Whether it's free is checked when the invoice is saved
+                        match state.active_input.parse::<i32>().ok().filter(|number| *number > 0) {
+                            Some(number) => {
+                                state.number = number;
+                                state.toggle_editing();
+                            }
+                            None => {
+                                state.show_error = Some("Invalid invoice number. Enter a whole number greater than 0.".to_string());
+                            }
+                        }
+                    } else if state.current_field == InvoiceField::Rate {
                         // Validate rate
-                        if let Ok(rate) = state.active_input.parse::<f64>() {
+                        if let Some(rate) = money::parse_number(&state.active_input) {
                             state.rate = rate;
                             state.toggle_editing();
                         } else {
                             state.show_error = Some("Invalid rate. Please enter a valid number.".to_string());
                         }
+                    } else if state.current_field == InvoiceField::TaxRate {
+                        // Blank means the invoice has no tax
+                        if state.active_input.trim().is_empty() {
+                            state.tax_rate = None;
+                            state.toggle_editing();
+                        } else if let Some(rate) = money::parse_number(&state.active_input).filter(|rate| (0.0..=100.0).contains(rate)) {
+                            state.tax_rate = Some(rate);
+                            state.toggle_editing();
+                        } else {
+                            state.show_error = Some("Invalid tax rate. Enter a percentage from 0 to 100, or leave it blank for no tax.".to_string());
+                        }
                     } else if state.current_field == InvoiceField::LineItems {
                         if state.editing_line_item.is_some() {
                             state.next_field_in_line_item();
@@ -629,16 +1520,50 @@ pub fn handle_input(state: &mut InvoiceWizardState) -> Result<Option<InvoiceWiza
                 }
             }
             KeyCode::Char('s') if !state.editing => {
-                if state.is_valid() {
+                let today = Local::now().date_naive();
+                if let Some(i) = state.first_blank_line_item() {
+                    state.show_error = Some(format!("Line item {} has no description. Edit or delete it before saving.", i + 1));
+                } else if state.is_valid() && state.needs_future_date_confirmation(today) {
+                    // Soft confirmation: the next save with the same date goes through
+                    state.confirmed_future_date = Some(state.submit_date);
+                    state.show_warning = Some(format!(
+                        "Submit date {} is {} days in the future. Continue?",
+                        state.submit_date.format("%Y-%m-%d"),
+                        (state.submit_date - today).num_days(),
+                    ));
+                } else if state.is_valid() {
                     let invoice = state.to_invoice();
                     return Ok(Some(InvoiceWizardAction::Save(
                         invoice,
                         state.line_items.clone(),
                     )));
                 } else {
-                    state.show_error = Some("Please complete all required fields. Rate must be > 0 and at least one line item is required.".to_string());
+                    state.show_error = Some(if state.flat_fee {
+                        "Please complete all required fields. At least one line item is required.".to_string()
+                    } else {
+                        "Please complete all required fields. Rate must be > 0 and at least one line item is required.".to_string()
+                    });
+                }
+            }
+            KeyCode::Char('S') if !state.editing => {
+                if let Some(i) = state.first_blank_line_item() {
+                    state.show_error = Some(format!("Line item {} has no description. Edit or delete it before saving.", i + 1));
+                } else if state.is_valid_draft() {
+                    return Ok(Some(InvoiceWizardAction::SaveDraft(
+                        state.to_invoice(),
+                        state.line_items.clone(),
+                    )));
+                } else {
+                    state.show_error = Some("Rate must be > 0 to save a draft.".to_string());
                 }
             }
+            KeyCode::Char('r') if !state.editing && state.invoice_id.is_none() &&
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.request_reset();
+            }
+            KeyCode::Char('b') if !state.editing => {
+                state.toggle_billing_type();
+            }
             KeyCode::Char('a') => {
                 if state.current_field == InvoiceField::LineItems && state.editing && state.editing_line_item.is_none() {
                     state.add_line_item();
@@ -646,6 +1571,20 @@ pub fn handle_input(state: &mut InvoiceWizardState) -> Result<Option<InvoiceWiza
                     state.edit_current_field(key.code);
                 }
             }
+            KeyCode::Char('i') => {
+                if state.current_field == InvoiceField::LineItems && state.editing && state.editing_line_item.is_none() {
+                    state.insert_line_item_below();
+                } else if state.editing {
+                    state.edit_current_field(key.code);
+                }
+            }
+            KeyCode::Char('c') => {
+                if state.current_field == InvoiceField::LineItems && state.editing && state.editing_line_item.is_none() {
+                    state.copy_previous_line_items();
+                } else if state.editing {
+                    state.edit_current_field(key.code);
+                }
+            }
             KeyCode::Char('e') => {
                 if state.current_field == InvoiceField::LineItems && state.editing && 
                    state.editing_line_item.is_none() && state.line_items_list_state.selected().is_some() {
@@ -721,10 +1660,11 @@ pub fn handle_input(state: &mut InvoiceWizardState) -> Result<Option<InvoiceWiza
 pub async fn save_invoice_with_line_items(
     db: &crate::db::Database, 
     invoice: &Invoice, 
-    line_items: &[InvoiceLineItem]
-) -> Result<i32> {
+    line_items: &[InvoiceLineItem],
+    time_entry_ids: &[i32],
+) -> crate::error::Result<i32> {
     // Use the database layer's method instead of direct access
-    let invoice_id = db.save_invoice_with_line_items(invoice, line_items).await?;
+    let invoice_id = db.save_invoice_billing_time_entries(invoice, line_items, time_entry_ids).await?;
     
     // No longer generating invoice files here - will be done on-demand when email wizard is opened
     
@@ -733,5 +1673,393 @@ pub async fn save_invoice_with_line_items(
 
 pub async fn get_invoice_with_line_items(db: &crate::db::Database, id: i32) -> Result<(Invoice, Vec<InvoiceLineItem>)> {
     // Use the database layer instead of direct access
-    db.get_invoice_with_line_items(id).await
-} 
\ No newline at end of file
+    Ok(db.get_invoice_with_line_items(id).await?)
+} 
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use super::*;
+    use crate::test_support::{config, date, invoice, line_item};
+
+    // Feed keys to the wizard as if typed, returning the last action
+    fn press(state: &mut InvoiceWizardState, keys: &[KeyCode]) -> Option<InvoiceWizardAction> {
+        keys.iter().fold(None, |_, key| {
+            handle_event(state, Event::Key(KeyEvent::new(*key, KeyModifiers::NONE))).unwrap()
+        })
+    }
+
+    fn type_text(state: &mut InvoiceWizardState, text: &str) {
+        for c in text.chars() {
+            press(state, &[KeyCode::Char(c)]);
+        }
+    }
+
+    // Wizard for a saved invoice at the fixture's rate of 100 with the given line items
+    fn editing(line_items: Vec<InvoiceLineItem>) -> InvoiceWizardState {
+        InvoiceWizardState::new(1, Some(7), Some(invoice(7)), Some(line_items), &config())
+    }
+
+    fn paid_invoice() -> Invoice {
+        Invoice {
+            status: "Paid".to_string(),
+            paid_date: Some(date(2024, 2, 1)),
+            ..invoice(7)
+        }
+    }
+
+    #[test]
+    fn a_committed_rate_is_what_gets_saved() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.current_field = InvoiceField::Rate;
+        press(&mut state, &[KeyCode::Enter]);
+        for _ in 0..5 {
+            press(&mut state, &[KeyCode::Backspace]);
+        }
+        type_text(&mut state, "1,250.50");
+        press(&mut state, &[KeyCode::Enter]);
+
+        assert!(!state.editing);
+        assert_eq!(state.to_invoice().rate, 1250.5);
+        assert!(state.is_valid());
+    }
+
+    #[test]
+    fn an_uncommitted_rate_edit_is_ignored() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.current_field = InvoiceField::Rate;
+        press(&mut state, &[KeyCode::Enter]);
+        type_text(&mut state, "9");
+        assert_eq!(state.active_input, "1009");
+
+        // Neither validation nor the saved invoice read the edit buffer
+        assert_eq!(state.to_invoice().rate, 100.0);
+        press(&mut state, &[KeyCode::Esc]);
+        assert_eq!(state.to_invoice().rate, 100.0);
+    }
+
+    #[test]
+    fn an_invalid_rate_isnt_committed() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.rate = 0.0;
+        state.current_field = InvoiceField::Rate;
+        press(&mut state, &[KeyCode::Enter, KeyCode::Backspace]);
+        type_text(&mut state, "1.2.3");
+        press(&mut state, &[KeyCode::Enter]);
+
+        assert!(state.show_error.is_some());
+        assert!(state.editing);
+        assert_eq!(state.rate, 0.0);
+        assert!(!state.is_valid());
+    }
+
+    #[test]
+    fn nudging_empty_or_zero_hours_starts_from_zero() {
+        assert_eq!(nudge_hours("", 0.5), "0.5");
+        assert_eq!(nudge_hours("0", 0.25), "0.25");
+        assert_eq!(nudge_hours("abc", 1.0), "1");
+        assert_eq!(nudge_hours("1.5", 0.5), "2");
+    }
+
+    #[test]
+    fn nudging_hours_below_zero_stops_at_zero() {
+        assert_eq!(nudge_hours("0.25", -0.5), "0");
+        assert_eq!(nudge_hours("", -0.5), "0");
+        assert_eq!(nudge_hours("0", -1.0), "0");
+    }
+
+    #[test]
+    fn repeated_nudges_dont_collect_float_noise() {
+        let mut hours = String::new();
+        for _ in 0..3 {
+            hours = nudge_hours(&hours, 0.1);
+        }
+        assert_eq!(hours, "0.3");
+    }
+
+    #[test]
+    fn shifting_months_clamps_to_shorter_months() {
+        assert_eq!(shift_months(date(2023, 1, 31), 1), date(2023, 2, 28));
+        assert_eq!(shift_months(date(2024, 1, 31), 1), date(2024, 2, 29));
+        assert_eq!(shift_months(date(2024, 3, 31), 1), date(2024, 4, 30));
+        assert_eq!(shift_months(date(2024, 1, 15), 1), date(2024, 2, 15));
+    }
+
+    #[test]
+    fn shifting_months_rolls_over_the_year() {
+        assert_eq!(shift_months(date(2023, 12, 31), 1), date(2024, 1, 31));
+        assert_eq!(shift_months(date(2023, 11, 30), 3), date(2024, 2, 29));
+        assert_eq!(shift_months(date(2024, 5, 10), 12), date(2025, 5, 10));
+    }
+
+    #[test]
+    fn weekend_due_dates_move_to_monday() {
+        assert_eq!(next_business_day(date(2024, 1, 6), &[]), date(2024, 1, 8));
+        assert_eq!(next_business_day(date(2024, 1, 7), &[]), date(2024, 1, 8));
+        assert_eq!(next_business_day(date(2024, 1, 5), &[]), date(2024, 1, 5));
+    }
+
+    #[test]
+    fn holidays_are_skipped_along_with_weekends() {
+        // Saturday, with the Monday after a holiday
+        assert_eq!(next_business_day(date(2024, 1, 6), &[date(2024, 1, 8)]), date(2024, 1, 9));
+        assert_eq!(next_business_day(date(2024, 12, 25), &[date(2024, 12, 25), date(2024, 12, 26)]), date(2024, 12, 27));
+    }
+
+    #[test]
+    fn completing_a_date_moves_to_the_next_field_when_enabled() {
+        let config = Config { date_auto_advance: true, ..config() };
+        let mut state = InvoiceWizardState::new(1, Some(7), Some(invoice(7)), Some(vec![line_item(1, "Work", 2.0)]), &config);
+        state.current_field = InvoiceField::SubmitDate;
+        press(&mut state, &[KeyCode::Enter]);
+        type_text(&mut state, "20240301");
+
+        assert_eq!(state.submit_date, date(2024, 3, 1));
+        assert!(!state.editing);
+        assert!(state.current_field == InvoiceField::DueDate);
+    }
+
+    #[test]
+    fn completing_a_date_stays_on_the_field_by_default() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.current_field = InvoiceField::SubmitDate;
+        press(&mut state, &[KeyCode::Enter]);
+        type_text(&mut state, "2024");
+        press(&mut state, &[KeyCode::Right]);
+        type_text(&mut state, "03");
+        press(&mut state, &[KeyCode::Right]);
+        type_text(&mut state, "01");
+
+        assert_eq!(state.submit_date, date(2024, 3, 1));
+        assert!(state.editing);
+        assert!(state.current_field == InvoiceField::SubmitDate);
+    }
+
+    #[test]
+    fn submit_dates_past_the_threshold_need_confirming() {
+        let today = date(2024, 1, 1);
+        let mut state = InvoiceWizardState::new(1, None, None, None, &config());
+        state.submit_date = today + chrono::Duration::days(FUTURE_SUBMIT_DATE_THRESHOLD_DAYS);
+        assert!(!state.needs_future_date_confirmation(today));
+
+        state.submit_date = today + chrono::Duration::days(FUTURE_SUBMIT_DATE_THRESHOLD_DAYS + 1);
+        assert!(state.needs_future_date_confirmation(today));
+
+        state.confirmed_future_date = Some(state.submit_date);
+        assert!(!state.needs_future_date_confirmation(today));
+
+        // Changing the date again asks again
+        state.submit_date += chrono::Duration::days(1);
+        assert!(state.needs_future_date_confirmation(today));
+    }
+
+    #[test]
+    fn summary_shows_the_breakdown_of_the_invoice() {
+        let taxed = Invoice { tax_rate: Some(7.5), ..invoice(7) };
+        let items = vec![line_item(1, "Design", 2.5), line_item(2, "Meetings", 1.25)];
+        let state = InvoiceWizardState::new(1, Some(7), Some(taxed), Some(items.clone()), &config());
+        let breakdown = money::compute_breakdown(&state.to_invoice(), &items);
+        let (tax_rate, tax) = breakdown.tax.unwrap();
+        
+        let summary = state.line_items_summary();
+        assert_eq!(&summary[3..], [
+            "Total Hours: 3.75".to_string(),
+            format!("Subtotal: {}", money::format_money_in(breakdown.subtotal, "USD")),
+            format!("Tax ({}%): {}", money::format_tax_rate(tax_rate), money::format_money_in(tax, "USD")),
+            format!("Total Amount: {}", money::format_money_in(breakdown.total, "USD")),
+        ]);
+        assert_eq!(summary[6], "Total Amount: $403.13");
+    }
+
+    // Descriptions of the line items a save key would write
+    fn saved_descriptions(action: Option<InvoiceWizardAction>) -> Vec<String> {
+        match action {
+            Some(InvoiceWizardAction::Save(_, items)) | Some(InvoiceWizardAction::SaveDraft(_, items)) => {
+                items.into_iter().map(|item| item.description).collect()
+            }
+            _ => panic!("expected the invoice to be saved"),
+        }
+    }
+
+    #[test]
+    fn a_half_entered_line_item_is_discarded_on_save() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.current_field = InvoiceField::LineItems;
+        press(&mut state, &[KeyCode::Enter, KeyCode::Char('a')]);
+        type_text(&mut state, "Unfinish");
+        assert_eq!(state.line_items.len(), 2);
+
+        // Leaving line item editing drops the item before its description was committed
+        press(&mut state, &[KeyCode::Esc]);
+        assert_eq!(state.line_items.len(), 1);
+        assert_eq!(saved_descriptions(press(&mut state, &[KeyCode::Char('s')])), ["Work"]);
+    }
+
+    #[test]
+    fn a_committed_description_keeps_the_new_line_item() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.current_field = InvoiceField::LineItems;
+        press(&mut state, &[KeyCode::Enter, KeyCode::Char('a')]);
+        type_text(&mut state, "Review");
+        press(&mut state, &[KeyCode::Enter, KeyCode::Esc]);
+
+        assert_eq!(saved_descriptions(press(&mut state, &[KeyCode::Char('S')])), ["Work", "Review"]);
+    }
+
+    #[test]
+    fn inserting_below_the_selection_keeps_the_order() {
+        let mut state = editing(vec![line_item(1, "Design", 2.0), line_item(5, "Build", 8.0), line_item(2, "Launch", 1.0)]);
+        state.current_field = InvoiceField::LineItems;
+        // The first line item starts out selected
+        press(&mut state, &[KeyCode::Enter, KeyCode::Char('i')]);
+
+        assert_eq!(state.line_items_list_state.selected(), Some(1));
+        type_text(&mut state, "Review");
+        press(&mut state, &[KeyCode::Enter, KeyCode::Esc]);
+
+        let order: Vec<_> = state.line_items.iter().map(|item| (item.id, item.description.as_str())).collect();
+        assert_eq!(order, [(1, "Design"), (6, "Review"), (5, "Build"), (2, "Launch")]);
+        assert_eq!(state.line_items_list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn adding_still_appends_at_the_end() {
+        let mut state = editing(vec![line_item(1, "Design", 2.0), line_item(2, "Build", 8.0)]);
+        state.current_field = InvoiceField::LineItems;
+        press(&mut state, &[KeyCode::Enter, KeyCode::Down, KeyCode::Char('a')]);
+
+        assert_eq!(state.line_items.len(), 3);
+        assert_eq!(state.line_items[2].id, 3);
+        assert_eq!(state.line_items_list_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn only_a_draft_can_be_saved_without_line_items() {
+        let mut state = editing(Vec::new());
+
+        assert!(press(&mut state, &[KeyCode::Char('s')]).is_none());
+        assert!(state.show_error.is_some());
+        assert!(saved_descriptions(press(&mut state, &[KeyCode::Char('S')])).is_empty());
+    }
+
+    #[test]
+    fn a_blank_line_item_blocks_saving() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0), line_item(2, " ", 1.0)]);
+
+        assert!(press(&mut state, &[KeyCode::Char('s')]).is_none());
+        assert!(state.show_error.as_deref().is_some_and(|error| error.starts_with("Line item 2 has no description")));
+        assert!(press(&mut state, &[KeyCode::Char('S')]).is_none());
+    }
+
+    // Text drawn by the wizard, row by row
+    fn rendered(state: &mut InvoiceWizardState) -> String {
+        let mut terminal = tui::Terminal::new(tui::backend::TestBackend::new(120, 50)).unwrap();
+        terminal.draw(|frame| render_invoice_wizard(frame, state)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn a_euro_invoice_shows_euro_amounts() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]).with_currency("EUR".to_string());
+
+        assert!(rendered(&mut state).contains("Hourly Rate: €"));
+        assert_eq!(state.line_items_summary().last().unwrap(), "Total Amount: €200.00");
+    }
+
+    #[test]
+    fn dollars_are_the_default() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        assert!(rendered(&mut state).contains("Hourly Rate: $"));
+    }
+
+    #[test]
+    fn new_invoices_start_with_the_default_line_items() {
+        let defaults = ["Design".to_string(), "Meetings".to_string()];
+        let state = InvoiceWizardState::new(1, None, None, None, &config()).with_default_line_items(&defaults);
+
+        let items: Vec<_> = state.line_items.iter().map(|item| (item.id, item.description.as_str(), item.hours)).collect();
+        assert_eq!(items, [(1, "Design", 0.0), (2, "Meetings", 0.0)]);
+        assert_eq!(state.line_items_list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn edited_invoices_keep_their_own_line_items() {
+        let defaults = ["Design".to_string()];
+        let state = editing(vec![line_item(1, "Work", 2.0)]).with_default_line_items(&defaults);
+        let descriptions: Vec<_> = state.line_items.iter().map(|item| item.description.as_str()).collect();
+        assert_eq!(descriptions, ["Work"]);
+
+        // Even an existing invoice without line items doesn't get the defaults
+        let empty = editing(Vec::new()).with_default_line_items(&defaults);
+        assert!(empty.line_items.is_empty());
+    }
+
+    #[test]
+    fn copied_line_items_get_fresh_ids() {
+        let previous = vec![
+            InvoiceLineItem { invoice_id: 9, ..line_item(41, "Design", 2.0) },
+            InvoiceLineItem { invoice_id: 9, rate_override: Some(150.0), ..line_item(42, "Review", 1.0) },
+        ];
+        let mut state = InvoiceWizardState::new(1, None, None, None, &config())
+            .with_default_line_items(&["Hosting".to_string()])
+            .with_previous_line_items(previous);
+        state.current_field = InvoiceField::LineItems;
+        press(&mut state, &[KeyCode::Enter, KeyCode::Char('c')]);
+
+        let items: Vec<_> = state.line_items.iter().map(|item| (item.id, item.invoice_id, item.description.as_str(), item.hours)).collect();
+        assert_eq!(items, [(1, 0, "Hosting", 0.0), (2, 0, "Design", 2.0), (3, 0, "Review", 1.0)]);
+        assert_eq!(state.line_items[2].rate_override, Some(150.0));
+        assert_eq!(state.line_items_list_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn copying_without_a_previous_invoice_explains_why() {
+        let mut state = InvoiceWizardState::new(1, None, None, None, &config());
+        state.copy_previous_line_items();
+        assert!(state.line_items.is_empty());
+        assert!(state.show_error.is_some());
+    }
+
+    #[test]
+    fn undo_restores_the_value_from_before_the_edit() {
+        let mut state = editing(vec![line_item(1, "Work", 2.0)]);
+        state.notes = "Net 30".to_string();
+        state.current_field = InvoiceField::Notes;
+        press(&mut state, &[KeyCode::Enter]);
+        for _ in 0..6 {
+            press(&mut state, &[KeyCode::Backspace]);
+        }
+        type_text(&mut state, "Due now");
+
+        handle_event(&mut state, Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL))).unwrap();
+        assert_eq!(state.notes, "Net 30");
+        assert!(state.editing);
+    }
+
+    #[test]
+    fn saving_an_approved_invoice_keeps_it_approved() {
+        let approved = Invoice { status: "Approved".to_string(), ..invoice(7) };
+        let state = InvoiceWizardState::new(1, Some(7), Some(approved), Some(vec![line_item(1, "Work", 2.0)]), &config());
+        assert_eq!(state.to_invoice().status, "Approved");
+    }
+
+    #[test]
+    fn saving_an_edited_invoice_keeps_its_status_and_paid_date() {
+        let state = InvoiceWizardState::new(1, Some(7), Some(paid_invoice()), Some(vec![line_item(1, "Work", 2.0)]), &config());
+        let saved = state.to_invoice();
+        assert_eq!(saved.status, "Paid");
+        assert_eq!(saved.paid_date, Some(date(2024, 2, 1)));
+    }
+
+    #[test]
+    fn copies_of_an_invoice_start_as_unpaid_drafts() {
+        let state = InvoiceWizardState::new(1, None, Some(paid_invoice()), Some(vec![line_item(1, "Work", 2.0)]), &config());
+        let saved = state.to_invoice();
+        assert_eq!(saved.status, "Draft");
+        assert_eq!(saved.paid_date, None);
+    }
+}
@@ -1,53 +1,156 @@
 use anyhow::Result;
+use std::collections::HashMap;
+use chrono::{Local, NaiveDate, NaiveDateTime};
 use crossterm::event::{self, Event, KeyCode};
+use rust_decimal::Decimal;
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Cell, Row, Table, TableState, Paragraph},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Clear, List, ListItem, Row, Table, TableState, Paragraph},
     Frame,
 };
 
-use crate::models::Invoice;
+use crate::config::Config;
+use crate::models::{Invoice, InvoiceEmailLogEntry, InvoiceSnapshot};
+use crate::money;
+use crate::ui::components::confirm::render_confirmation;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::{footer_spans, truncate_to_width};
+use crate::ui::theme::status_color;
 use crate::ui::email_wizard::{
     self, EmailWizardState, EmailWizardAction, 
     render_email_wizard, handle_input as handle_email_input, send_invoice_email,
     generate_invoice_files
 };
+use crate::ui::time_entries_preview::{
+    self, TimeEntriesPreviewState, TimeEntriesPreviewAction, TimeEntryGroup, render_time_entries_preview
+};
+
+// Invoices shown per page of the table
+const INVOICES_PAGE_SIZE: i64 = 20;
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Select an invoice"),
+    ("PgUp/PgDn", "Previous or next page of invoices"),
+    ("N", "New invoice"),
+    ("T", "New invoice from unbilled time entries"),
+    ("L", "Clone the latest invoice"),
+    ("C", "Duplicate the selected invoice, dated today"),
+    ("E", "Edit the selected invoice"),
+    ("D", "Delete the selected invoice"),
+    ("A", "Approve the selected draft"),
+    ("P", "Record a payment"),
+    ("Shift+P", "Mark the invoice paid or unpaid"),
+    ("M", "Email the invoice"),
+    ("Shift+M", "Quick send the invoice to the client"),
+    ("H", "Sent history of the invoice, Enter opens a copy as it was sent"),
+    ("U", "Hide or show paid invoices"),
+    ("[ / ]", "Switch to the previous or next profile"),
+    ("?", "Show or hide this help"),
+    ("Esc / Q", "Back to projects"),
+];
+
+// One page of a project's invoices, with what the screen needs to know about the rest
+pub struct InvoicePage {
+    pub invoices: Vec<Invoice>,
+    pub number: i64, // Zero based page number
+    pub invoice_count: i64, // Invoices of the project across all pages
+    pub latest_invoice_id: Option<i32>,
+    pub project_totals: (f64, f64), // Billed and outstanding across all pages
+}
 
 // Represents the state of the invoice table screen
 pub struct InvoicesState {
     project_id: i32,
     project_name: String,
+    // Invoices of the current page
     invoices: Vec<Invoice>,
+    page: i64,
+    invoice_count: i64,
+    latest_invoice_id: Option<i32>,
+    project_totals: (f64, f64),
+    // Indices into invoices that are shown in the table, in display order
+    visible: Vec<usize>,
+    // Whether paid invoices are left out of the table
+    hide_paid: bool,
+    totals: HashMap<i32, f64>, // Invoice totals keyed by invoice id
+    paid: HashMap<i32, f64>, // Amounts paid so far keyed by invoice id, missing when nothing was paid
+    // Currency of the project's profile that amounts are shown in
+    currency: String,
     table_state: TableState,
     email_wizard_state: Option<EmailWizardState>,
+    // Sent history being viewed. Contains the invoice number, its sent snapshots and every email attempt
+    snapshots: Option<(i32, Vec<InvoiceSnapshot>, Vec<InvoiceEmailLogEntry>)>,
+    // Snapshot selected in the sent history, and whether its contents are open
+    snapshot_index: usize,
+    viewing_snapshot: bool,
+    time_entries_preview: Option<TimeEntriesPreviewState>,
+    // Draft about to be emailed, waiting for approval first. Contains the invoice id and
+    // whether it's a quick send
+    confirm_approval: Option<(i32, bool)>,
+    // Amount being typed for a payment received. Contains the invoice id and the amount
+    payment_prompt: Option<(i32, String)>,
+    // Invoice waiting for confirmation before it's deleted
+    confirm_delete: Option<i32>,
+    show_help: bool,
 }
 
 impl InvoicesState {
-    pub fn new(project_id: i32, project_name: String, invoices: Vec<Invoice>) -> Self {
+    pub fn new(
+        project_id: i32,
+        project_name: String,
+        page: InvoicePage,
+        totals: HashMap<i32, f64>,
+        paid: HashMap<i32, f64>,
+    ) -> Self {
         let mut table_state = TableState::default();
-        if !invoices.is_empty() {
+        if !page.invoices.is_empty() {
             table_state.select(Some(0));
         }
         
         Self {
             project_id,
             project_name,
-            invoices,
+            visible: (0..page.invoices.len()).collect(),
+            hide_paid: false,
+            invoices: page.invoices,
+            page: page.number,
+            invoice_count: page.invoice_count,
+            latest_invoice_id: page.latest_invoice_id,
+            project_totals: page.project_totals,
+            totals,
+            paid,
+            currency: money::DEFAULT_CURRENCY.to_string(),
             table_state,
             email_wizard_state: None,
+            snapshots: None,
+            snapshot_index: 0,
+            viewing_snapshot: false,
+            time_entries_preview: None,
+            confirm_approval: None,
+            payment_prompt: None,
+            confirm_delete: None,
+            show_help: false,
         }
     }
 
+    // Show amounts in the currency of the project's profile
+    pub fn with_currency(mut self, currency: String) -> Self {
+        self.currency = currency;
+        self
+    }
+
     pub fn next(&mut self) {
-        if self.invoices.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
 
         let i = match self.table_state.selected() {
             Some(i) => {
-                if i >= self.invoices.len() - 1 {
+                if i >= self.visible.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -59,14 +162,14 @@ impl InvoicesState {
     }
 
     pub fn previous(&mut self) {
-        if self.invoices.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
 
         let i = match self.table_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.invoices.len() - 1
+                    self.visible.len() - 1
                 } else {
                     i - 1
                 }
@@ -77,13 +180,105 @@ impl InvoicesState {
     }
     
     pub fn selected_invoice(&self) -> Option<&Invoice> {
-        self.table_state.selected().and_then(|i| self.invoices.get(i))
+        self.table_state
+            .selected()
+            .and_then(|i| self.visible.get(i))
+            .and_then(|&i| self.invoices.get(i))
+    }
+    
+    // Invoices shown in the table, in display order
+    fn visible_invoices(&self) -> impl Iterator<Item = &Invoice> {
+        self.visible.iter().map(|&i| &self.invoices[i])
+    }
+    
+    pub fn is_hiding_paid(&self) -> bool {
+        self.hide_paid
+    }
+    
+    // Show or hide paid invoices, keeping the selection on the same invoice when it's still shown
+    pub fn toggle_hide_paid(&mut self) {
+        self.hide_paid = !self.hide_paid;
+        self.refresh_visible();
+    }
+    
+    fn refresh_visible(&mut self) {
+        let previous = self.selected_invoice_id();
+        
+        self.visible = self.invoices
+            .iter()
+            .enumerate()
+            .filter(|(_, invoice)| !(self.hide_paid && invoice.status == "Paid"))
+            .map(|(i, _)| i)
+            .collect();
+        
+        let selected = self.visible
+            .iter()
+            .position(|&i| Some(self.invoices[i].id) == previous)
+            .or(if self.visible.is_empty() { None } else { Some(0) });
+        self.table_state.select(selected);
     }
     
     pub fn selected_invoice_id(&self) -> Option<i32> {
         self.selected_invoice().map(|i| i.id)
     }
     
+    // Most recently submitted invoice of the project, whichever page is shown
+    pub fn latest_invoice_id(&self) -> Option<i32> {
+        self.latest_invoice_id
+    }
+    
+    // Replace the table with another page of invoices
+    fn show_page(&mut self, page: InvoicePage) {
+        self.invoices = page.invoices;
+        self.page = page.number;
+        self.invoice_count = page.invoice_count;
+        self.latest_invoice_id = page.latest_invoice_id;
+        self.project_totals = page.project_totals;
+        self.refresh_visible();
+    }
+    
+    fn has_next_page(&self) -> bool {
+        (self.page + 1) * INVOICES_PAGE_SIZE < self.invoice_count
+    }
+    
+    // Range of invoices on the current page, e.g. "Showing 21-40 of 57"
+    fn page_range(&self) -> String {
+        if self.invoices.is_empty() {
+            return format!("Showing 0 of {}", self.invoice_count);
+        }
+        let first = self.page * INVOICES_PAGE_SIZE + 1;
+        format!("Showing {}-{} of {}", first, first + self.invoices.len() as i64 - 1, self.invoice_count)
+    }
+    
+    pub fn invoice_total(&self, invoice_id: i32) -> f64 {
+        self.totals.get(&invoice_id).copied().unwrap_or(0.0)
+    }
+    
+    pub fn amount_paid(&self, invoice_id: i32) -> f64 {
+        self.paid.get(&invoice_id).copied().unwrap_or(0.0)
+    }
+    
+    // Total minus the payments received so far
+    pub fn balance_due(&self, invoice_id: i32) -> Decimal {
+        money::to_decimal(self.invoice_total(invoice_id)) - money::to_decimal(self.amount_paid(invoice_id))
+    }
+    
+    pub fn add_payment(&mut self, invoice_id: i32, amount: f64) {
+        *self.paid.entry(invoice_id).or_insert(0.0) += amount;
+    }
+    
+    // Count and summed total of the invoices shown in the table
+    pub fn displayed_summary(&self) -> (usize, f64) {
+        let total = self.visible_invoices().map(|i| self.invoice_total(i.id)).sum();
+        (self.visible.len(), total)
+    }
+    
+    // Billed and outstanding totals of the whole project, across all pages and whether or
+    // not paid invoices are hidden
+    pub fn project_totals(&self) -> (f64, f64) {
+        self.project_totals
+    }
+    
     pub fn project_id(&self) -> i32 {
         self.project_id
     }
@@ -92,8 +287,13 @@ impl InvoicesState {
         &self.project_name
     }
     
-    pub fn start_email_wizard(&mut self, invoice_id: i32) {
-        self.email_wizard_state = Some(EmailWizardState::new(invoice_id));
+    pub fn start_email_wizard(&mut self, invoice_id: i32, config: &Config) {
+        self.email_wizard_state = Some(
+            EmailWizardState::new(invoice_id)
+                .with_inline_html(config.email_inline_html)
+                .with_allowed_domains(config.allowed_email_domains.clone())
+                .with_line_item_sort(config.line_item_sort)
+        );
     }
     
     pub fn close_email_wizard(&mut self) {
@@ -112,6 +312,36 @@ impl InvoicesState {
         Ok(())
     }
     
+    pub fn show_snapshots(&mut self, invoice_number: i32, snapshots: Vec<InvoiceSnapshot>, email_log: Vec<InvoiceEmailLogEntry>) {
+        self.snapshots = Some((invoice_number, snapshots, email_log));
+        self.snapshot_index = 0;
+        self.viewing_snapshot = false;
+    }
+    
+    pub fn set_status(&mut self, invoice_id: i32, status: &str) {
+        if let Some(invoice) = self.invoices.iter_mut().find(|i| i.id == invoice_id) {
+            invoice.status = status.to_string();
+        }
+        
+        // An invoice that just got paid drops out of the table when paid ones are hidden
+        if self.hide_paid {
+            self.refresh_visible();
+        }
+    }
+    
+    pub fn set_paid_date(&mut self, invoice_id: i32, paid_date: Option<NaiveDate>) {
+        if let Some(invoice) = self.invoices.iter_mut().find(|i| i.id == invoice_id) {
+            invoice.paid_date = paid_date;
+        }
+    }
+    
+    pub fn set_emailed(&mut self, invoice_id: i32, emailed_at: NaiveDateTime, recipient: String) {
+        if let Some(invoice) = self.invoices.iter_mut().find(|i| i.id == invoice_id) {
+            invoice.last_emailed_at = Some(emailed_at);
+            invoice.last_emailed_to = Some(recipient);
+        }
+    }
+    
     pub fn is_in_email_wizard(&self) -> bool {
         self.email_wizard_state.is_some()
     }
@@ -121,23 +351,85 @@ pub enum InvoiceAction {
     Back,
     NewInvoice(i32), // Contains project_id
     EditInvoice(i32), // Contains invoice_id
+    DeleteInvoice(i32), // Contains invoice_id
     EmailInvoice(i32), // Contains invoice_id
+    CloneLatestInvoice(i32), // Contains invoice_id of the project's most recent invoice
+    DuplicateInvoice(i32), // Contains invoice_id of the invoice to copy
+    InvoiceFromTimeEntries(i32, Vec<TimeEntryGroup>), // Contains project_id and the grouped time entries
+    PreviousProfile,
+    NextProfile,
 }
 
 // DB operations for invoices
-pub async fn load_invoices_by_project(db: &crate::db::Database, project_id: i32) -> Result<Vec<Invoice>> {
-    // Use the database layer instead of direct access
-    db.load_invoices_by_project(project_id).await
+pub async fn load_invoice_page(db: &crate::db::Database, project_id: i32, number: i64) -> Result<InvoicePage> {
+    let (invoices, invoice_count) = db
+        .load_invoices_by_project_paged(project_id, INVOICES_PAGE_SIZE, number * INVOICES_PAGE_SIZE)
+        .await?;
+    Ok(InvoicePage {
+        invoices,
+        number,
+        invoice_count,
+        latest_invoice_id: db.get_latest_invoice_id(project_id).await?,
+        project_totals: db.get_project_invoice_totals(project_id).await?,
+    })
+}
+
+pub async fn load_invoice_totals(db: &crate::db::Database, project_id: i32) -> Result<HashMap<i32, f64>> {
+    Ok(db.get_invoice_totals(project_id).await?)
+}
+
+pub async fn load_invoice_paid_amounts(db: &crate::db::Database, project_id: i32) -> Result<HashMap<i32, f64>> {
+    Ok(db.get_invoice_paid_amounts(project_id).await?)
 }
 
 pub async fn delete_invoice(db: &crate::db::Database, id: i32) -> Result<()> {
     // Use the database layer instead of direct access
-    db.delete_invoice(id).await
+    Ok(db.delete_invoice(id).await?)
 }
 
 pub async fn get_invoice_with_line_items(db: &crate::db::Database, id: i32) -> Result<(Invoice, Vec<crate::models::InvoiceLineItem>)> {
     // Use the database layer instead of direct access
-    db.get_invoice_with_line_items(id).await
+    Ok(db.get_invoice_with_line_items(id).await?)
+}
+
+// Status of the invoice, with the date it was paid once it is
+fn status_cell(invoice: &Invoice) -> Cell<'static> {
+    let text = match invoice.paid_date.filter(|_| invoice.status == "Paid") {
+        Some(paid_date) => format!("Paid {}", paid_date.format("%Y-%m-%d")),
+        None => invoice.status.clone(),
+    };
+    Cell::from(text).style(Style::default().fg(status_color(&invoice.status)))
+}
+
+// Date the invoice was last emailed, so invoices that already went out stand out
+fn sent_cell(invoice: &Invoice) -> Cell<'static> {
+    match invoice.last_emailed_at {
+        Some(at) => Cell::from(at.format("%Y-%m-%d").to_string()).style(Style::default().fg(Color::Green)),
+        None => Cell::from("-").style(Style::default().fg(Color::DarkGray)),
+    }
+}
+
+// How long until the invoice is due, green while there's time left and red once it's
+// overdue. Paid and cancelled invoices aren't due anymore.
+fn due_in_cell(invoice: &Invoice, today: NaiveDate) -> Cell<'static> {
+    let (text, color) = due_in(invoice, today);
+    Cell::from(text).style(Style::default().fg(color))
+}
+
+fn due_in(invoice: &Invoice, today: NaiveDate) -> (String, Color) {
+    match invoice.status.as_str() {
+        "Paid" => return ("paid".to_string(), Color::Green),
+        "Cancelled" => return ("-".to_string(), Color::Reset),
+        _ => {}
+    }
+    
+    match invoice.days_until_due(today) {
+        0 => ("due today".to_string(), Color::Yellow),
+        1 => ("due tomorrow".to_string(), Color::Green),
+        -1 => ("overdue 1 day".to_string(), Color::Red),
+        days if days > 0 => (format!("due in {} days", days), Color::Green),
+        days => (format!("overdue {} days", -days), Color::Red),
+    }
 }
 
 pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesState) {
@@ -147,8 +439,8 @@ pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesSta
     
     // If in email wizard mode, render the email wizard instead
     if let Some(email_state) = &mut state.email_wizard_state {
-        // Don't render the email wizard if it's being dismissed
-        if !email_state.is_dismissing() {
+        // Don't render the email wizard if it's being dismissed, quick send only needs a popup over the table
+        if !email_state.is_dismissing() && !email_state.is_quick_send() {
             render_email_wizard(frame, email_state);
             return;
         }
@@ -162,12 +454,13 @@ pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesSta
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(1),
+            Constraint::Length(1),
             Constraint::Length(3),
         ].as_ref())
         .split(size);
 
     // Define the header cells
-    let header_cells = ["Number", "Submit Date", "Due Date", "Status", "Actions"]
+    let header_cells = ["Number", "Submit Date", "Due Date", "Due In", "Status", "Sent", "Paid", "Balance", "Actions"]
         .iter()
         .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
     let header = Row::new(header_cells)
@@ -176,7 +469,8 @@ pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesSta
         .bottom_margin(1);
 
     // Create the rows with data and action buttons
-    let rows = state.invoices.iter().map(|invoice| {
+    let today = Local::now().date_naive();
+    let rows = state.visible.iter().map(|&i| &state.invoices[i]).map(|invoice| {
         let submit_date = invoice.submit_date.format("%Y-%m-%d").to_string();
         let due_date = invoice.due_date.format("%Y-%m-%d").to_string();
         
@@ -184,7 +478,11 @@ pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesSta
             Cell::from(invoice.number.to_string()),
             Cell::from(submit_date),
             Cell::from(due_date),
-            Cell::from(invoice.status.as_str()),
+            due_in_cell(invoice, today),
+            status_cell(invoice),
+            sent_cell(invoice),
+            Cell::from(money::format_money_in(state.amount_paid(invoice.id), &state.currency)),
+            Cell::from(money::format_money_in(state.balance_due(invoice.id), &state.currency)),
             Cell::from("Edit | Email"),
         ];
         
@@ -192,7 +490,12 @@ pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesSta
     });
 
     // Create the table
-    let title = format!("Invoices for {}", state.project_name());
+    let title_width = chunks[0].width.saturating_sub(2) as usize;
+    let hiding_paid = if state.is_hiding_paid() { " (hiding paid)" } else { "" };
+    let title = truncate_to_width(
+        &format!("Invoices for {} ({}){}", state.project_name(), state.page_range(), hiding_paid),
+        title_width,
+    );
     let table = Table::new(rows)
         .header(header)
         .block(Block::default().title(title).borders(Borders::ALL))
@@ -203,31 +506,272 @@ pub fn render_invoices<B: Backend>(frame: &mut Frame<B>, state: &mut InvoicesSta
                 .add_modifier(Modifier::BOLD),
         )
         .widths(&[
+            Constraint::Percentage(7),
+            Constraint::Percentage(11),
+            Constraint::Percentage(11),
+            Constraint::Percentage(12),
             Constraint::Percentage(15),
-            Constraint::Percentage(20),
-            Constraint::Percentage(20),
-            Constraint::Percentage(20),
-            Constraint::Percentage(25),
+            Constraint::Percentage(11),
+            Constraint::Percentage(10),
+            Constraint::Percentage(10),
+            Constraint::Percentage(13),
         ]);
 
     frame.render_stateful_widget(table, chunks[0], &mut state.table_state);
 
+    // Render the summary of the displayed invoices
+    let (count, total) = state.displayed_summary();
+    let emailed = match state.selected_invoice() {
+        Some(Invoice { last_emailed_at: Some(at), last_emailed_to, .. }) => format!(
+            " | Selected: emailed {} to {}",
+            at.format("%Y-%m-%d %H:%M"),
+            last_emailed_to.as_deref().unwrap_or("unknown recipient"),
+        ),
+        Some(_) => " | Selected: not emailed yet".to_string(),
+        None => String::new(),
+    };
+    let (billed, outstanding) = state.project_totals();
+    let summary = Paragraph::new(format!(
+        " {} invoice(s) | Total: {} | Billed: {} | Outstanding: {}{}",
+        count,
+        money::format_money_in(total, &state.currency),
+        money::format_money_in(billed, &state.currency),
+        money::format_money_in(outstanding, &state.currency),
+        emailed,
+    ))
+        .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+    frame.render_widget(summary, chunks[1]);
+
     // Create and render the buttons
     let selected = state.selected_invoice().is_some();
+    let paid_toggle = if state.is_hiding_paid() { "<U> Show Paid" } else { "<U> Hide Paid" };
     let buttons_text = if selected {
-        format!("<N> New Invoice | <E> Edit Invoice | <M> Email Invoice | <Esc> Back")
+        format!("<N> New Invoice | <L> Clone Latest | <C> Duplicate | <E> Edit Invoice | <D> Delete | <A> Approve | <P> Record Payment | <Shift+P> Toggle Paid | <M> Email Invoice | <Shift+M> Quick Send | <H> Sent History | <T> From Time Entries | {} | <PgUp/PgDn> Page | <[/]> Switch Profile | <Esc> Back", paid_toggle)
     } else {
-        format!("<N> New Invoice | <Esc> Back")
+        format!("<N> New Invoice | <T> From Time Entries | {} | <PgUp/PgDn> Page | <[/]> Switch Profile | <Esc> Back", paid_toggle)
     };
 
-    let buttons = Paragraph::new(buttons_text)
+    let buttons = Paragraph::new(footer_spans(&buttons_text, chunks[2].width as usize))
         .block(Block::default().borders(Borders::TOP))
         .style(Style::default().fg(Color::White));
 
-    frame.render_widget(buttons, chunks[1]);
+    frame.render_widget(buttons, chunks[2]);
+    
+    // Render the sent snapshots popup if needed
+    if let Some((number, snapshots, email_log)) = &state.snapshots {
+        match snapshots.get(state.snapshot_index).filter(|_| state.viewing_snapshot) {
+            Some(snapshot) => render_snapshot(frame, size, *number, snapshot, &state.currency),
+            None => render_snapshots(frame, size, *number, snapshots, email_log, state.snapshot_index, &state.currency),
+        }
+    }
+    
+    // Render the time entries preview if needed
+    if let Some(preview) = &mut state.time_entries_preview {
+        render_time_entries_preview(frame, size, preview);
+    }
+    
+    // Render the payment prompt if needed
+    if let Some((id, amount)) = &state.payment_prompt {
+        render_payment_prompt(frame, size, state.balance_due(*id), amount, &state.currency);
+    }
+    
+    // Render the delete confirmation if needed
+    if let Some(id) = state.confirm_delete {
+        let number = state.invoices.iter().find(|i| i.id == id).map_or(0, |i| i.number);
+        render_confirmation(
+            frame,
+            "Confirm Delete",
+            &format!("Delete invoice #{} with its line items? This can't be undone.", number),
+        );
+    }
+    
+    // Render the approval prompt for emailing a draft if needed
+    if let Some((id, _)) = state.confirm_approval {
+        let number = state.invoices.iter().find(|i| i.id == id).map_or(0, |i| i.number);
+        render_confirmation(
+            frame,
+            "Approve Invoice",
+            &format!("Invoice #{} is still a draft. Approve it and continue to email it?", number),
+        );
+    }
+    
+    // Render the quick send confirmation if needed
+    if let Some(email_state) = state.email_wizard_state.as_ref().filter(|s| s.is_quick_send()) {
+        let number = email_state.loaded_invoice().map_or(0, |(invoice, _)| invoice.number);
+        render_confirmation(
+            frame,
+            "Quick Send",
+            &format!("Send invoice #{} to {} now?", number, email_state.recipient_email()),
+        );
+    }
+    
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
 }
 
-pub async fn handle_input(db: &crate::db::Database, state: &mut InvoicesState) -> Result<Option<InvoiceAction>> {
+fn render_snapshots<B: Backend>(
+    frame: &mut Frame<B>,
+    size: Rect,
+    invoice_number: i32,
+    snapshots: &[InvoiceSnapshot],
+    email_log: &[InvoiceEmailLogEntry],
+    selected: usize,
+    currency: &str,
+) {
+    let popup_area = centered_rect(60, 50, size);
+    
+    let mut items: Vec<ListItem> = email_log
+        .iter()
+        .map(|entry| {
+            let (outcome, color) = if entry.success { ("Emailed", Color::Green) } else { ("Failed", Color::Red) };
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:<8}", outcome), Style::default().fg(color)),
+                Span::raw(format!("{} to {}", entry.sent_at.format("%Y-%m-%d %H:%M"), entry.recipient)),
+            ]))
+        })
+        .collect();
+    
+    if !snapshots.is_empty() {
+        if !items.is_empty() {
+            items.push(ListItem::new(""));
+        }
+        items.extend(snapshots.iter().enumerate().map(|(i, snapshot)| {
+            let item = ListItem::new(format!(
+                "Sent {} - Total {}",
+                snapshot.created_at.format("%Y-%m-%d %H:%M UTC"),
+                money::format_money_in(snapshot.total, currency),
+            ));
+            if i == selected {
+                item.style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                item
+            }
+        }));
+    }
+    
+    if items.is_empty() {
+        items.push(ListItem::new("This invoice has not been sent yet"));
+    }
+    
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(if snapshots.is_empty() {
+                    format!("Sent History for Invoice #{} (any key to close)", invoice_number)
+                } else {
+                    format!("Sent History for Invoice #{} (Up/Down select, Enter view, any other key to close)", invoice_number)
+                })
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(list, popup_area);
+}
+
+// The stored copy of an invoice exactly as it was sent
+fn render_snapshot<B: Backend>(frame: &mut Frame<B>, size: Rect, invoice_number: i32, snapshot: &InvoiceSnapshot, currency: &str) {
+    let popup_area = centered_rect(60, 60, size);
+    
+    let lines: Vec<Spans> = snapshot.lines(currency).into_iter().map(Spans::from).collect();
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(format!(
+                    "Invoice #{} as Sent {} (any key to go back)",
+                    invoice_number,
+                    snapshot.created_at.format("%Y-%m-%d %H:%M UTC"),
+                ))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_payment_prompt<B: Backend>(frame: &mut Frame<B>, size: Rect, balance_due: Decimal, amount: &str, currency: &str) {
+    let popup_area = centered_rect(50, 20, size);
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(format!("Balance due: {}", money::format_money_in(balance_due, currency))),
+        Spans::from(vec![
+            Span::raw("Amount received: "),
+            Span::styled(format!("{}|", amount), Style::default().fg(Color::Yellow)),
+        ]),
+        Spans::from(""),
+        Spans::from("<Enter> Record | <Esc> Cancel"),
+    ])
+    .block(Block::default().title("Record Payment").borders(Borders::ALL))
+    .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+// Helper function to create a centered rect
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+// Start the email wizard for an invoice, pre-filled from its project and client
+async fn open_email_wizard(db: &crate::db::Database, config: &Config, state: &mut InvoicesState, id: i32) -> Result<()> {
+    // Initialize the email wizard and load invoice data
+    state.start_email_wizard(id, config);
+    
+    if let Some(email_state) = &mut state.email_wizard_state {
+        // Load the invoice and line items
+        let (invoice, line_items) = get_invoice_with_line_items(db, id).await?;
+        
+        // Get the project to access its name and client
+        let project = db.get_project(invoice.project_id).await?;
+        
+        // Get the client to access email
+        let client = db.get_client(project.client_id).await?;
+        
+        // Amounts in the message are in the currency of the client's profile
+        let currency = db.get_currency_by_project(project.id).await?;
+        
+        // Now load invoice with project name, client email and currency
+        email_state.load_invoice(invoice, line_items, project.name, client.email, currency);
+        
+        // Generate invoice files on-demand
+        generate_invoice_files(db, config, email_state).await?;
+    }
+    
+    Ok(())
+}
+
+// Open the email wizard, or the quick send confirmation, for the invoice
+async fn start_email(db: &crate::db::Database, config: &Config, state: &mut InvoicesState, id: i32, quick_send: bool) -> Result<()> {
+    open_email_wizard(db, config, state, id).await?;
+    if let Some(email_state) = state.email_wizard_state.as_mut().filter(|_| quick_send) {
+        email_state.start_quick_send(config.quick_send_message_file.as_deref());
+    }
+    
+    Ok(())
+}
+
+pub async fn handle_input(db: &crate::db::Database, config: &Config, state: &mut InvoicesState) -> Result<Option<InvoiceAction>> {
     // If in email wizard mode, handle email input instead
     if state.is_in_email_wizard() {
         if let Some(email_state) = &mut state.email_wizard_state {
@@ -249,15 +793,33 @@ pub async fn handle_input(db: &crate::db::Database, state: &mut InvoicesState) -
                 }
                 Some(EmailWizardAction::Send) => {
                     // Send the email
-                    send_invoice_email(email_state).await?;
+                    send_invoice_email(db, config, email_state).await?;
                     
                     // Check if we've successfully sent the email - we'll need to add a method to check this
                     if email_state.has_success_message() {
+                        // Keep an immutable record of exactly what the client received
+                        let mut sent_invoice = None;
+                        let emailed_at = Local::now().naive_local();
+                        let recipient = email_state.all_recipients();
+                        if let Some((invoice, line_items)) = email_state.loaded_invoice() {
+                            db.save_invoice_snapshot(invoice, line_items).await?;
+                            let status = invoice.status_after_sending().to_string();
+                            if status != invoice.status {
+                                db.set_invoice_status(invoice.id, &status).await?;
+                            }
+                            db.record_invoice_emailed(invoice.id, &recipient, emailed_at).await?;
+                            sent_invoice = Some((invoice.id, status));
+                        }
+                        
                         // Add a short delay to let user see the success message
                         std::thread::sleep(std::time::Duration::from_millis(1500));
                         // Then dismiss and immediately close
                         email_state.dismiss();
                         state.close_email_wizard();
+                        if let Some((invoice_id, status)) = sent_invoice {
+                            state.set_status(invoice_id, &status);
+                            state.set_emailed(invoice_id, emailed_at, recipient);
+                        }
                         return Ok(None);
                     }
                 }
@@ -268,10 +830,101 @@ pub async fn handle_input(db: &crate::db::Database, state: &mut InvoicesState) -
     }
     
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+        
+        // Browsing the sent history, any other key closes it
+        if let Some((_, snapshots, _)) = &state.snapshots {
+            let count = snapshots.len();
+            match key.code {
+                _ if state.viewing_snapshot => state.viewing_snapshot = false,
+                KeyCode::Up if count > 0 => state.snapshot_index = state.snapshot_index.saturating_sub(1),
+                KeyCode::Down if count > 0 => state.snapshot_index = (state.snapshot_index + 1).min(count - 1),
+                KeyCode::Enter if count > 0 => state.viewing_snapshot = true,
+                _ => state.snapshots = None,
+            }
+            return Ok(None);
+        }
+        
+        // Typing the amount of a payment received
+        if let Some((id, amount)) = &mut state.payment_prompt {
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' || c == ',' => amount.push(c),
+                KeyCode::Backspace => {
+                    amount.pop();
+                }
+                KeyCode::Esc => state.payment_prompt = None,
+                KeyCode::Enter => {
+                    if let Some(paid) = money::parse_number(amount).filter(|paid| *paid > 0.0) {
+                        let id = *id;
+                        state.payment_prompt = None;
+                        db.record_payment(id, paid, Local::now().date_naive()).await?;
+                        state.add_payment(id, paid);
+                        
+                        // Nothing left to pay
+                        if state.balance_due(id) <= Decimal::ZERO {
+                            let today = Local::now().date_naive();
+                            db.mark_invoice_paid(id, today).await?;
+                            state.set_paid_date(id, Some(today));
+                            state.set_status(id, "Paid");
+                        }
+                        state.project_totals = db.get_project_invoice_totals(state.project_id).await?;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(None);
+        }
+        
+        // Delete on 'y', any other key keeps the invoice
+        if let Some(id) = state.confirm_delete.take() {
+            if key.code == KeyCode::Char('y') {
+                return Ok(Some(InvoiceAction::DeleteInvoice(id)));
+            }
+            return Ok(None);
+        }
+        
+        // Approve and email on 'y', any other key leaves the draft as it is
+        if let Some((id, quick_send)) = state.confirm_approval.take() {
+            if key.code == KeyCode::Char('y') {
+                db.approve_invoice(id, config.approver_name.as_deref()).await?;
+                state.set_status(id, "Approved");
+                start_email(db, config, state, id, quick_send).await?;
+            }
+            return Ok(None);
+        }
+        
+        if let Some(preview) = &mut state.time_entries_preview {
+            match time_entries_preview::handle_input(preview, key.code) {
+                Some(TimeEntriesPreviewAction::Cancel) => {
+                    state.time_entries_preview = None;
+                }
+                Some(TimeEntriesPreviewAction::CreateInvoice(groups)) => {
+                    state.time_entries_preview = None;
+                    return Ok(Some(InvoiceAction::InvoiceFromTimeEntries(state.project_id(), groups)));
+                }
+                None => {}
+            }
+            return Ok(None);
+        }
+        
         match key.code {
+            KeyCode::Char('?') => {
+                state.show_help = true;
+            }
             KeyCode::Char('q') | KeyCode::Esc => {
                 return Ok(Some(InvoiceAction::Back));
             }
+            KeyCode::Char('[') => {
+                return Ok(Some(InvoiceAction::PreviousProfile));
+            }
+            KeyCode::Char(']') => {
+                return Ok(Some(InvoiceAction::NextProfile));
+            }
             KeyCode::Char('n') => {
                 return Ok(Some(InvoiceAction::NewInvoice(state.project_id())));
             }
@@ -280,29 +933,79 @@ pub async fn handle_input(db: &crate::db::Database, state: &mut InvoicesState) -
                     return Ok(Some(InvoiceAction::EditInvoice(id)));
                 }
             }
-            KeyCode::Char('m') => {
+            KeyCode::Char('d') => {
+                state.confirm_delete = state.selected_invoice_id();
+            }
+            KeyCode::Char('u') => {
+                state.toggle_hide_paid();
+            }
+            KeyCode::Char('t') => {
+                let entries = db.get_unbilled_time_entries(state.project_id()).await?;
+                state.time_entries_preview = Some(TimeEntriesPreviewState::new(&entries, config.hours_step));
+            }
+            KeyCode::Char('h') => {
+                if let Some(invoice) = state.selected_invoice() {
+                    let number = invoice.number;
+                    let snapshots = db.get_invoice_snapshots(invoice.id).await?;
+                    let email_log = db.get_invoice_email_log(invoice.id).await?;
+                    state.show_snapshots(number, snapshots, email_log);
+                }
+            }
+            KeyCode::Char('l') => {
+                if let Some(id) = state.latest_invoice_id() {
+                    return Ok(Some(InvoiceAction::CloneLatestInvoice(id)));
+                }
+            }
+            KeyCode::Char('c') => {
                 if let Some(id) = state.selected_invoice_id() {
-                    // Initialize the email wizard and load invoice data
-                    state.start_email_wizard(id);
-                    
-                    if let Some(email_state) = &mut state.email_wizard_state {
-                        // Load the invoice and line items
-                        let (invoice, line_items) = get_invoice_with_line_items(db, id).await?;
-                        
-                        // Get the project to access its name and client
-                        let project = db.get_project(invoice.project_id).await?;
-                        
-                        // Get the client to access email
-                        let client = db.get_client(project.client_id).await?;
-                        
-                        // Now load invoice with project name and client email
-                        email_state.load_invoice(invoice, line_items, project.name, client.email);
-                        
-                        // Generate invoice files on-demand
-                        generate_invoice_files(db, email_state).await?;
+                    return Ok(Some(InvoiceAction::DuplicateInvoice(id)));
+                }
+            }
+            KeyCode::Char('a') => {
+                if let Some(invoice) = state.selected_invoice().filter(|i| i.status == "Draft") {
+                    let id = invoice.id;
+                    db.approve_invoice(id, config.approver_name.as_deref()).await?;
+                    state.set_status(id, "Approved");
+                }
+            }
+            KeyCode::Char('p') => {
+                if let Some(invoice) = state.selected_invoice().filter(|i| i.awaits_payment()) {
+                    state.payment_prompt = Some((invoice.id, String::new()));
+                }
+            }
+            KeyCode::Char('P') => {
+                // Drafts and cancelled invoices aren't waiting on a payment
+                match state.selected_invoice() {
+                    Some(invoice) if invoice.status == "Paid" => {
+                        let id = invoice.id;
+                        let status = db.mark_invoice_unpaid(id).await?;
+                        state.set_paid_date(id, None);
+                        state.set_status(id, &status);
                     }
-                    
-                    return Ok(None);
+                    Some(invoice) if invoice.awaits_payment() => {
+                        let id = invoice.id;
+                        let today = Local::now().date_naive();
+                        db.mark_invoice_paid(id, today).await?;
+                        state.set_paid_date(id, Some(today));
+                        state.set_status(id, "Paid");
+                    }
+                    _ => return Ok(None),
+                }
+                state.project_totals = db.get_project_invoice_totals(state.project_id).await?;
+            }
+            KeyCode::Char(c @ ('m' | 'M')) => {
+                let quick_send = c == 'M';
+                match state.selected_invoice() {
+                    // Drafts need approving before they can be emailed
+                    Some(invoice) if invoice.status == "Draft" => {
+                        state.confirm_approval = Some((invoice.id, quick_send));
+                    }
+                    // Cancelled invoices are never sent
+                    Some(invoice) if invoice.can_be_emailed() => {
+                        let id = invoice.id;
+                        start_email(db, config, state, id, quick_send).await?;
+                    }
+                    _ => {}
                 }
             }
             KeyCode::Down => {
@@ -311,8 +1014,109 @@ pub async fn handle_input(db: &crate::db::Database, state: &mut InvoicesState) -
             KeyCode::Up => {
                 state.previous();
             }
+            KeyCode::PageDown if state.has_next_page() => {
+                let page = load_invoice_page(db, state.project_id, state.page + 1).await?;
+                state.show_page(page);
+            }
+            KeyCode::PageUp if state.page > 0 => {
+                let page = load_invoice_page(db, state.project_id, state.page - 1).await?;
+                state.show_page(page);
+            }
             _ => {}
         }
     }
     Ok(None)
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{date, invoice};
+
+    fn with_status(id: i32, status: &str) -> Invoice {
+        Invoice { status: status.to_string(), ..invoice(id) }
+    }
+
+    // A single page of the given invoices, with each total keyed by invoice id
+    fn state_with(invoices: Vec<Invoice>, totals: &[(i32, f64)]) -> InvoicesState {
+        let page = InvoicePage {
+            invoice_count: invoices.len() as i64,
+            invoices,
+            number: 0,
+            latest_invoice_id: None,
+            project_totals: (0.0, 0.0),
+        };
+        InvoicesState::new(1, "Website".to_string(), page, totals.iter().copied().collect(), HashMap::new())
+    }
+
+    #[test]
+    fn displayed_summary_sums_the_filtered_invoices() {
+        let mut state = state_with(
+            vec![with_status(1, "Sent"), with_status(2, "Paid"), with_status(3, "Draft")],
+            &[(1, 100.25), (2, 40.0), (3, 9.75)],
+        );
+        assert_eq!(state.displayed_summary(), (3, 150.0));
+
+        state.toggle_hide_paid();
+        let shown: f64 = state.visible_invoices().map(|i| state.invoice_total(i.id)).sum();
+        assert_eq!(state.displayed_summary(), (2, shown));
+        assert_eq!(shown, 110.0);
+    }
+
+    #[test]
+    fn toggling_hide_paid_hides_paid_invoices_and_restores_them() {
+        let mut state = state_with(vec![with_status(1, "Sent"), with_status(2, "Paid"), with_status(3, "Draft")], &[]);
+        let shown = |state: &InvoicesState| state.visible_invoices().map(|i| i.id).collect::<Vec<_>>();
+        assert!(!state.is_hiding_paid());
+        assert_eq!(shown(&state), [1, 2, 3]);
+
+        state.table_state.select(Some(2));
+        state.toggle_hide_paid();
+        assert!(state.is_hiding_paid());
+        assert_eq!(shown(&state), [1, 3]);
+        assert_eq!(state.selected_invoice_id(), Some(3));
+
+        state.toggle_hide_paid();
+        assert!(!state.is_hiding_paid());
+        assert_eq!(shown(&state), [1, 2, 3]);
+        assert_eq!(state.selected_invoice_id(), Some(3));
+    }
+
+    #[test]
+    fn paying_an_invoice_drops_it_while_paid_ones_are_hidden() {
+        let mut state = state_with(vec![with_status(1, "Sent"), with_status(2, "Sent")], &[]);
+        state.toggle_hide_paid();
+
+        state.set_status(1, "Paid");
+        assert_eq!(state.visible_invoices().map(|i| i.id).collect::<Vec<_>>(), [2]);
+        assert_eq!(state.selected_invoice_id(), Some(2));
+    }
+
+    #[test]
+    fn due_dates_read_relative_to_today() {
+        // The fixture is due 2024-02-14
+        let due = |y, m, d| due_in(&with_status(1, "Sent"), date(y, m, d));
+        assert_eq!(due(2024, 2, 11), ("due in 3 days".to_string(), Color::Green));
+        assert_eq!(due(2024, 2, 13), ("due tomorrow".to_string(), Color::Green));
+        assert_eq!(due(2024, 2, 14), ("due today".to_string(), Color::Yellow));
+        assert_eq!(due(2024, 2, 15), ("overdue 1 day".to_string(), Color::Red));
+        assert_eq!(due(2024, 2, 19), ("overdue 5 days".to_string(), Color::Red));
+    }
+
+    #[test]
+    fn paid_and_cancelled_invoices_arent_due() {
+        let today = date(2024, 3, 1);
+        assert_eq!(due_in(&with_status(1, "Paid"), today).0, "paid");
+        assert_eq!(due_in(&with_status(1, "Cancelled"), today).0, "-");
+    }
+
+    #[test]
+    fn partial_payments_leave_the_rest_as_the_balance() {
+        let mut state = state_with(vec![with_status(1, "Sent"), with_status(2, "Sent")], &[(1, 300.0), (2, 80.0)]);
+        state.add_payment(1, 120.5);
+        state.add_payment(1, 79.5);
+
+        assert_eq!(state.amount_paid(1), 200.0);
+        assert_eq!(state.balance_due(1), Decimal::new(10000, 2));
+        assert_eq!(state.balance_due(2), Decimal::new(8000, 2));
+    }
+}
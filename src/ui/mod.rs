@@ -4,7 +4,11 @@ pub mod projects;
 pub mod invoices;
 pub mod invoice_wizard;
 pub mod email_wizard;
+pub mod time_entries_preview;
+pub mod settings;
+pub mod report;
 pub mod profile_wizard;
 pub mod client_wizard;
 pub mod project_wizard;
-pub mod components;
\ No newline at end of file
+pub mod components;
+pub mod theme;
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -9,11 +9,26 @@ use tui::{
     Frame,
 };
 
-use crate::models::Profile;
+use crate::models::{is_hex_color, Profile, DEFAULT_ACCENT_COLOR, DEFAULT_HEADER_COLOR};
+use crate::money::{is_currency_code, DEFAULT_CURRENCY};
+use crate::ui::components::confirm::render_confirmation;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::{footer_spans, value_or_hint};
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Move between fields"),
+    ("Enter", "Edit the selected field, or save it while editing"),
+    ("Ctrl+Z", "Undo changes to the field being edited"),
+    ("S", "Save the profile"),
+    ("Ctrl+R", "Start a new profile over"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Cancel editing, or leave without saving"),
+];
 
 pub enum ProfileWizardAction {
     Cancel,
-    Save(Profile),
+    Save(Box<Profile>, Vec<String>), // Contains the profile and its default line items
 }
 
 #[derive(Clone, PartialEq, Copy)]
@@ -25,12 +40,22 @@ pub enum ProfileField {
     BankName,
     BankAccountNumber,
     BankRoutingNumber,
+    HeaderColor,
+    AccentColor,
+    Currency,
+    DefaultLineItems,
 }
 
 pub struct ProfileWizardState {
     pub profile: Profile,
     pub current_field: ProfileField,
     pub editing: bool,
+    pub confirm_reset: bool,
+    pub show_help: bool,
+    // Descriptions new invoices start with, separated by semicolons while editing
+    pub default_line_items: String,
+    // Value of the field when editing started, restored by undo
+    edit_start_value: String,
 }
 
 impl ProfileWizardState {
@@ -45,9 +70,16 @@ impl ProfileWizardState {
                 bank_name: String::new(),
                 bank_account_number: String::new(),
                 bank_routing_number: String::new(),
+                header_color: DEFAULT_HEADER_COLOR.to_string(),
+                accent_color: DEFAULT_ACCENT_COLOR.to_string(),
+                currency: DEFAULT_CURRENCY.to_string(),
             },
             current_field: ProfileField::Name,
             editing: false,
+            confirm_reset: false,
+            show_help: false,
+            default_line_items: String::new(),
+            edit_start_value: String::new(),
         }
     }
 
@@ -56,11 +88,56 @@ impl ProfileWizardState {
             profile,
             current_field: ProfileField::Name,
             editing: false,
+            confirm_reset: false,
+            show_help: false,
+            default_line_items: String::new(),
+            edit_start_value: String::new(),
         }
     }
 
+    pub fn with_default_line_items(mut self, descriptions: Vec<String>) -> Self {
+        self.default_line_items = descriptions.join("; ");
+        self
+    }
+
+    // Default line item descriptions as entered, skipping empty ones
+    pub fn default_line_item_descriptions(&self) -> Vec<String> {
+        self.default_line_items
+            .split(';')
+            .map(|description| description.trim())
+            .filter(|description| !description.is_empty())
+            .map(|description| description.to_string())
+            .collect()
+    }
+
     pub fn toggle_editing(&mut self) {
         self.editing = !self.editing;
+        if self.editing {
+            self.edit_start_value = self.current_field_value().clone();
+        }
+    }
+
+    // Put the field being edited back to its value from when editing started
+    pub fn undo_edit(&mut self) {
+        if self.editing {
+            *self.current_field_value() = self.edit_start_value.clone();
+        }
+    }
+
+    fn current_field_value(&mut self) -> &mut String {
+        match self.current_field {
+            ProfileField::Name => &mut self.profile.name,
+            ProfileField::Email => &mut self.profile.email,
+            ProfileField::PhoneNumber => &mut self.profile.phonenumber,
+            ProfileField::Address => self.profile.address.get_or_insert_with(String::new),
+            ProfileField::BankName => &mut self.profile.bank_name,
+            ProfileField::BankAccountNumber => &mut self.profile.bank_account_number,
+            ProfileField::BankRoutingNumber => &mut self.profile.bank_routing_number,
+            ProfileField::HeaderColor => &mut self.profile.header_color,
+            ProfileField::AccentColor => &mut self.profile.accent_color,
+            ProfileField::Currency => &mut self.profile.currency,
+            ProfileField::DefaultLineItems => &mut self.default_line_items,
+        }
     }
 
     pub fn next_field(&mut self) {
@@ -71,19 +148,27 @@ impl ProfileWizardState {
             ProfileField::Address => ProfileField::BankName,
             ProfileField::BankName => ProfileField::BankAccountNumber,
             ProfileField::BankAccountNumber => ProfileField::BankRoutingNumber,
-            ProfileField::BankRoutingNumber => ProfileField::Name,
+            ProfileField::BankRoutingNumber => ProfileField::HeaderColor,
+            ProfileField::HeaderColor => ProfileField::AccentColor,
+            ProfileField::AccentColor => ProfileField::Currency,
+            ProfileField::Currency => ProfileField::DefaultLineItems,
+            ProfileField::DefaultLineItems => ProfileField::Name,
         };
     }
 
     pub fn previous_field(&mut self) {
         self.current_field = match self.current_field {
-            ProfileField::Name => ProfileField::BankRoutingNumber,
+            ProfileField::Name => ProfileField::DefaultLineItems,
             ProfileField::Email => ProfileField::Name,
             ProfileField::PhoneNumber => ProfileField::Email,
             ProfileField::Address => ProfileField::PhoneNumber,
             ProfileField::BankName => ProfileField::Address,
             ProfileField::BankAccountNumber => ProfileField::BankName,
             ProfileField::BankRoutingNumber => ProfileField::BankAccountNumber,
+            ProfileField::HeaderColor => ProfileField::BankRoutingNumber,
+            ProfileField::AccentColor => ProfileField::HeaderColor,
+            ProfileField::Currency => ProfileField::AccentColor,
+            ProfileField::DefaultLineItems => ProfileField::Currency,
         };
     }
 
@@ -92,20 +177,7 @@ impl ProfileWizardState {
             return;
         }
 
-        let field_value = match self.current_field {
-            ProfileField::Name => &mut self.profile.name,
-            ProfileField::Email => &mut self.profile.email,
-            ProfileField::PhoneNumber => &mut self.profile.phonenumber,
-            ProfileField::Address => {
-                if self.profile.address.is_none() {
-                    self.profile.address = Some(String::new());
-                }
-                self.profile.address.as_mut().unwrap()
-            }
-            ProfileField::BankName => &mut self.profile.bank_name,
-            ProfileField::BankAccountNumber => &mut self.profile.bank_account_number,
-            ProfileField::BankRoutingNumber => &mut self.profile.bank_routing_number,
-        };
+        let field_value = self.current_field_value();
 
         match key {
             KeyCode::Char(c) => {
@@ -118,13 +190,44 @@ impl ProfileWizardState {
         }
     }
 
+    // Whether anything was entered since the wizard opened
+    pub fn is_dirty(&self) -> bool {
+        !self.profile.name.is_empty() ||
+        !self.profile.email.is_empty() ||
+        !self.profile.phonenumber.is_empty() ||
+        self.profile.address.as_ref().is_some_and(|address| !address.is_empty()) ||
+        !self.profile.bank_name.is_empty() ||
+        !self.profile.bank_account_number.is_empty() ||
+        !self.profile.bank_routing_number.is_empty() ||
+        self.profile.header_color != DEFAULT_HEADER_COLOR ||
+        self.profile.accent_color != DEFAULT_ACCENT_COLOR ||
+        self.profile.currency != DEFAULT_CURRENCY ||
+        !self.default_line_items.is_empty()
+    }
+
+    // Start a new profile over, asking first if anything would be lost
+    pub fn request_reset(&mut self) {
+        if self.is_dirty() {
+            self.confirm_reset = true;
+        } else {
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
     pub fn is_valid(&self) -> bool {
         !self.profile.name.is_empty() &&
         !self.profile.email.is_empty() &&
         !self.profile.phonenumber.is_empty() &&
         !self.profile.bank_name.is_empty() &&
         !self.profile.bank_account_number.is_empty() &&
-        !self.profile.bank_routing_number.is_empty()
+        !self.profile.bank_routing_number.is_empty() &&
+        is_hex_color(&self.profile.header_color) &&
+        is_hex_color(&self.profile.accent_color) &&
+        is_currency_code(&self.profile.currency)
     }
 }
 
@@ -160,15 +263,25 @@ pub fn render_profile_wizard<B: Backend>(f: &mut Frame<B>, state: &mut ProfileWi
 
     // Help text
     let help_text = if state.editing {
-        "Enter - Save field | Esc - Cancel editing"
+        "Enter - Save field | Ctrl+Z - Undo changes | Esc - Cancel editing"
+    } else if state.profile.id == 0 {
+        "Enter - Edit field | Up/Down - Navigate fields | S - Save profile | Ctrl+R - Reset | Esc - Cancel"
     } else {
         "Enter - Edit field | Up/Down - Navigate fields | S - Save profile | Esc - Cancel"
     };
     
-    let help = Paragraph::new(help_text)
+    let help = Paragraph::new(footer_spans(help_text, chunks[2].width.saturating_sub(2) as usize))
         .style(Style::default().fg(Color::Gray))
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(help, chunks[2]);
+
+    if state.confirm_reset {
+        render_confirmation(f, "Reset", "Clear all fields and start over?");
+    }
+
+    if state.show_help {
+        render_help(f, HELP_ENTRIES);
+    }
 }
 
 fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProfileWizardState, area: Rect) {
@@ -180,6 +293,25 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProfileWizardState, are
         "Bank Name",
         "Bank Account Number",
         "Bank Routing Number",
+        "Invoice Header Color",
+        "Invoice Accent Color",
+        "Currency",
+        "Default Line Items",
+    ];
+
+    // Shown in empty fields as an example of what to enter
+    let field_hints = [
+        "e.g. Jane Doe Consulting",
+        "e.g. jane@example.com",
+        "e.g. +1 555 123 4567",
+        "e.g. 123 Main St, Springfield",
+        "e.g. First National Bank",
+        "e.g. 000123456789",
+        "e.g. 9 digits, 021000021",
+        "e.g. #343876",
+        "e.g. #e83e8c",
+        "e.g. USD, EUR or GBP",
+        "e.g. Design; Development; Meetings",
     ];
 
     let empty_string = String::new();
@@ -192,6 +324,10 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProfileWizardState, are
         &state.profile.bank_name,
         &state.profile.bank_account_number,
         &state.profile.bank_routing_number,
+        &state.profile.header_color,
+        &state.profile.accent_color,
+        &state.profile.currency,
+        &state.default_line_items,
     ];
 
     let items: Vec<ListItem> = field_names
@@ -217,10 +353,21 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProfileWizardState, are
                     Style::default()
                 };
                 
-                Spans::from(vec![
+                let mut spans = vec![
                     Span::styled(format!("{}: ", name), style),
-                    Span::raw(value.as_str()),
-                ])
+                    value_or_hint(value, field_hints[i]),
+                ];
+                
+                // Colors and the currency are checked before saving, point out one that won't be accepted
+                let is_color = i == ProfileField::HeaderColor as usize || i == ProfileField::AccentColor as usize;
+                if is_color && !is_hex_color(value) {
+                    spans.push(Span::styled("  must be #RRGGBB", Style::default().fg(Color::Red)));
+                }
+                if i == ProfileField::Currency as usize && !is_currency_code(value) {
+                    spans.push(Span::styled("  must be a 3 letter code", Style::default().fg(Color::Red)));
+                }
+                
+                Spans::from(spans)
             };
 
             ListItem::new(content)
@@ -236,7 +383,25 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProfileWizardState, are
 
 pub fn handle_input(state: &mut ProfileWizardState) -> Result<Option<ProfileWizardAction>> {
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+
+        if state.confirm_reset {
+            state.confirm_reset = false;
+            if key.code == KeyCode::Char('y') {
+                state.reset();
+            }
+            return Ok(None);
+        }
+
         match key.code {
+            KeyCode::Char('?') if !state.editing => {
+                state.show_help = true;
+            }
             KeyCode::Esc => {
                 if state.editing {
                     state.toggle_editing();
@@ -257,9 +422,19 @@ pub fn handle_input(state: &mut ProfileWizardState) -> Result<Option<ProfileWiza
             KeyCode::Down if !state.editing => {
                 state.next_field();
             }
+            KeyCode::Char('r') if !state.editing && state.profile.id == 0 &&
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.request_reset();
+            }
+            KeyCode::Char('z') if state.editing && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.undo_edit();
+            }
             KeyCode::Char('s') if !state.editing => {
                 if state.is_valid() {
-                    return Ok(Some(ProfileWizardAction::Save(state.profile.clone())));
+                    return Ok(Some(ProfileWizardAction::Save(
+                        Box::new(state.profile.clone()),
+                        state.default_line_item_descriptions(),
+                    )));
                 }
             }
             _ if state.editing => {
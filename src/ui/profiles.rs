@@ -5,17 +5,62 @@ use tui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
-use crate::models::Profile;
+use crate::export::ExportFormat;
+use crate::models::{DuplicateInvoiceNumber, OrphanReport, Profile};
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::footer_spans;
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Select a profile"),
+    ("Enter", "View the profile's clients"),
+    ("N", "New profile"),
+    ("E", "Edit the selected profile"),
+    ("D", "Delete the selected profile with its clients and projects"),
+    ("/", "Filter profiles by name"),
+    ("X", "Export the profile's invoices to CSV"),
+    ("B", "Copy the profile's bank details"),
+    ("T", "Test the SMTP settings"),
+    ("O", "Check for orphaned records"),
+    ("U", "Check for duplicate invoice numbers"),
+    ("C", "Recompute invoice totals"),
+    ("R", "Revenue report"),
+    ("S", "Settings"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Clear the filter, or exit"),
+    ("Q", "Exit"),
+];
+
+// Represents the CSV export prompt for the selected profile
+pub struct ExportPrompt {
+    pub format: ExportFormat,
+    pub path: String,
+}
 
 // Represents the state of the profile selection screen
 pub struct ProfilesState {
     profiles: Vec<Profile>,
+    // Indices into profiles that match the name filter, in display order
+    visible: Vec<usize>,
+    filter: String,
+    // Whether the name filter is being typed
+    filtering: bool,
     list_state: ListState,
     show_delete_confirmation: bool,
+    // Asked before quitting, so a stray Esc doesn't close the app
+    show_exit_confirmation: bool,
+    export_prompt: Option<ExportPrompt>,
+    orphan_report: Option<OrphanReport>,
+    duplicate_numbers: Option<(i32, Vec<DuplicateInvoiceNumber>)>, // Contains profile_id and the conflicts
+    message_title: String,
+    show_error: Option<String>,
+    show_success: Option<String>,
+    show_notice: Option<String>,
+    show_help: bool,
 }
 
 impl ProfilesState {
@@ -26,20 +71,32 @@ impl ProfilesState {
         }
         
         Self {
+            visible: (0..profiles.len()).collect(),
             profiles,
+            filter: String::new(),
+            filtering: false,
             list_state,
             show_delete_confirmation: false,
+            show_exit_confirmation: false,
+            export_prompt: None,
+            orphan_report: None,
+            duplicate_numbers: None,
+            message_title: String::new(),
+            show_error: None,
+            show_success: None,
+            show_notice: None,
+            show_help: false,
         }
     }
 
     pub fn next(&mut self) {
-        if self.profiles.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
 
         let i = match self.list_state.selected() {
             Some(i) => {
-                if i >= self.profiles.len() - 1 {
+                if i >= self.visible.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -51,14 +108,14 @@ impl ProfilesState {
     }
 
     pub fn previous(&mut self) {
-        if self.profiles.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
 
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.profiles.len() - 1
+                    self.visible.len() - 1
                 } else {
                     i - 1
                 }
@@ -73,12 +130,101 @@ impl ProfilesState {
     }
 
     pub fn selected_profile(&self) -> Option<&Profile> {
-        self.list_state.selected().and_then(|i| self.profiles.get(i))
+        self.list_state
+            .selected()
+            .and_then(|i| self.visible.get(i))
+            .and_then(|&i| self.profiles.get(i))
     }
     
     pub fn selected_profile_id(&self) -> Option<i32> {
         self.selected_profile().map(|p| p.id)
     }
+    
+    // Show the outcome of an SMTP connection test in a popup
+    pub fn show_smtp_test_result(&mut self, result: Result<String, String>) {
+        self.show_result("SMTP Test", result);
+    }
+    
+    // Show the outcome of an operation in a popup with the given title
+    pub fn show_result(&mut self, title: &str, result: Result<String, String>) {
+        self.message_title = title.to_string();
+        match result {
+            Ok(message) => self.show_success = Some(message),
+            Err(error) => self.show_error = Some(error),
+        }
+    }
+    
+    pub fn start_export(&mut self) {
+        self.export_prompt = Some(ExportPrompt {
+            format: ExportFormat::QuickBooks,
+            path: "invoices_export.csv".to_string(),
+        });
+    }
+    
+    // Show the orphan check results, or a success message when the data is consistent
+    pub fn show_orphan_report(&mut self, report: OrphanReport) {
+        if report.is_empty() {
+            self.show_result("Consistency Check", Ok("No orphaned records found".to_string()));
+        } else {
+            self.orphan_report = Some(report);
+        }
+    }
+    
+    // Show an informational popup with the given title
+    pub fn show_notice(&mut self, title: &str, message: String) {
+        self.message_title = title.to_string();
+        self.show_notice = Some(message);
+    }
+    
+    // Show the duplicate invoice numbers of a profile, or a success message when there are none
+    pub fn show_duplicate_numbers(&mut self, profile_id: i32, duplicates: Vec<DuplicateInvoiceNumber>) {
+        if duplicates.is_empty() {
+            self.show_result("Invoice Numbers", Ok("No duplicate invoice numbers found".to_string()));
+        } else {
+            self.duplicate_numbers = Some((profile_id, duplicates));
+        }
+    }
+    
+    // Show only profiles whose name contains the filter, ignoring case
+    pub fn set_filter(&mut self, filter: &str) {
+        let previous = self.selected_profile_id();
+        let needle = filter.to_lowercase();
+        
+        self.filter = filter.to_string();
+        self.visible = self.profiles
+            .iter()
+            .enumerate()
+            .filter(|(_, profile)| profile.name.to_lowercase().contains(&needle))
+            .map(|(i, _)| i)
+            .collect();
+        
+        // Keep the same profile selected when it still matches
+        let selected = self.visible
+            .iter()
+            .position(|&i| Some(self.profiles[i].id) == previous)
+            .or(if self.visible.is_empty() { None } else { Some(0) });
+        self.list_state.select(selected);
+    }
+    
+    pub fn clear_filter(&mut self) {
+        self.filtering = false;
+        self.set_filter("");
+    }
+    
+    fn has_popup_message(&self) -> bool {
+        self.show_error.is_some() || self.show_success.is_some() || self.show_notice.is_some()
+    }
+}
+
+// Bank details of a profile formatted for pasting into a message
+pub fn format_bank_details(profile: &Profile) -> String {
+    format!(
+        "Payable to: {}\nBank: {}\nAccount Number: {}\nRouting Number: {}",
+        profile.name,
+        profile.bank_name,
+        profile.bank_account_number,
+        profile.bank_routing_number,
+    )
 }
 
 pub enum ProfileAction {
@@ -87,6 +233,16 @@ pub enum ProfileAction {
     DeleteProfile(i32),
     SelectProfile(i32),
     EditProfile(i32),
+    TestSmtp,
+    ExportCsv(i32, ExportFormat, String), // Contains profile_id, format and output path
+    CheckOrphans,
+    CleanOrphans,
+    RecomputeTotals,
+    CheckDuplicateNumbers(i32), // Contains profile_id
+    RenumberInvoices(i32), // Contains profile_id
+    CopyBankDetails(String), // Contains the formatted bank details
+    Settings,
+    Report,
 }
 
 pub fn render_profiles<B: Backend>(frame: &mut Frame<B>, state: &mut ProfilesState) {
@@ -103,15 +259,23 @@ pub fn render_profiles<B: Backend>(frame: &mut Frame<B>, state: &mut ProfilesSta
 
     // Create and render the profiles list
     let items: Vec<ListItem> = state
-        .profiles
+        .visible
         .iter()
-        .map(|profile| {
-            ListItem::new(Spans::from(vec![Span::raw(&profile.name)]))
+        .map(|&i| {
+            ListItem::new(Spans::from(vec![Span::raw(&state.profiles[i].name)]))
         })
         .collect();
 
+    let title = if state.filtering {
+        format!("Profiles (filter: {}|)", state.filter)
+    } else if !state.filter.is_empty() {
+        format!("Profiles (filter: {})", state.filter)
+    } else {
+        "Profiles".to_string()
+    };
+
     let profiles_list = List::new(items)
-        .block(Block::default().title("Profiles").borders(Borders::ALL))
+        .block(Block::default().title(title).borders(Borders::ALL))
         .highlight_style(
             Style::default()
                 .bg(Color::Blue)
@@ -122,13 +286,17 @@ pub fn render_profiles<B: Backend>(frame: &mut Frame<B>, state: &mut ProfilesSta
     frame.render_stateful_widget(profiles_list, chunks[0], &mut state.list_state);
 
     // Create and render the buttons
-    let buttons_text = if state.selected_profile().is_some() {
-        format!("<N> New Profile | <E> Edit Profile | <D> Delete Profile | <Enter> View Clients | <Esc> Exit")
+    let buttons_text = if state.filtering {
+        "<Enter> Done | <Esc> Clear filter".to_string()
+    } else if !state.filter.is_empty() {
+        "<Enter> View Clients | </> Edit Filter | <Esc> Clear Filter".to_string()
+    } else if state.selected_profile().is_some() {
+        format!("<N> New Profile | <E> Edit Profile | <D> Delete Profile | <Enter> View Clients | </> Filter | <X> Export CSV | <B> Copy Bank Details | <T> Test SMTP | <O> Check Data | <U> Check Numbers | <C> Recompute Totals | <R> Report | <S> Settings | <Esc> Exit")
     } else {
-        format!("<N> New Profile | <Esc> Exit")
+        format!("<N> New Profile | <T> Test SMTP | <O> Check Data | <C> Recompute Totals | <R> Report | <S> Settings | <Esc> Exit")
     };
 
-    let buttons = Paragraph::new(buttons_text)
+    let buttons = Paragraph::new(footer_spans(&buttons_text, chunks[1].width as usize))
         .block(Block::default().borders(Borders::TOP))
         .style(Style::default().fg(Color::White));
 
@@ -138,6 +306,39 @@ pub fn render_profiles<B: Backend>(frame: &mut Frame<B>, state: &mut ProfilesSta
     if state.show_delete_confirmation {
         render_delete_confirmation(frame, size);
     }
+    
+    // Render exit confirmation popup if needed
+    if state.show_exit_confirmation {
+        render_exit_confirmation(frame, size);
+    }
+    
+    // Render export prompt if needed
+    if let Some(prompt) = &state.export_prompt {
+        render_export_prompt(frame, size, prompt);
+    }
+    
+    // Render orphan report if needed
+    if let Some(report) = &state.orphan_report {
+        render_orphan_report(frame, size, report);
+    }
+    
+    // Render duplicate invoice numbers if needed
+    if let Some((_, duplicates)) = &state.duplicate_numbers {
+        render_duplicate_numbers(frame, size, duplicates);
+    }
+    
+    // Render result popup if needed
+    if let Some(error) = &state.show_error {
+        render_message(frame, size, &format!("{} Failed", state.message_title), error, Color::Red);
+    } else if let Some(message) = &state.show_success {
+        render_message(frame, size, &state.message_title, message, Color::Green);
+    } else if let Some(message) = &state.show_notice {
+        render_message(frame, size, &state.message_title, message, Color::Yellow);
+    }
+    
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
 }
 
 fn render_delete_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect) {
@@ -157,6 +358,106 @@ fn render_delete_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect) {
     frame.render_widget(popup, popup_area);
 }
 
+fn render_exit_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect) {
+    let popup_area = centered_rect(50, 20, size);
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from("Quit Invoice Manager?"),
+        Spans::from(""),
+        Spans::from("<Y> Yes  <N> No"),
+    ])
+    .block(Block::default().title("Confirm Exit").borders(Borders::ALL))
+    .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_export_prompt<B: Backend>(frame: &mut Frame<B>, size: Rect, prompt: &ExportPrompt) {
+    let popup_area = centered_rect(60, 25, size);
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(vec![
+            Span::raw("Format: "),
+            Span::styled(prompt.format.name(), Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Spans::from(vec![
+            Span::raw("Output path: "),
+            Span::styled(format!("{}|", prompt.path), Style::default().fg(Color::Yellow)),
+        ]),
+        Spans::from(""),
+        Spans::from("<Tab> Switch format | <Enter> Export | <Esc> Cancel"),
+    ])
+    .block(Block::default().title("Export Invoices to CSV").borders(Borders::ALL))
+    .style(Style::default().fg(Color::White).bg(Color::Black));
+    
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_orphan_report<B: Backend>(frame: &mut Frame<B>, size: Rect, report: &OrphanReport) {
+    let popup_area = centered_rect(60, 40, size);
+    
+    let format_ids = |ids: &[i32]| {
+        ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+    };
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(format!("Found {} orphaned record(s):", report.total())),
+        Spans::from(""),
+        Spans::from(format!("Clients without profile: {} [{}]", report.client_ids.len(), format_ids(&report.client_ids))),
+        Spans::from(format!("Projects without client: {} [{}]", report.project_ids.len(), format_ids(&report.project_ids))),
+        Spans::from(format!("Invoices without project: {} [{}]", report.invoice_ids.len(), format_ids(&report.invoice_ids))),
+        Spans::from(format!("Line items without invoice: {} [{}]", report.line_item_ids.len(), format_ids(&report.line_item_ids))),
+        Spans::from(""),
+        Spans::from("Delete them? <Y> Yes  <N> No"),
+    ])
+    .block(Block::default().title("Consistency Check").borders(Borders::ALL))
+    .style(Style::default().fg(Color::Yellow).bg(Color::Black));
+    
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_duplicate_numbers<B: Backend>(frame: &mut Frame<B>, size: Rect, duplicates: &[DuplicateInvoiceNumber]) {
+    let popup_area = centered_rect(60, 40, size);
+    
+    let mut lines = vec![
+        Spans::from(""),
+        Spans::from(format!("{} invoice number(s) are used more than once:", duplicates.len())),
+        Spans::from(""),
+    ];
+    lines.extend(duplicates.iter().map(|duplicate| {
+        Spans::from(format!("#{} used by {} invoices", duplicate.number, duplicate.count))
+    }));
+    lines.push(Spans::from(""));
+    lines.push(Spans::from("Renumber all invoices sequentially by submit date? <Y> Yes  <N> No"));
+    
+    let popup = Paragraph::new(lines)
+        .block(Block::default().title("Duplicate Invoice Numbers").borders(Borders::ALL))
+        .style(Style::default().fg(Color::Yellow).bg(Color::Black))
+        .wrap(Wrap { trim: true });
+    
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_message<B: Backend>(frame: &mut Frame<B>, size: Rect, title: &str, message: &str, color: Color) {
+    let popup_area = centered_rect(60, 20, size);
+    
+    let popup = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(message),
+        Spans::from(""),
+        Spans::from("Press any key to continue"),
+    ])
+    .block(Block::default().title(title).borders(Borders::ALL))
+    .style(Style::default().fg(color).bg(Color::Black))
+    .wrap(Wrap { trim: true });
+    
+    frame.render_widget(popup, popup_area);
+}
+
 // Helper function to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -180,12 +481,86 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
 pub fn handle_input(state: &mut ProfilesState) -> Result<Option<ProfileAction>> {
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+        
+        // Any key dismisses the result popup
+        if state.has_popup_message() {
+            state.show_error = None;
+            state.show_success = None;
+            state.show_notice = None;
+            return Ok(None);
+        }
+        
+        // Quit on 'y', any other key goes back to the list
+        if state.show_exit_confirmation {
+            state.show_exit_confirmation = false;
+            if key.code == KeyCode::Char('y') {
+                return Ok(Some(ProfileAction::Exit));
+            }
+            return Ok(None);
+        }
+        
+        if state.export_prompt.is_some() {
+            return Ok(handle_export_input(state, key.code));
+        }
+        
+        // Renumber on 'y', any other key closes the duplicate numbers report
+        if let Some((profile_id, _)) = state.duplicate_numbers.take() {
+            if key.code == KeyCode::Char('y') {
+                return Ok(Some(ProfileAction::RenumberInvoices(profile_id)));
+            }
+            return Ok(None);
+        }
+        
+        // Clean up on 'y', any other key closes the orphan report
+        if state.orphan_report.take().is_some() {
+            if key.code == KeyCode::Char('y') {
+                return Ok(Some(ProfileAction::CleanOrphans));
+            }
+            return Ok(None);
+        }
+        
+        // Typing the name filter
+        if state.filtering {
+            match key.code {
+                KeyCode::Char(c) => {
+                    let filter = format!("{}{}", state.filter, c);
+                    state.set_filter(&filter);
+                }
+                KeyCode::Backspace => {
+                    let mut filter = state.filter.clone();
+                    filter.pop();
+                    state.set_filter(&filter);
+                }
+                KeyCode::Enter => state.filtering = false,
+                KeyCode::Esc => state.clear_filter(),
+                KeyCode::Down => state.next(),
+                KeyCode::Up => state.previous(),
+                _ => {}
+            }
+            return Ok(None);
+        }
+        
         match key.code {
+            KeyCode::Char('?') if !state.show_delete_confirmation => {
+                state.show_help = true;
+            }
+            KeyCode::Char('/') if !state.show_delete_confirmation => {
+                state.filtering = true;
+            }
+            KeyCode::Esc if !state.show_delete_confirmation && !state.filter.is_empty() => {
+                state.clear_filter();
+            }
             KeyCode::Char('q') | KeyCode::Esc => {
                 if state.show_delete_confirmation {
                     state.toggle_delete_confirmation();
                 } else {
-                    return Ok(Some(ProfileAction::Exit));
+                    state.show_exit_confirmation = true;
                 }
             }
             KeyCode::Char('n') => {
@@ -213,6 +588,34 @@ pub fn handle_input(state: &mut ProfilesState) -> Result<Option<ProfileAction>>
                     }
                 }
             }
+            KeyCode::Char('x') if !state.show_delete_confirmation && state.selected_profile().is_some() => {
+                state.start_export();
+            }
+            KeyCode::Char('t') if !state.show_delete_confirmation => {
+                return Ok(Some(ProfileAction::TestSmtp));
+            }
+            KeyCode::Char('b') if !state.show_delete_confirmation && state.selected_profile().is_some() => {
+                if let Some(profile) = state.selected_profile() {
+                    return Ok(Some(ProfileAction::CopyBankDetails(format_bank_details(profile))));
+                }
+            }
+            KeyCode::Char('u') if !state.show_delete_confirmation && state.selected_profile().is_some() => {
+                if let Some(id) = state.selected_profile_id() {
+                    return Ok(Some(ProfileAction::CheckDuplicateNumbers(id)));
+                }
+            }
+            KeyCode::Char('o') if !state.show_delete_confirmation => {
+                return Ok(Some(ProfileAction::CheckOrphans));
+            }
+            KeyCode::Char('c') if !state.show_delete_confirmation => {
+                return Ok(Some(ProfileAction::RecomputeTotals));
+            }
+            KeyCode::Char('s') if !state.show_delete_confirmation => {
+                return Ok(Some(ProfileAction::Settings));
+            }
+            KeyCode::Char('r') if !state.show_delete_confirmation => {
+                return Ok(Some(ProfileAction::Report));
+            }
             KeyCode::Down => {
                 if !state.show_delete_confirmation {
                     state.next();
@@ -234,4 +637,83 @@ pub fn handle_input(state: &mut ProfilesState) -> Result<Option<ProfileAction>>
         }
     }
     Ok(None)
-} 
\ No newline at end of file
+} 
+
+// Handle keys while the CSV export prompt is open
+fn handle_export_input(state: &mut ProfilesState, key: KeyCode) -> Option<ProfileAction> {
+    let profile_id = state.selected_profile_id();
+    let prompt = state.export_prompt.as_mut()?;
+    
+    match key {
+        KeyCode::Esc => {
+            state.export_prompt = None;
+        }
+        KeyCode::Tab => {
+            prompt.format = prompt.format.toggle();
+        }
+        KeyCode::Backspace => {
+            prompt.path.pop();
+        }
+        KeyCode::Char(c) => {
+            prompt.path.push(c);
+        }
+        KeyCode::Enter if !prompt.path.trim().is_empty() => {
+            let prompt = state.export_prompt.take()?;
+            return profile_id.map(|id| ProfileAction::ExportCsv(id, prompt.format, prompt.path.trim().to_string()));
+        }
+        _ => {}
+    }
+    
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::profile;
+
+    fn profiles_state() -> ProfilesState {
+        let names = ["Studio North", "Freelance", "studio south", "Consulting"];
+        let profiles = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Profile { id: i as i32 + 1, ..profile(name) })
+            .collect();
+        ProfilesState::new(profiles)
+    }
+
+    #[test]
+    fn selection_follows_the_filtered_profiles() {
+        let mut state = profiles_state();
+        state.set_filter("STUDIO");
+        assert_eq!(state.selected_profile_id(), Some(1));
+
+        state.next();
+        assert_eq!(state.selected_profile_id(), Some(3));
+        // Navigation wraps within the filtered profiles
+        state.next();
+        assert_eq!(state.selected_profile_id(), Some(1));
+        state.previous();
+        assert_eq!(state.selected_profile_id(), Some(3));
+    }
+
+    #[test]
+    fn clearing_the_filter_keeps_the_selected_profile() {
+        let mut state = profiles_state();
+        state.set_filter("south");
+        assert_eq!(state.selected_profile_id(), Some(3));
+
+        state.clear_filter();
+        assert_eq!(state.profiles.len(), 4);
+        assert_eq!(state.selected_profile_id(), Some(3));
+    }
+
+    #[test]
+    fn nothing_is_selected_when_no_profile_matches() {
+        let mut state = profiles_state();
+        state.set_filter("agency");
+        assert_eq!(state.selected_profile_id(), None);
+        state.next();
+        assert_eq!(state.selected_profile_id(), None);
+    }
+}
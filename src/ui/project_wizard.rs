@@ -1,6 +1,6 @@
 use anyhow::Result;
 use chrono::{Datelike, NaiveDate};
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -11,7 +11,22 @@ use tui::{
 };
 
 use crate::models::Project;
+use crate::ui::components::confirm::render_confirmation;
 use crate::ui::components::date_input::{DateInputState, DatePart};
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::{footer_spans, value_or_hint};
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Move between fields"),
+    ("Enter", "Edit the selected field, or save it while editing"),
+    ("Left/Right", "Switch date part while editing a date"),
+    ("Ctrl+Z", "Undo changes to the field being edited"),
+    ("S", "Save the project"),
+    ("Ctrl+R", "Start a new project over"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Cancel editing, or leave without saving"),
+];
 
 pub enum ProjectWizardAction {
     Cancel,
@@ -32,6 +47,10 @@ pub struct ProjectWizardState {
     pub editing: bool,
     pub start_date_state: DateInputState,
     pub end_date_state: DateInputState,
+    pub confirm_reset: bool,
+    pub show_help: bool,
+    // Name when editing started, restored by undo
+    edit_start_name: String,
 }
 
 impl ProjectWizardState {
@@ -51,6 +70,9 @@ impl ProjectWizardState {
             editing: false,
             start_date_state: DateInputState::new(today),
             end_date_state: DateInputState::new(today),
+            confirm_reset: false,
+            show_help: false,
+            edit_start_name: String::new(),
         }
     }
 
@@ -63,9 +85,19 @@ impl ProjectWizardState {
             editing: false,
             start_date_state: DateInputState::new(project.start_date),
             end_date_state: DateInputState::new(end_date),
+            confirm_reset: false,
+            show_help: false,
+            edit_start_name: String::new(),
         }
     }
 
+    // Typing the day of a date commits it and moves to the next field
+    pub fn with_date_auto_advance(mut self, auto_advance: bool) -> Self {
+        self.start_date_state.auto_advance = auto_advance;
+        self.end_date_state.auto_advance = auto_advance;
+        self
+    }
+
     pub fn client_id(&self) -> i32 {
         self.client_id
     }
@@ -73,6 +105,7 @@ impl ProjectWizardState {
     pub fn toggle_editing(&mut self) {
         self.editing = !self.editing;
         if self.editing {
+            self.edit_start_name = self.project.name.clone();
             match self.current_field {
                 ProjectField::StartDate => self.start_date_state.toggle_editing(),
                 ProjectField::EndDate => self.end_date_state.toggle_editing(),
@@ -84,6 +117,13 @@ impl ProjectWizardState {
         }
     }
 
+    // Put the name back to how it was when editing started
+    pub fn undo_edit(&mut self) {
+        if self.editing && self.current_field == ProjectField::Name {
+            self.project.name = self.edit_start_name.clone();
+        }
+    }
+
     pub fn next_field(&mut self) {
         self.current_field = match self.current_field {
             ProjectField::Name => ProjectField::StartDate,
@@ -120,6 +160,10 @@ impl ProjectWizardState {
             ProjectField::StartDate => {
                 self.start_date_state.handle_input(key);
                 self.project.start_date = self.start_date_state.date;
+                if self.start_date_state.take_completed() {
+                    self.toggle_editing();
+                    self.next_field();
+                }
             }
             ProjectField::EndDate => {
                 if self.project.end_date.is_none() {
@@ -129,10 +173,34 @@ impl ProjectWizardState {
                 if let Some(end_date) = &mut self.project.end_date {
                     *end_date = self.end_date_state.date;
                 }
+                if self.end_date_state.take_completed() {
+                    self.toggle_editing();
+                    self.next_field();
+                }
             }
         }
     }
 
+    // Whether anything was entered since the wizard opened
+    pub fn is_dirty(&self) -> bool {
+        !self.project.name.is_empty() ||
+        self.project.start_date != chrono::Local::now().date_naive() ||
+        self.project.end_date.is_some()
+    }
+
+    // Start a new project over, asking first if anything would be lost
+    pub fn request_reset(&mut self) {
+        if self.is_dirty() {
+            self.confirm_reset = true;
+        } else {
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.client_id).with_date_auto_advance(self.start_date_state.auto_advance);
+    }
+
     pub fn is_valid(&self) -> bool {
         !self.project.name.is_empty()
     }
@@ -171,18 +239,28 @@ pub fn render_project_wizard<B: Backend>(f: &mut Frame<B>, state: &mut ProjectWi
     // Help text
     let help_text = if state.editing {
         match state.current_field {
-            ProjectField::Name => "Enter - Save field | Esc - Cancel editing",
+            ProjectField::Name => "Enter - Save field | Ctrl+Z - Undo changes | Esc - Cancel editing",
             ProjectField::StartDate | ProjectField::EndDate => 
                 "Enter - Save field | Left/Right - Switch date part | Esc - Cancel editing",
         }
+    } else if state.project.id == 0 {
+        "Enter - Edit field | Up/Down - Navigate fields | S - Save project | Ctrl+R - Reset | Esc - Cancel"
     } else {
         "Enter - Edit field | Up/Down - Navigate fields | S - Save project | Esc - Cancel"
     };
     
-    let help = Paragraph::new(help_text)
+    let help = Paragraph::new(footer_spans(help_text, chunks[2].width.saturating_sub(2) as usize))
         .style(Style::default().fg(Color::Gray))
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(help, chunks[2]);
+
+    if state.confirm_reset {
+        render_confirmation(f, "Reset", "Clear all fields and start over?");
+    }
+
+    if state.show_help {
+        render_help(f, HELP_ENTRIES);
+    }
 }
 
 fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProjectWizardState, area: Rect) {
@@ -192,6 +270,13 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProjectWizardState, are
         "End Date",
     ];
 
+    // Shown in empty fields as an example of what to enter
+    let field_hints = [
+        "e.g. Website redesign",
+        "",
+        "",
+    ];
+
     // Format dates
     let end_date_str = match &state.project.end_date {
         Some(date) => format!("{}", date.format("%Y-%m-%d")),
@@ -237,7 +322,7 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProjectWizardState, are
                 
                 Spans::from(vec![
                     Span::styled(format!("{}: ", name), style),
-                    Span::raw(value),
+                    value_or_hint(value, field_hints[i]),
                 ])
             };
 
@@ -254,7 +339,25 @@ fn render_form<B: Backend>(f: &mut Frame<B>, state: &mut ProjectWizardState, are
 
 pub fn handle_input(state: &mut ProjectWizardState) -> Result<Option<ProjectWizardAction>> {
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+
+        if state.confirm_reset {
+            state.confirm_reset = false;
+            if key.code == KeyCode::Char('y') {
+                state.reset();
+            }
+            return Ok(None);
+        }
+
         match key.code {
+            KeyCode::Char('?') if !state.editing => {
+                state.show_help = true;
+            }
             KeyCode::Esc => {
                 if state.editing {
                     state.toggle_editing();
@@ -275,6 +378,13 @@ pub fn handle_input(state: &mut ProjectWizardState) -> Result<Option<ProjectWiza
             KeyCode::Down if !state.editing => {
                 state.next_field();
             }
+            KeyCode::Char('r') if !state.editing && state.project.id == 0 &&
+                                  key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.request_reset();
+            }
+            KeyCode::Char('z') if state.editing && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.undo_edit();
+            }
             KeyCode::Char('s') if !state.editing => {
                 if state.is_valid() {
                     return Ok(Some(ProjectWizardAction::Save(state.project.clone())));
@@ -288,4 +398,22 @@ pub fn handle_input(state: &mut ProjectWizardState) -> Result<Option<ProjectWiza
     }
 
     Ok(None)
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::project;
+
+    #[test]
+    fn undo_restores_the_name_from_before_the_edit() {
+        let mut state = ProjectWizardState::from_existing(project(1, "Website"));
+        state.toggle_editing();
+        for _ in 0..7 {
+            state.edit_current_field(KeyCode::Backspace);
+        }
+        assert!(state.project.name.is_empty());
+
+        state.undo_edit();
+        assert_eq!(state.project.name, "Website");
+    }
+}
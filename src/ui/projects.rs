@@ -9,19 +9,38 @@ use tui::{
     Frame,
 };
 
-use crate::models::Project;
+use crate::models::{Project, ProjectSummary};
+use crate::money;
 use crate::db::Database;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::{footer_spans, truncate_to_width};
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Select a project"),
+    ("Enter", "View the project's invoices"),
+    ("N", "New project"),
+    ("E", "Edit the selected project"),
+    ("D", "Delete the selected project and its invoices"),
+    ("[ / ]", "Switch to the previous or next profile"),
+    ("?", "Show or hide this help"),
+    ("Esc / Q", "Back to clients"),
+];
 
 // Represents the state of the project selection screen
 pub struct ProjectsState {
     client_id: i32,
     projects: Vec<Project>,
+    summaries: Vec<ProjectSummary>,
+    // Currency of the client's profile that billed totals are shown in
+    currency: String,
     list_state: ListState,
     show_delete_confirmation: bool,
+    show_help: bool,
 }
 
 impl ProjectsState {
-    pub fn new(client_id: i32, projects: Vec<Project>) -> Self {
+    pub fn new(client_id: i32, projects: Vec<Project>, summaries: Vec<ProjectSummary>) -> Self {
         let mut list_state = ListState::default();
         if !projects.is_empty() {
             list_state.select(Some(0));
@@ -30,11 +49,20 @@ impl ProjectsState {
         Self {
             client_id,
             projects,
+            summaries,
+            currency: money::DEFAULT_CURRENCY.to_string(),
             list_state,
             show_delete_confirmation: false,
+            show_help: false,
         }
     }
 
+    // Show billed totals in the currency of the client's profile
+    pub fn with_currency(mut self, currency: String) -> Self {
+        self.currency = currency;
+        self
+    }
+
     pub fn next(&mut self) {
         if self.projects.is_empty() {
             return;
@@ -83,6 +111,14 @@ impl ProjectsState {
         self.selected_project().map(|p| p.id)
     }
     
+    // Invoice count and billed total of a project, zero when it has no invoices yet
+    pub fn project_summary(&self, project_id: i32) -> (i64, f64) {
+        self.summaries
+            .iter()
+            .find(|s| s.project_id == project_id)
+            .map_or((0, 0.0), |s| (s.invoice_count, s.billed))
+    }
+    
     pub fn client_id(&self) -> i32 {
         self.client_id
     }
@@ -94,15 +130,21 @@ pub enum ProjectAction {
     EditProject(i32), // Contains project_id
     DeleteProject(i32), // Contains project_id
     SelectProject(i32), // Contains project_id
+    PreviousProfile,
+    NextProfile,
 }
 
 // DB operations for projects
 pub async fn load_projects_by_client(db: &Database, client_id: i32) -> Result<Vec<Project>> {
-    db.load_projects_by_client(client_id).await
+    Ok(db.load_projects_by_client(client_id).await?)
+}
+
+pub async fn load_project_summaries(db: &Database, client_id: i32) -> Result<Vec<ProjectSummary>> {
+    Ok(db.get_project_summaries(client_id).await?)
 }
 
 pub async fn delete_project(db: &Database, id: i32) -> Result<()> {
-    db.delete_project(id).await
+    Ok(db.delete_project(id).await?)
 }
 
 pub fn render_projects<B: Backend>(
@@ -119,7 +161,8 @@ pub fn render_projects<B: Backend>(
         ].as_ref())
         .split(size);
 
-    // Create and render the projects list
+    // Create and render the projects list, leaving room for the borders
+    let row_width = chunks[0].width.saturating_sub(2) as usize;
     let items: Vec<ListItem> = state
         .projects
         .iter()
@@ -133,11 +176,17 @@ pub fn render_projects<B: Backend>(
                        project.start_date.format("%Y-%m-%d"))
             };
             
+            let (invoice_count, billed) = state.project_summary(project.id);
+            let summary = format!(" | {} invoice(s), {} billed", invoice_count, money::format_money_in(billed, &state.currency));
+            
+            // The dates and summary always fit, the name gets whatever is left
+            let name_width = row_width.saturating_sub(dates.len() + summary.len() + 3);
             ListItem::new(Spans::from(vec![
-                Span::raw(&project.name),
+                Span::raw(truncate_to_width(&project.name, name_width)),
                 Span::raw(" ("),
                 Span::raw(dates),
                 Span::raw(")"),
+                Span::styled(summary, Style::default().fg(Color::DarkGray)),
             ]))
         })
         .collect();
@@ -155,12 +204,12 @@ pub fn render_projects<B: Backend>(
 
     // Create and render the buttons
     let buttons_text = if state.selected_project().is_some() {
-        "<N> New Project | <E> Edit Project | <D> Delete Project | <Enter> View Invoices | <Esc> Back".to_string()
+        "<N> New Project | <E> Edit Project | <D> Delete Project | <Enter> View Invoices | <[/]> Switch Profile | <Esc> Back".to_string()
     } else {
-        "<N> New Project | <Esc> Back".to_string()
+        "<N> New Project | <[/]> Switch Profile | <Esc> Back".to_string()
     };
 
-    let buttons = Paragraph::new(buttons_text)
+    let buttons = Paragraph::new(footer_spans(&buttons_text, chunks[1].width as usize))
         .block(Block::default().borders(Borders::TOP))
         .style(Style::default().fg(Color::White));
 
@@ -170,6 +219,10 @@ pub fn render_projects<B: Backend>(
     if state.show_delete_confirmation {
         render_delete_confirmation(frame, size);
     }
+    
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
 }
 
 fn render_delete_confirmation<B: Backend>(frame: &mut Frame<B>, size: Rect) {
@@ -212,7 +265,17 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 
 pub fn handle_input(state: &mut ProjectsState) -> Result<Option<ProjectAction>> {
     if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+        
         match key.code {
+            KeyCode::Char('?') if !state.show_delete_confirmation => {
+                state.show_help = true;
+            }
             KeyCode::Char('q') | KeyCode::Esc => {
                 if state.show_delete_confirmation {
                     state.toggle_delete_confirmation();
@@ -220,6 +283,12 @@ pub fn handle_input(state: &mut ProjectsState) -> Result<Option<ProjectAction>>
                     return Ok(Some(ProjectAction::Back));
                 }
             }
+            KeyCode::Char('[') if !state.show_delete_confirmation => {
+                return Ok(Some(ProjectAction::PreviousProfile));
+            }
+            KeyCode::Char(']') if !state.show_delete_confirmation => {
+                return Ok(Some(ProjectAction::NextProfile));
+            }
             KeyCode::Char('n') => {
                 if !state.show_delete_confirmation {
                     return Ok(Some(ProjectAction::NewProject(state.client_id())));
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+use crossterm::event::{self, Event, KeyCode};
+use rust_decimal::Decimal;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::models::ProfileTotals;
+use crate::money;
+use crate::ui::components::date_input::DateInputState;
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::footer_spans;
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("F", "Set the start of the date range"),
+    ("T", "Set the end of the date range"),
+    ("Left/Right", "Switch date part while setting a date"),
+    ("Enter", "Apply the date being set"),
+    ("C", "Clear the date range"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Cancel the date being set, or go back to profiles"),
+    ("Q", "Back to profiles"),
+];
+
+// End of the date range being edited
+#[derive(Clone, Copy, PartialEq)]
+enum RangeBound {
+    From,
+    To,
+}
+
+// Represents the state of the consolidated report across all profiles
+pub struct ReportState {
+    totals: Vec<ProfileTotals>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    table_state: TableState,
+    // Date being typed for one end of the range
+    date_input: Option<(RangeBound, DateInputState)>,
+    show_help: bool,
+}
+
+// Possible actions from the report screen
+pub enum ReportAction {
+    Back,
+    Reload(Option<NaiveDate>, Option<NaiveDate>), // Contains the new from and to dates
+}
+
+impl ReportState {
+    pub fn new(totals: Vec<ProfileTotals>, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Self {
+        Self {
+            totals,
+            from,
+            to,
+            table_state: TableState::default(),
+            date_input: None,
+            show_help: false,
+        }
+    }
+
+    // Sum of each column across the profiles of each currency, added up exactly. Amounts
+    // in different currencies are never added together.
+    pub fn grand_totals(&self) -> Vec<(String, Decimal, Decimal, Decimal)> {
+        let mut totals: BTreeMap<&str, (Decimal, Decimal, Decimal)> = BTreeMap::new();
+        for t in &self.totals {
+            let (billed, paid, outstanding) = totals.entry(t.currency.as_str()).or_default();
+            *billed += money::to_decimal(t.billed);
+            *paid += money::to_decimal(t.paid);
+            *outstanding += money::to_decimal(t.outstanding);
+        }
+        totals
+            .into_iter()
+            .map(|(currency, (billed, paid, outstanding))| (currency.to_string(), billed, paid, outstanding))
+            .collect()
+    }
+
+    fn start_date_input(&mut self, bound: RangeBound) {
+        let today = Local::now().date_naive();
+        let date = match bound {
+            RangeBound::From => self.from.unwrap_or_else(|| today.with_ordinal(1).unwrap_or(today)),
+            RangeBound::To => self.to.unwrap_or(today),
+        };
+        let mut input = DateInputState::new(date);
+        input.toggle_editing();
+        self.date_input = Some((bound, input));
+    }
+
+    fn range_description(&self) -> String {
+        match (self.from, self.to) {
+            (None, None) => "All dates".to_string(),
+            (Some(from), None) => format!("From {}", from.format("%Y-%m-%d")),
+            (None, Some(to)) => format!("Up to {}", to.format("%Y-%m-%d")),
+            (Some(from), Some(to)) => format!("{} to {}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d")),
+        }
+    }
+}
+
+pub fn render_report<B: Backend>(frame: &mut Frame<B>, state: &mut ReportState) {
+    let size = frame.size();
+
+    // Create the layout
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(3),
+        ].as_ref())
+        .split(size);
+
+    // Date range, or the date being edited
+    let range_text = match &state.date_input {
+        Some((RangeBound::From, input)) => format!("From: {}", input.get_display_string()),
+        Some((RangeBound::To, input)) => format!("To: {}", input.get_display_string()),
+        None => state.range_description(),
+    };
+    let range = Paragraph::new(range_text)
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().title("Submit Date Range").borders(Borders::ALL));
+
+    frame.render_widget(range, chunks[0]);
+
+    let header_cells = ["Profile", "Billed", "Paid", "Outstanding"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
+    let header = Row::new(header_cells)
+        .style(Style::default())
+        .height(1)
+        .bottom_margin(1);
+
+    let mut rows: Vec<Row> = state.totals
+        .iter()
+        .map(|t| {
+            Row::new(vec![
+                Cell::from(t.profile_name.clone()),
+                Cell::from(money::format_money_in(t.billed, &t.currency)),
+                Cell::from(money::format_money_in(t.paid, &t.currency)),
+                Cell::from(money::format_money_in(t.outstanding, &t.currency)),
+            ])
+        })
+        .collect();
+
+    // Gap above the grand total row
+    if let Some(last) = rows.pop() {
+        rows.push(last.bottom_margin(1));
+    }
+
+    // A grand total per currency, named after it when profiles bill in more than one
+    let grand_totals = state.grand_totals();
+    let mixed_currencies = grand_totals.len() > 1;
+    for (currency, billed, paid, outstanding) in grand_totals {
+        let label = if mixed_currencies { format!("Grand Total ({})", currency) } else { "Grand Total".to_string() };
+        rows.push(
+            Row::new(vec![
+                Cell::from(label),
+                Cell::from(money::format_money_in(billed, &currency)),
+                Cell::from(money::format_money_in(paid, &currency)),
+                Cell::from(money::format_money_in(outstanding, &currency)),
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+    }
+
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default().title("Revenue by Profile").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Percentage(40),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ]);
+
+    frame.render_stateful_widget(table, chunks[1], &mut state.table_state);
+
+    let buttons_text = if state.date_input.is_some() {
+        "<Enter> Apply | <Left/Right> Switch date part | <Esc> Cancel"
+    } else {
+        "<F> Set From Date | <T> Set To Date | <C> Clear Range | <Esc> Back"
+    };
+
+    let buttons = Paragraph::new(footer_spans(buttons_text, chunks[2].width as usize))
+        .block(Block::default().borders(Borders::TOP))
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(buttons, chunks[2]);
+
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
+}
+
+pub fn handle_input(state: &mut ReportState) -> Result<Option<ReportAction>> {
+    if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+
+        // Typing one end of the date range
+        if let Some((bound, input)) = &mut state.date_input {
+            match key.code {
+                KeyCode::Enter => {
+                    let (bound, date) = (*bound, input.date);
+                    state.date_input = None;
+                    let (from, to) = match bound {
+                        RangeBound::From => (Some(date), state.to),
+                        RangeBound::To => (state.from, Some(date)),
+                    };
+                    return Ok(Some(ReportAction::Reload(from, to)));
+                }
+                KeyCode::Esc => state.date_input = None,
+                code => input.handle_input(code),
+            }
+            return Ok(None);
+        }
+
+        match key.code {
+            KeyCode::Char('?') => state.show_help = true,
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Some(ReportAction::Back)),
+            KeyCode::Char('f') => state.start_date_input(RangeBound::From),
+            KeyCode::Char('t') => state.start_date_input(RangeBound::To),
+            KeyCode::Char('c') if state.from.is_some() || state.to.is_some() => {
+                return Ok(Some(ReportAction::Reload(None, None)));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,247 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::config::{Config, EDITABLE_SETTINGS};
+use crate::ui::components::confirm::{centered_rect, render_error};
+use crate::ui::components::help_overlay::render_help;
+use crate::ui::components::text::footer_spans;
+
+// Settings shown masked on screen
+const SECRET_SETTINGS: &[&str] = &["SMTP_PASSWORD"];
+
+// Key bindings listed by the ? help overlay
+const HELP_ENTRIES: &[(&str, &str)] = &[
+    ("Up/Down", "Select a setting"),
+    ("Enter", "Edit the selected setting, or finish editing it"),
+    ("S", "Save the settings to .env"),
+    ("?", "Show or hide this help"),
+    ("Esc", "Cancel editing, or go back to profiles"),
+];
+
+// Represents the state of the settings screen
+pub struct SettingsState {
+    // (environment variable name, current value) in display order
+    settings: Vec<(String, String)>,
+    list_state: ListState,
+    // Value being typed for the selected setting
+    editing: Option<String>,
+    changed: bool,
+    show_error: Option<String>,
+    show_success: Option<String>,
+    show_help: bool,
+}
+
+// Possible actions from the settings screen
+pub enum SettingsAction {
+    Back,
+    Save(Vec<(String, String)>),
+}
+
+impl SettingsState {
+    pub fn new(config: &Config) -> Self {
+        let settings = EDITABLE_SETTINGS
+            .iter()
+            .map(|key| (key.to_string(), config.setting_value(key)))
+            .collect();
+
+        let mut list_state = ListState::default();
+        list_state.select(Some(0));
+
+        Self {
+            settings,
+            list_state,
+            editing: None,
+            changed: false,
+            show_error: None,
+            show_success: None,
+            show_help: false,
+        }
+    }
+
+    pub fn next(&mut self) {
+        let i = self.list_state.selected().map_or(0, |i| (i + 1) % self.settings.len());
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        let i = self.list_state.selected().map_or(0, |i| (i + self.settings.len() - 1) % self.settings.len());
+        self.list_state.select(Some(i));
+    }
+
+    fn start_editing(&mut self) {
+        if let Some((_, value)) = self.list_state.selected().and_then(|i| self.settings.get(i)) {
+            self.editing = Some(value.clone());
+        }
+    }
+
+    fn commit_edit(&mut self) {
+        let (Some(value), Some(i)) = (self.editing.take(), self.list_state.selected()) else {
+            return;
+        };
+        if self.settings[i].1 != value {
+            self.settings[i].1 = value;
+            self.changed = true;
+        }
+    }
+
+    // Report the outcome of saving, keeping unsaved edits on failure
+    pub fn show_save_result(&mut self, result: Result<String, String>) {
+        match result {
+            Ok(message) => {
+                self.changed = false;
+                self.show_success = Some(message);
+            }
+            Err(error) => self.show_error = Some(error),
+        }
+    }
+}
+
+// Setting name as shown on screen, e.g. SMTP_SERVER -> Smtp Server
+fn setting_label(key: &str) -> String {
+    key.split('_')
+        .map(|word| {
+            let lower = word.to_lowercase();
+            let mut chars = lower.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn render_settings<B: Backend>(frame: &mut Frame<B>, state: &mut SettingsState) {
+    let size = frame.size();
+
+    // Create the layout
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(3),
+        ].as_ref())
+        .split(size);
+
+    let selected = state.list_state.selected();
+    let items: Vec<ListItem> = state
+        .settings
+        .iter()
+        .enumerate()
+        .map(|(i, (key, value))| {
+            let shown = match &state.editing {
+                Some(input) if selected == Some(i) && SECRET_SETTINGS.contains(&key.as_str()) => format!("{}|", "*".repeat(input.chars().count())),
+                Some(input) if selected == Some(i) => format!("{}|", input),
+                _ if SECRET_SETTINGS.contains(&key.as_str()) && !value.is_empty() => "********".to_string(),
+                _ if value.is_empty() => "Not set".to_string(),
+                _ => value.clone(),
+            };
+
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{}: ", setting_label(key)), Style::default().fg(Color::Yellow)),
+                Span::raw(shown),
+            ]))
+        })
+        .collect();
+
+    let title = if state.changed { "Settings (unsaved changes)" } else { "Settings" };
+    let settings_list = List::new(items)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_stateful_widget(settings_list, chunks[0], &mut state.list_state);
+
+    let buttons_text = if state.editing.is_some() {
+        "<Enter> Done | <Esc> Cancel editing"
+    } else {
+        "<Enter> Edit Setting | <S> Save to .env | <Esc> Back"
+    };
+
+    let buttons = Paragraph::new(footer_spans(buttons_text, chunks[1].width as usize))
+        .block(Block::default().borders(Borders::TOP))
+        .style(Style::default().fg(Color::White));
+
+    frame.render_widget(buttons, chunks[1]);
+
+    if let Some(error) = &state.show_error {
+        render_error(frame, "Error", error);
+    } else if let Some(message) = &state.show_success {
+        render_saved(frame, size, message);
+    }
+
+    if state.show_help {
+        render_help(frame, HELP_ENTRIES);
+    }
+}
+
+fn render_saved<B: Backend>(frame: &mut Frame<B>, size: Rect, message: &str) {
+    let popup_area = centered_rect(60, 20, size);
+
+    let message = Paragraph::new(vec![
+        Spans::from(""),
+        Spans::from(message),
+        Spans::from(""),
+        Spans::from("Press any key to continue"),
+    ])
+    .block(Block::default().title("Settings Saved").borders(Borders::ALL))
+    .style(Style::default().fg(Color::Green).bg(Color::Black))
+    .wrap(Wrap { trim: false });
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(message, popup_area);
+}
+
+pub fn handle_input(state: &mut SettingsState) -> Result<Option<SettingsAction>> {
+    if let Event::Key(key) = event::read()? {
+        if state.show_help {
+            if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                state.show_help = false;
+            }
+            return Ok(None);
+        }
+
+        // Any key dismisses a message popup
+        if state.show_error.take().is_some() || state.show_success.take().is_some() {
+            return Ok(None);
+        }
+
+        // Typing a new value for the selected setting
+        if let Some(input) = &mut state.editing {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => state.commit_edit(),
+                KeyCode::Esc => state.editing = None,
+                _ => {}
+            }
+            return Ok(None);
+        }
+
+        match key.code {
+            KeyCode::Char('?') => state.show_help = true,
+            KeyCode::Esc => return Ok(Some(SettingsAction::Back)),
+            KeyCode::Down => state.next(),
+            KeyCode::Up => state.previous(),
+            KeyCode::Enter => state.start_editing(),
+            KeyCode::Char('s') => return Ok(Some(SettingsAction::Save(state.settings.clone()))),
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,41 @@
+use tui::style::Color;
+
+// Color an invoice status is drawn in, so unpaid and problem invoices stand out.
+// Status is stored as free text, unknown values fall back to the default color.
+pub fn status_color(status: &str) -> Color {
+    match status.trim().to_lowercase().as_str() {
+        "draft" => Color::Gray,
+        "approved" => Color::Cyan,
+        "sent" => Color::Blue,
+        "paid" => Color::Green,
+        "overdue" | "cancelled" => Color::Red,
+        _ => Color::Reset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_status_has_its_color() {
+        let expected = [
+            ("Draft", Color::Gray),
+            ("Approved", Color::Cyan),
+            ("Sent", Color::Blue),
+            ("Paid", Color::Green),
+            ("Overdue", Color::Red),
+            ("Cancelled", Color::Red),
+        ];
+        for (status, color) in expected {
+            assert_eq!(status_color(status), color, "{}", status);
+        }
+    }
+
+    #[test]
+    fn status_matching_ignores_case_and_unknown_statuses_are_uncolored() {
+        assert_eq!(status_color(" paid "), Color::Green);
+        assert_eq!(status_color("DRAFT"), Color::Gray);
+        assert_eq!(status_color("Disputed"), Color::Reset);
+    }
+}
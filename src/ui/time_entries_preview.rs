@@ -0,0 +1,326 @@
+use crossterm::event::KeyCode;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Spans,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::models::{InvoiceLineItem, TimeEntry};
+
+// Unbilled time entries that will become a single line item
+#[derive(Debug, Clone)]
+pub struct TimeEntryGroup {
+    pub description: String,
+    pub hours: f64,
+    pub entries: Vec<TimeEntry>,
+}
+
+impl TimeEntryGroup {
+    pub fn entry_ids(&self) -> Vec<i32> {
+        self.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    // First and last day worked in the group
+    pub fn date_range(&self) -> Option<(chrono::NaiveDate, chrono::NaiveDate)> {
+        let first = self.entries.iter().map(|entry| entry.entry_date).min()?;
+        let last = self.entries.iter().map(|entry| entry.entry_date).max()?;
+        Some((first, last))
+    }
+}
+
+// Group entries by description, ignoring case and surrounding whitespace.
+// Groups keep the order their first entry appears in.
+pub fn group_time_entries(entries: &[TimeEntry]) -> Vec<TimeEntryGroup> {
+    let mut groups: Vec<TimeEntryGroup> = Vec::new();
+
+    for entry in entries {
+        let key = entry.description.trim().to_lowercase();
+        match groups.iter_mut().find(|group| group.description.trim().to_lowercase() == key) {
+            Some(group) => {
+                group.hours += entry.hours;
+                group.entries.push(entry.clone());
+            }
+            None => groups.push(TimeEntryGroup {
+                description: entry.description.trim().to_string(),
+                hours: entry.hours,
+                entries: vec![entry.clone()],
+            }),
+        }
+    }
+
+    groups
+}
+
+// Combine two groups, keeping both descriptions when they differ
+pub fn merge_groups(first: TimeEntryGroup, second: TimeEntryGroup) -> TimeEntryGroup {
+    let description = if first.description.eq_ignore_ascii_case(&second.description) {
+        first.description
+    } else {
+        format!("{}; {}", first.description, second.description)
+    };
+
+    let mut entries = first.entries;
+    entries.extend(second.entries);
+
+    TimeEntryGroup {
+        description,
+        hours: first.hours + second.hours,
+        entries,
+    }
+}
+
+// Break a group back into one group per entry
+pub fn split_group(group: TimeEntryGroup) -> Vec<TimeEntryGroup> {
+    group.entries
+        .into_iter()
+        .map(|entry| TimeEntryGroup {
+            description: entry.description.trim().to_string(),
+            hours: entry.hours,
+            entries: vec![entry],
+        })
+        .collect()
+}
+
+// Represents the time entries preview shown before creating an invoice from them
+pub struct TimeEntriesPreviewState {
+    groups: Vec<TimeEntryGroup>,
+    list_state: ListState,
+    hours_step: f64,
+}
+
+// Possible actions from the time entries preview
+pub enum TimeEntriesPreviewAction {
+    Cancel,
+    CreateInvoice(Vec<TimeEntryGroup>),
+}
+
+impl TimeEntriesPreviewState {
+    pub fn new(entries: &[TimeEntry], hours_step: f64) -> Self {
+        let groups = group_time_entries(entries);
+        let mut list_state = ListState::default();
+        if !groups.is_empty() {
+            list_state.select(Some(0));
+        }
+
+        Self {
+            groups,
+            list_state,
+            hours_step,
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.groups.is_empty() {
+            return;
+        }
+        let i = self.list_state.selected().map_or(0, |i| (i + 1) % self.groups.len());
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.groups.is_empty() {
+            return;
+        }
+        let i = self.list_state.selected().map_or(0, |i| (i + self.groups.len() - 1) % self.groups.len());
+        self.list_state.select(Some(i));
+    }
+
+    // Merge the selected group with the one below it
+    pub fn merge_selected(&mut self) {
+        if let Some(i) = self.list_state.selected().filter(|i| i + 1 < self.groups.len()) {
+            let second = self.groups.remove(i + 1);
+            let first = self.groups.remove(i);
+            self.groups.insert(i, merge_groups(first, second));
+        }
+    }
+
+    pub fn split_selected(&mut self) {
+        if let Some(i) = self.list_state.selected().filter(|i| *i < self.groups.len()) {
+            let group = self.groups.remove(i);
+            for (offset, split) in split_group(group).into_iter().enumerate() {
+                self.groups.insert(i + offset, split);
+            }
+        }
+    }
+
+    pub fn adjust_selected_hours(&mut self, delta: f64) {
+        if let Some(group) = self.list_state.selected().and_then(|i| self.groups.get_mut(i)) {
+            group.hours = (group.hours + delta).max(0.0);
+        }
+    }
+
+    pub fn total_hours(&self) -> f64 {
+        self.groups.iter().map(|group| group.hours).sum()
+    }
+}
+
+// Turn the groups into line items for a new invoice
+pub fn groups_to_line_items(groups: &[TimeEntryGroup]) -> Vec<InvoiceLineItem> {
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| InvoiceLineItem {
+            id: i as i32 + 1,
+            invoice_id: 0, // Will be updated when invoice is saved
+            description: group.description.clone(),
+            hours: group.hours,
+            amount: 0.0,
+            rate_override: None,
+            item_type: "Hourly".to_string(),
+        })
+        .collect()
+}
+
+pub fn render_time_entries_preview<B: Backend>(frame: &mut Frame<B>, size: Rect, state: &mut TimeEntriesPreviewState) {
+    let popup_area = centered_rect(70, 60, size);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(3),
+        ].as_ref())
+        .split(popup_area);
+
+    let items: Vec<ListItem> = if state.groups.is_empty() {
+        vec![ListItem::new("No unbilled time entries for this project")]
+    } else {
+        state.groups
+            .iter()
+            .map(|group| {
+                let dates = match group.date_range() {
+                    Some((first, last)) if first != last => format!(", {} to {}", first.format("%Y-%m-%d"), last.format("%Y-%m-%d")),
+                    Some((first, _)) => format!(", {}", first.format("%Y-%m-%d")),
+                    None => String::new(),
+                };
+                ListItem::new(Spans::from(format!(
+                    "{}: {} hours ({} entr{}{})",
+                    group.description,
+                    group.hours,
+                    group.entries.len(),
+                    if group.entries.len() == 1 { "y" } else { "ies" },
+                    dates,
+                )))
+            })
+            .collect()
+    };
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Unbilled Time - {} hours", state.total_hours()))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::White).bg(Color::Black))
+        .highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let help = Paragraph::new("<M> Merge with next | <S> Split | <+/-> Adjust hours | <Enter> Create invoice | <Esc> Cancel")
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::White).bg(Color::Black));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_stateful_widget(list, chunks[0], &mut state.list_state);
+    frame.render_widget(help, chunks[1]);
+}
+
+// Helper function to create a centered rect
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+pub fn handle_input(state: &mut TimeEntriesPreviewState, key: KeyCode) -> Option<TimeEntriesPreviewAction> {
+    match key {
+        KeyCode::Esc => return Some(TimeEntriesPreviewAction::Cancel),
+        KeyCode::Down => state.next(),
+        KeyCode::Up => state.previous(),
+        KeyCode::Char('m') => state.merge_selected(),
+        KeyCode::Char('s') => state.split_selected(),
+        KeyCode::Char('+') => state.adjust_selected_hours(state.hours_step),
+        KeyCode::Char('-') => state.adjust_selected_hours(-state.hours_step),
+        KeyCode::Enter if !state.groups.is_empty() => {
+            return Some(TimeEntriesPreviewAction::CreateInvoice(state.groups.clone()));
+        }
+        _ => {}
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::date;
+
+    fn entry(id: i32, day: u32, description: &str, hours: f64) -> TimeEntry {
+        TimeEntry {
+            id,
+            entry_date: date(2024, 3, day),
+            description: description.to_string(),
+            hours,
+        }
+    }
+
+    #[test]
+    fn entries_are_grouped_by_description_ignoring_case_and_whitespace() {
+        let groups = group_time_entries(&[
+            entry(1, 1, "Design", 2.0),
+            entry(2, 2, "Meetings", 1.0),
+            entry(3, 3, " design ", 1.5),
+        ]);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].description, "Design");
+        assert_eq!(groups[0].hours, 3.5);
+        assert_eq!(groups[0].entry_ids(), [1, 3]);
+        assert_eq!(groups[0].date_range(), Some((date(2024, 3, 1), date(2024, 3, 3))));
+        assert_eq!(groups[1].description, "Meetings");
+    }
+
+    #[test]
+    fn merging_keeps_both_descriptions_and_splitting_undoes_it() {
+        let mut groups = group_time_entries(&[entry(1, 1, "Design", 2.0), entry(2, 2, "Meetings", 1.0)]);
+        let merged = merge_groups(groups.remove(0), groups.remove(0));
+        assert_eq!(merged.description, "Design; Meetings");
+        assert_eq!(merged.hours, 3.0);
+
+        let split = split_group(merged);
+        assert_eq!(split.iter().map(|group| group.description.as_str()).collect::<Vec<_>>(), ["Design", "Meetings"]);
+    }
+
+    #[test]
+    fn edited_groups_become_numbered_hourly_line_items() {
+        let mut state = TimeEntriesPreviewState::new(&[entry(1, 1, "Design", 2.0), entry(2, 2, "Meetings", 1.0)], 0.5);
+        state.adjust_selected_hours(0.5);
+        state.next();
+        state.adjust_selected_hours(-2.0);
+        assert_eq!(state.total_hours(), 2.5);
+
+        let items = groups_to_line_items(&state.groups);
+        assert_eq!(items.iter().map(|item| (item.id, item.hours)).collect::<Vec<_>>(), [(1, 2.5), (2, 0.0)]);
+        assert!(items.iter().all(|item| !item.is_fixed()));
+    }
+}